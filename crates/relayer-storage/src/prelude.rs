@@ -12,6 +12,9 @@ use crate::{
 pub trait StorageReader<S: EthSpec>: Send + Sync + Sized {
     fn get_base_beacon_header_slot(&self) -> Result<Option<Slot>>;
     fn get_tip_beacon_header_slot(&self) -> Result<Option<Slot>>;
+    fn get_verified_beacon_header_slot(&self) -> Result<Option<Slot>>;
+    fn get_verified_correlation_id(&self) -> Result<Option<String>>;
+    fn get_announced_sync_committee_slot(&self) -> Result<Option<Slot>>;
 
     fn get_beacon_header_digest(&self, position: u64) -> Result<Option<packed::HeaderDigest>>;
 }
@@ -19,11 +22,18 @@ pub trait StorageReader<S: EthSpec>: Send + Sync + Sized {
 pub trait StorageWriter<S: EthSpec>: Send + Sync + Sized {
     fn put_base_beacon_header_slot(&self, slot: Slot) -> Result<()>;
     fn put_tip_beacon_header_slot(&self, slot: Slot) -> Result<()>;
+    fn put_verified_beacon_header_slot(&self, slot: Slot) -> Result<()>;
+    fn put_verified_correlation_id(&self, correlation_id: &str) -> Result<()>;
+    fn put_announced_sync_committee_slot(&self, slot: Slot) -> Result<()>;
 
     fn delete_base_beacon_header_slot(&self) -> Result<()>;
     fn delete_tip_beacon_header_slot(&self) -> Result<()>;
+    fn delete_verified_beacon_header_slot(&self) -> Result<()>;
+    fn delete_verified_correlation_id(&self) -> Result<()>;
+    fn delete_announced_sync_committee_slot(&self) -> Result<()>;
 
     fn put_beacon_header_digest(&self, position: u64, digest: &packed::HeaderDigest) -> Result<()>;
+    fn delete_beacon_header_digest(&self, position: u64) -> Result<()>;
 }
 
 pub trait StorageAsMMRStore<S: EthSpec>:
@@ -35,16 +45,71 @@ pub trait StorageAsMMRStore<S: EthSpec>:
             .map(|inner| inner.is_some())
     }
 
+    /// Rolls back the tip to `slot_opt`, or clears the MMR if it's `None`. The verified slot
+    /// is lowered with the tip, since the headers above it are gone, and so is the announced
+    /// slot of the sync committee, which is forgotten since the previous one isn't kept. The
+    /// correlation id of a lowered verified slot is forgotten as well.
     fn rollback_to(&self, slot_opt: Option<Slot>) -> Result<()> {
         if let Some(slot) = slot_opt {
             self.put_tip_beacon_header_slot(slot)?;
+            if matches!(self.get_verified_beacon_header_slot()?, Some(verified) if verified > slot)
+            {
+                self.put_verified_beacon_header_slot(slot)?;
+                self.delete_verified_correlation_id()?;
+            }
+            let announced_opt = self.get_announced_sync_committee_slot()?;
+            if matches!(announced_opt, Some(announced) if announced > slot) {
+                self.delete_announced_sync_committee_slot()?;
+            }
         } else {
             self.delete_base_beacon_header_slot()?;
             self.delete_tip_beacon_header_slot()?;
+            self.delete_verified_beacon_header_slot()?;
+            self.delete_verified_correlation_id()?;
+            self.delete_announced_sync_committee_slot()?;
         }
         Ok(())
     }
 
+    /// Advances the verified slot to `slot`, below which the headers are verified and committed
+    /// on chain, so they are not verified again. It never moves backward, except by a rollback.
+    fn advance_verified_to(&self, slot: Slot) -> Result<()> {
+        match self.get_verified_beacon_header_slot()? {
+            Some(verified) if verified >= slot => Ok(()),
+            _ => self.put_verified_beacon_header_slot(slot),
+        }
+    }
+
+    /// Same as `advance_verified_to`, and records the correlation id of the update which
+    /// commits `slot`, or forgets the previous one if it's `None`.
+    fn advance_verified_with(&self, slot: Slot, correlation_id: Option<&str>) -> Result<()> {
+        self.advance_verified_to(slot)?;
+        match correlation_id {
+            Some(correlation_id) => self.put_verified_correlation_id(correlation_id),
+            None => self.delete_verified_correlation_id(),
+        }
+    }
+
+    /// Deletes the digests beyond the MMR of `tip`, which are left by the rollbacks, and returns
+    /// how many are deleted. The digests of the MMR are written in order, so the stale ones are
+    /// contiguous from the size of the MMR.
+    fn prune_after(&self, tip: Slot) -> Result<u64> {
+        let Some(base) = self.get_base_beacon_header_slot()? else {
+            return Ok(0);
+        };
+        if tip < base {
+            return Err(Error::data(format!("tip {tip} is below the base {base}")));
+        }
+        let mut position = mmr::lib::leaf_index_to_mmr_size(tip - base);
+        let mut pruned = 0;
+        while self.get_beacon_header_digest(position)?.is_some() {
+            self.delete_beacon_header_digest(position)?;
+            position += 1;
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+
     fn initialize_with(&self, slot: Slot, digest: packed::HeaderDigest) -> Result<()> {
         self.put_base_beacon_header_slot(slot)?;
         let mut mmr = ClientRootMMR::new(0, self.clone());
@@ -7,3 +7,9 @@ pub const MIGRATION_VERSION_KEY: &[u8] = b"db-version";
 pub const BASE_BEACON_HEADER_SLOT: &[u8] = b"base-beacon-header-slot";
 /// The current tip beacon header.
 pub const TIP_BEACON_HEADER_SLOT: &[u8] = b"tip-beacon-header-slot";
+/// The last beacon header which is verified and committed on chain.
+pub const VERIFIED_BEACON_HEADER_SLOT: &[u8] = b"verified-beacon-header-slot";
+/// The correlation id of the update which commits the verified beacon header.
+pub const VERIFIED_CORRELATION_ID: &[u8] = b"verified-correlation-id";
+/// The last beacon header whose update announces the next sync committee.
+pub const ANNOUNCED_SYNC_COMMITTEE_SLOT: &[u8] = b"announced-sync-committee-slot";
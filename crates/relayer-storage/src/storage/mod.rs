@@ -2,10 +2,10 @@ use std::{marker::PhantomData, path::Path, sync::Arc};
 
 use rocksdb::{
     prelude::{
-        Delete as _, GetColumnFamilys as _, GetPinned as _, GetPinnedCF as _, OpenCF as _,
-        Put as _, PutCF as _,
+        CompactRangeCF as _, Delete as _, DeleteCF as _, FlushCF as _, GetColumnFamilys as _,
+        GetPinned as _, GetPinnedCF as _, GetPropertyCF as _, OpenCF as _, Put as _, PutCF as _,
     },
-    ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, Options, DB,
+    ColumnFamily, ColumnFamilyDescriptor, DBCompressionType, DBPinnableSlice, Options, DB,
 };
 
 use crate::{
@@ -29,6 +29,14 @@ pub struct Storage<S> {
 
 impl<S> Storage<S> {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new_with_compression(path, false)
+    }
+
+    /// Opens the storage, the data written afterwards is compressed by zstd if `compress` is set.
+    ///
+    /// The compression is done by the database per block, so the data is decompressed
+    /// transparently on reading, and a storage could be reopened with a different setting.
+    pub fn new_with_compression<P: AsRef<Path>>(path: P, compress: bool) -> Result<Self> {
         let cf_names = {
             let mut cf_names = Vec::with_capacity(columns::COUNT);
             cf_names.push(columns::COLUMN_BEACON_HEADER_MMR.to_string());
@@ -36,11 +44,11 @@ impl<S> Storage<S> {
         };
         let cf_descriptors: Vec<_> = cf_names
             .iter()
-            .map(|c| ColumnFamilyDescriptor::new(c, Options::default()))
+            .map(|c| ColumnFamilyDescriptor::new(c, compression_options(compress)))
             .collect();
 
         let opts = {
-            let mut opts = Options::default();
+            let mut opts = compression_options(compress);
             opts.create_if_missing(true);
             opts.create_missing_column_families(true);
             opts
@@ -57,6 +65,39 @@ impl<S> Storage<S> {
         Ok(storage)
     }
 
+    /// Compacts all columns, so the space of the deleted data is reclaimed from the disk.
+    pub fn compact(&self) -> Result<()> {
+        for col in all_columns() {
+            let cf = cf_handle(&self.db, col)?;
+            self.db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+        }
+        Ok(())
+    }
+
+    /// Flushes the buffered writes of all columns to the disk, e.g. before shutdown.
+    pub fn flush(&self) -> Result<()> {
+        for col in all_columns() {
+            let cf = cf_handle(&self.db, col)?;
+            self.db.flush_cf(cf)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the approximate size in bytes of all columns, both on the disk and in memory.
+    pub fn approximate_size(&self) -> Result<u64> {
+        let mut size = 0;
+        for col in all_columns() {
+            let cf = cf_handle(&self.db, col)?;
+            for name in [
+                "rocksdb.total-sst-files-size",
+                "rocksdb.cur-size-all-mem-tables",
+            ] {
+                size += self.db.property_int_value_cf(cf, name)?.unwrap_or(0);
+            }
+        }
+        Ok(size)
+    }
+
     pub(crate) fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<DBPinnableSlice>> {
         self.db.get_pinned(key.as_ref()).map_err(Into::into)
     }
@@ -80,6 +121,11 @@ impl<S> Storage<S> {
         self.db.get_pinned_cf(cf, key.as_ref()).map_err(Into::into)
     }
 
+    pub(crate) fn delete_cf<K: AsRef<[u8]>>(&self, col: Column, key: K) -> Result<()> {
+        let cf = cf_handle(&self.db, col)?;
+        self.db.delete_cf(cf, key.as_ref()).map_err(Into::into)
+    }
+
     pub(crate) fn put_cf<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         &self,
         col: Column,
@@ -93,6 +139,19 @@ impl<S> Storage<S> {
     }
 }
 
+fn compression_options(compress: bool) -> Options {
+    let mut opts = Options::default();
+    if compress {
+        opts.set_compression_type(DBCompressionType::Zstd);
+    }
+    opts
+}
+
+// The default column is where the special values are put.
+fn all_columns() -> [Column; columns::COUNT + 1] {
+    ["default", columns::COLUMN_BEACON_HEADER_MMR]
+}
+
 pub(crate) fn cf_handle(db: &DB, col: Column) -> Result<&ColumnFamily> {
     db.cf_handle(col)
         .ok_or_else(|| Error::storage(format!("column {} not found", col)))
@@ -43,6 +43,26 @@ where
             .map_err(Into::into)
     }
 
+    fn get_verified_beacon_header_slot(&self) -> Result<Option<Slot>> {
+        self.get(keys::VERIFIED_BEACON_HEADER_SLOT)?
+            .map(|raw| packed::Uint64Reader::from_slice(&raw).map(|reader| reader.unpack()))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    fn get_verified_correlation_id(&self) -> Result<Option<String>> {
+        self.get(keys::VERIFIED_CORRELATION_ID)?
+            .map(|raw| String::from_utf8(raw.to_vec()).map_err(Error::data))
+            .transpose()
+    }
+
+    fn get_announced_sync_committee_slot(&self) -> Result<Option<Slot>> {
+        self.get(keys::ANNOUNCED_SYNC_COMMITTEE_SLOT)?
+            .map(|raw| packed::Uint64Reader::from_slice(&raw).map(|reader| reader.unpack()))
+            .transpose()
+            .map_err(Into::into)
+    }
+
     fn get_beacon_header_digest(&self, position: u64) -> Result<Option<packed::HeaderDigest>> {
         let key: packed::Uint64 = position.pack();
         self.get_cf(columns::COLUMN_BEACON_HEADER_MMR, key.as_slice())?
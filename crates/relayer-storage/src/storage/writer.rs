@@ -29,6 +29,20 @@ where
         self.put(keys::TIP_BEACON_HEADER_SLOT, value.as_slice())
     }
 
+    fn put_verified_beacon_header_slot(&self, slot: Slot) -> Result<()> {
+        let value = slot.pack();
+        self.put(keys::VERIFIED_BEACON_HEADER_SLOT, value.as_slice())
+    }
+
+    fn put_verified_correlation_id(&self, correlation_id: &str) -> Result<()> {
+        self.put(keys::VERIFIED_CORRELATION_ID, correlation_id)
+    }
+
+    fn put_announced_sync_committee_slot(&self, slot: Slot) -> Result<()> {
+        let value = slot.pack();
+        self.put(keys::ANNOUNCED_SYNC_COMMITTEE_SLOT, value.as_slice())
+    }
+
     fn delete_base_beacon_header_slot(&self) -> Result<()> {
         let mut writer = self
             .cache
@@ -44,6 +58,18 @@ where
         self.delete(keys::TIP_BEACON_HEADER_SLOT)
     }
 
+    fn delete_verified_beacon_header_slot(&self) -> Result<()> {
+        self.delete(keys::VERIFIED_BEACON_HEADER_SLOT)
+    }
+
+    fn delete_verified_correlation_id(&self) -> Result<()> {
+        self.delete(keys::VERIFIED_CORRELATION_ID)
+    }
+
+    fn delete_announced_sync_committee_slot(&self) -> Result<()> {
+        self.delete(keys::ANNOUNCED_SYNC_COMMITTEE_SLOT)
+    }
+
     fn put_beacon_header_digest(&self, position: u64, digest: &packed::HeaderDigest) -> Result<()> {
         let key: packed::Uint64 = position.pack();
         self.put_cf(
@@ -52,4 +78,9 @@ where
             digest.as_slice(),
         )
     }
+
+    fn delete_beacon_header_digest(&self, position: u64) -> Result<()> {
+        let key: packed::Uint64 = position.pack();
+        self.delete_cf(columns::COLUMN_BEACON_HEADER_MMR, key.as_slice())
+    }
 }
@@ -6,6 +6,7 @@ use crate::{
     prelude::*,
 };
 use core::convert::TryFrom;
+use core::time::Duration;
 use ibc_proto::google::protobuf::Any;
 use serde::{Deserialize, Serialize};
 
@@ -17,12 +18,23 @@ use ibc_proto::protobuf::Protobuf;
 
 pub const CLIENT_STATE_TYPE_URL: &str = "/eth.client.v1.state";
 
+// 12 seconds per slot, 8192 slots per sync committee period
+const SYNC_COMMITTEE_PERIOD: Duration = Duration::from_secs(12 * 8192);
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ClientState {
     pub chain_id: ChainId,
     pub lightclient_update: Update,
 }
 
+impl ClientState {
+    /// Returns the maximal duration between two updates of the client, it's 2/3 of a sync
+    /// committee period, so the client is updated at least once per period.
+    pub fn refresh_time(&self) -> Option<Duration> {
+        Some(2 * SYNC_COMMITTEE_PERIOD / 3)
+    }
+}
+
 impl Ics02ClientState for ClientState {
     fn chain_id(&self) -> ChainId {
         self.chain_id.clone()
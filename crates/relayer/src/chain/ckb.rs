@@ -1,13 +1,15 @@
 use ckb_jsonrpc_types::{OutputsValidator, TransactionView as JsonTx};
 use ckb_sdk::{Address, AddressPayload, NetworkType};
-use ckb_types::core::TransactionView;
-use ckb_types::packed::CellOutput;
+use ckb_types::bytes::Bytes;
+use ckb_types::core::{Capacity, TransactionView};
+use ckb_types::packed::{CellOutput, OutPoint, Script};
 use ckb_types::prelude::*;
+use ckb_types::H256;
 use eth2_types::MainnetEthSpec;
 use eth_light_client_in_ckb_verification::types::{
     packed::Client as PackedClient, packed::ClientInfo as PackedClientInfo,
-    packed::ClientTypeArgs as PackedClientTypeArgs, packed::Hash as PackedHash,
-    packed::ProofUpdate as PackedProofUpdate, prelude::Unpack,
+    packed::ClientTypeArgs as PackedClientTypeArgs, packed::ProofUpdate as PackedProofUpdate,
+    prelude::Unpack,
 };
 use ibc_proto::ibc::apps::fee::v1::{
     QueryIncentivizedPacketRequest, QueryIncentivizedPacketResponse,
@@ -21,7 +23,9 @@ use ibc_relayer_types::clients::ics07_ckb::{
     light_block::LightBlock as CkbLightBlock,
 };
 use ibc_relayer_types::clients::ics07_eth::{
-    client_state::ClientState as EthClientState, types::Update as EthUpdate,
+    client_state::ClientState as EthClientState,
+    consensus_state::ConsensusState as EthConsensusState,
+    types::{Update as EthUpdate, H256 as EthH256},
 };
 use ibc_relayer_types::{
     core::{
@@ -31,20 +35,29 @@ use ibc_relayer_types::{
             channel::{ChannelEnd, IdentifiedChannelEnd},
             packet::Sequence,
         },
-        ics23_commitment::{commitment::CommitmentPrefix, merkle::MerkleProof},
-        ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+        ics23_commitment::{
+            commitment::{CommitmentPrefix, CommitmentProofBytes},
+            merkle::MerkleProof,
+        },
+        ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
     },
     signer::Signer,
     Height as ICSHeight,
 };
+use secp256k1::PublicKey;
 use semver::Version;
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tendermint_light_client::errors::Error as LightClientError;
 use tendermint_rpc::endpoint::broadcast::tx_sync::Response;
 use tokio::runtime::Runtime as TokioRuntime;
+use tree_hash::TreeHash as _;
 
-#[cfg(test)]
 use crate::keyring::Store;
 
 use crate::{
@@ -52,18 +65,20 @@ use crate::{
     chain::cosmos::encode::key_pair_to_signer,
     chain::endpoint::{ChainEndpoint, ChainStatus, HealthCheck},
     client_state::{AnyClientState, IdentifiedAnyClientState},
-    config::ckb::ChainConfig as CkbChainConfig,
+    config::ckb::{ChainConfig as CkbChainConfig, ClientTypeArgs},
     config::ChainConfig,
     // config::GLOBAL_CONFIG_PATH,
     consensus_state::AnyConsensusState,
     denom::DenomTrace,
     error::Error,
-    event::IbcEventWithHeight,
+    event::{monitor::EventBatch, IbcEventWithHeight},
     keyring::{KeyRing, Secp256k1KeyPair},
     misbehaviour::MisbehaviourEvidence,
 };
 
-use super::requests::{CrossChainQueryRequest, QueryConsensusStateHeightsRequest};
+use super::requests::{
+    CrossChainQueryRequest, QueryConsensusStateHeightsRequest, QueryTxHash, QueryTxRequest,
+};
 use super::tracking::{NonCosmosTrackingId as NonCosmos, TrackedMsgs, TrackingId};
 use super::{
     client::ClientSettings,
@@ -79,10 +94,16 @@ use super::{
 };
 
 mod assembler;
+pub mod beacon_client;
 mod communication;
 mod helper;
 pub mod sighash;
 mod signer;
+#[cfg(feature = "ckb-status-server")]
+pub mod status_server;
+mod subscription;
+pub mod tip_watcher;
+pub mod update_worker;
 pub mod utils;
 
 #[cfg(test)]
@@ -97,21 +118,32 @@ mod tests;
 
 pub mod prelude {
     pub use super::{
-        assembler::{TxAssembler, UpdateCells},
-        communication::{CkbReader, CkbWriter, Response},
+        assembler::{FeeOptions, TxAssembler, UpdateCells},
+        communication::{
+            CkbReader, CkbWriter, FeeRateStatistics, RequestLimiter, RequestTimeouts, Response,
+        },
         helper::{CellSearcher, TxCompleter},
     };
 }
 
 use assembler::TxAssembler;
+use beacon_client::BeaconClient;
 
-use prelude::{CkbReader as _, CkbWriter as _, UpdateCells};
+use prelude::{CellSearcher as _, CkbReader as _, CkbWriter as _, FeeOptions, UpdateCells};
 
 use rpc_client::RpcClient;
 
 // Ref: https://github.com/satoshilabs/slips/pull/621
 pub const HD_PATH: &str = "m/44'/309'/0'/0/0";
 
+const INSTANCE_LOCK_FILE: &str = "relayer.lock";
+
+// The self-test creates a client with its own storage in this directory, which is removed
+// afterwards.
+const SELF_TEST_STORAGE_DIR: &str = "self-test";
+const SELF_TEST_START_SLOT: Slot = 1;
+const SELF_TEST_MIN_UPDATES_COUNT: u64 = 4;
+
 pub struct CkbChain {
     pub rt: Arc<TokioRuntime>,
     pub rpc_client: Arc<RpcClient>,
@@ -123,30 +155,838 @@ pub struct CkbChain {
     pub cached_network: RwLock<Option<NetworkType>>,
     pub cached_tx_assembler_address: RwLock<Option<Address>>,
     pub cached_onchain_packed_client: Option<PackedClient>,
+
+    pub keyed_clients: HashMap<String, KeyedClient>,
+
+    // Reused by the next proof of the default client, so it doesn't read the whole MMR again.
+    proof_state: RwLock<Option<utils::ProofState>>,
+
+    // The block which the last sent transaction is committed in, the next assembly must
+    // wait until the indexer has passed it to avoid reading stale cells.
+    last_committed_block: Option<u64>,
+
+    // The effective fee rate of the last sent transaction, see `last_fee_rate`.
+    last_fee_rate: Option<u64>,
+
+    // The hash and file of the transaction which is exported by the `export_signed_tx_dir` mode
+    // but not broadcast yet, no other update is assembled until it's broadcast or the file is
+    // removed, since they would spend the same cells.
+    exported_tx: Option<(H256, PathBuf)>,
+
+    // The recent committed update cycles of the default client, see `estimate_sync_eta`.
+    recent_update_cycles: VecDeque<utils::UpdateCycle>,
+
+    // The events of the recent committed updates by their transaction hashes, for `query_txs`.
+    recent_committed_events: VecDeque<(H256, IbcEventWithHeight)>,
+
+    // The correlation id of the messages which are being sent, i.e. their tracking id, which
+    // tags the events of the updates.
+    correlation_id: Option<String>,
+
+    beacon_client: Option<BeaconClient>,
+
+    // Held since the bootstrap until shutdown, so another instance with the same storage
+    // refuses to start.
+    instance_lock_path: PathBuf,
+    instance_lock: Option<utils::InstanceLock>,
+
+    // Skip sending messages while it's set, e.g. during the maintenance of the CKB node.
+    paused: AtomicBool,
+
+    // Set by a shutdown request to abort the in-progress update cycle, see `cancellation_token`.
+    cancelled: Arc<AtomicBool>,
+
+    // Serves the status if `status_server_addr` is configured, it's stopped with the chain.
+    #[cfg(feature = "ckb-status-server")]
+    status_server: Option<status_server::StatusServer>,
+
+    // Broadcasts the events of the committed transactions to the subscribers, and the changes
+    // of the on-chain client which are found by the tip watcher.
+    event_stream: Arc<Mutex<subscription::EventStream>>,
+
+    // Checks the on-chain client at the new tips pushed by `ckb_ws_rpc` or polled without it,
+    // it's spawned by the first subscription.
+    tip_watcher: Option<tip_watcher::TipWatcher>,
+    watched_client: tip_watcher::WatchedClient,
+}
+
+/// The native storage and cached on-chain client of a multi-client deployment which is
+/// selected by a client key, see `keyed_client_type_args` in the config.
+pub struct KeyedClient {
+    pub storage: Storage<MainnetEthSpec>,
+    pub cached_onchain_packed_client: Option<PackedClient>,
+    proof_state: RwLock<Option<utils::ProofState>>,
+}
+
+impl KeyedClient {
+    fn view(&self) -> ClientView<'_> {
+        ClientView {
+            storage: &self.storage,
+            cached_onchain_packed_client: self.cached_onchain_packed_client.as_ref(),
+            proof_state: &self.proof_state,
+        }
+    }
+}
+
+// The native states of a multi-client which a new client and its proof are built from, they are
+// borrowed from the default client, a keyed client or the client of the self-test.
+#[derive(Clone, Copy)]
+struct ClientView<'a> {
+    storage: &'a Storage<MainnetEthSpec>,
+    cached_onchain_packed_client: Option<&'a PackedClient>,
+    proof_state: &'a RwLock<Option<utils::ProofState>>,
+}
+
+/// The operations of [`ChainEndpoint`] which are implemented by a chain, the others panic or
+/// return an unsupported error, so they should be skipped by the callers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainCapabilities {
+    pub health_check: bool,
+    pub send_messages_and_wait_commit: bool,
+    pub send_messages_and_wait_check_tx: bool,
+    pub query_application_status: bool,
+    pub query_clients: bool,
+    pub query_client_state: bool,
+    pub query_consensus_state: bool,
+    pub query_consensus_state_heights: bool,
+    // Both the upgraded client state and the upgraded consensus state
+    pub query_upgraded_states: bool,
+    pub query_connections: bool,
+    pub query_channels: bool,
+    pub query_packet_commitments: bool,
+    pub query_txs: bool,
+    pub build_header: bool,
+    pub subscribe: bool,
+}
+
+/// The multi-client which is created from synthetic updates and queried back by a self-test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub type_id: H256,
+    pub minimal_slot: Slot,
+    pub maximal_slot: Slot,
+    pub block_number: Option<u64>,
+}
+
+/// Approximate sizes in bytes of the native storages around a compaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    // Digests of the MMR which are left by the rollbacks
+    pub pruned_digests: u64,
+}
+
+/// The headers in the native storage of the default client, and its approximate size in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    pub headers_count: u64,
+    // The base and tip slots of the headers, `None` if the storage is empty
+    pub slot_range: Option<(Slot, Slot)>,
+    pub bytes: u64,
 }
 
 impl CkbChain {
+    fn client_type_args(&self, client_key: Option<&str>) -> Result<&ClientTypeArgs, Error> {
+        self.config
+            .select_client_type_args(client_key)
+            .ok_or_else(|| Error::ckb_unknown_client_key(client_key.unwrap_or_default().to_owned()))
+    }
+
+    /// Returns the packed type args of the multi-client of `client_key`, or `None` if it hasn't
+    /// been created yet.
+    fn packed_client_type_args(
+        &self,
+        client_key: Option<&str>,
+    ) -> Result<Option<PackedClientTypeArgs>, Error> {
+        let client_type_args = self.client_type_args(client_key)?;
+        Ok(client_type_args
+            .type_id
+            .as_ref()
+            .map(|type_id| utils::pack_client_type_args(type_id, client_type_args.cells_count)))
+    }
+
+    /// Same as `packed_client_type_args`, except that the multi-client must have been created.
+    fn created_client_type_args(
+        &self,
+        client_key: Option<&str>,
+    ) -> Result<PackedClientTypeArgs, Error> {
+        // TODO: better error
+        self.packed_client_type_args(client_key)?
+            .ok_or_else(|| Error::other_error("no type id in client type args".to_owned()))
+    }
+
+    /// Fetches the update cells of the multi-client of `client_key`, returns `None` if it hasn't
+    /// been created yet or its cells are not found.
+    async fn fetch_update_cells_of(
+        &self,
+        client_key: Option<&str>,
+    ) -> Result<Option<UpdateCells>, Error> {
+        let Some(client_type_args) = self.packed_client_type_args(client_key)? else {
+            return Ok(None);
+        };
+        self.rpc_client
+            .fetch_update_cells(
+                &self.config.lightclient_contract_typeargs,
+                &client_type_args,
+            )
+            .await
+    }
+
+    /// Returns the client key of `client_id`, the clients are numbered as `query_clients` does.
+    fn client_key_of(&self, client_id: &ClientId) -> Result<Option<&str>, Error> {
+        if *client_id == ClientId::default() {
+            return Ok(None);
+        }
+        self.config
+            .keyed_client_type_args
+            .iter()
+            .find(|(_, args)| args.client_id.as_ref() == Some(client_id))
+            .map(|(key, _)| Some(key.as_str()))
+            .ok_or_else(|| Error::ckb_unknown_client_key(client_id.to_string()))
+    }
+
+    /// Returns the client id of `client_key`, the inverse of `client_key_of`.
+    fn client_id_of(&self, client_key: Option<&str>) -> Result<ClientId, Error> {
+        let Some(client_key) = client_key else {
+            return Ok(ClientId::default());
+        };
+        self.config
+            .keyed_client_type_args
+            .get(client_key)
+            .and_then(|args| args.client_id.clone())
+            .ok_or_else(|| Error::ckb_unknown_client_key(client_key.to_owned()))
+    }
+
+    fn client_storage(&self, client_key: Option<&str>) -> Result<&Storage<MainnetEthSpec>, Error> {
+        match client_key {
+            Some(key) => self
+                .keyed_clients
+                .get(key)
+                .map(|keyed| &keyed.storage)
+                .ok_or_else(|| Error::ckb_unknown_client_key(key.to_owned())),
+            None => Ok(&self.storage),
+        }
+    }
+
+    fn client_view(&self, client_key: Option<&str>) -> Result<ClientView<'_>, Error> {
+        match client_key {
+            Some(key) => self
+                .keyed_clients
+                .get(key)
+                .map(KeyedClient::view)
+                .ok_or_else(|| Error::ckb_unknown_client_key(key.to_owned())),
+            None => Ok(ClientView {
+                storage: &self.storage,
+                cached_onchain_packed_client: self.cached_onchain_packed_client.as_ref(),
+                proof_state: &self.proof_state,
+            }),
+        }
+    }
+
+    fn block_on<T, F: Future<Output = Result<T, Error>>>(&self, future: F) -> Result<T, Error> {
+        utils::block_on(&self.rt, future)
+    }
+
+    fn cached_onchain_client(&self, client_key: Option<&str>) -> Option<&PackedClient> {
+        match client_key {
+            Some(key) => self
+                .keyed_clients
+                .get(key)
+                .and_then(|keyed| keyed.cached_onchain_packed_client.as_ref()),
+            None => self.cached_onchain_packed_client.as_ref(),
+        }
+    }
+
+    fn set_cached_onchain_client(&mut self, client_key: Option<&str>, client: PackedClient) {
+        match client_key {
+            Some(key) => {
+                if let Some(keyed) = self.keyed_clients.get_mut(key) {
+                    keyed.cached_onchain_packed_client = Some(client);
+                }
+            }
+            None => {
+                // the change is announced by the chain itself, so the tip watcher skips it
+                if let Ok(Some(type_args)) = self.packed_client_type_args(None) {
+                    self.watched_client
+                        .observe(type_args, client.maximal_slot().unpack());
+                }
+                self.cached_onchain_packed_client = Some(client);
+            }
+        }
+    }
+
+    /// Returns the in-memory config, which includes the type ids populated at runtime after
+    /// the multi-client cells are created, so it can be dumped to reconstruct the TOML config.
+    pub fn effective_config(&self) -> CkbChainConfig {
+        self.config.clone()
+    }
+
+    /// Returns the base and tip slots of the headers in the native storage, or `None` if
+    /// the storage is empty.
+    pub fn native_slot_range(&self) -> Result<Option<(Slot, Slot)>, Error> {
+        self.client_native_slot_range(None)
+    }
+
+    fn client_native_slot_range(
+        &self,
+        client_key: Option<&str>,
+    ) -> Result<Option<(Slot, Slot)>, Error> {
+        let storage = self.client_storage(client_key)?;
+        let range = match (
+            storage.get_base_beacon_header_slot()?,
+            storage.get_tip_beacon_header_slot()?,
+        ) {
+            (Some(base_slot), Some(tip_slot)) => Some((base_slot, tip_slot)),
+            _ => None,
+        };
+        Ok(range)
+    }
+
+    /// Returns the minimal slot of the cached on-chain client, which is refreshed after each
+    /// create or update, or `None` if no client has been cached yet.
+    pub fn cached_minimal_slot(&self) -> Option<Slot> {
+        self.cached_onchain_client(None)
+            .map(|client| client.minimal_slot().unpack())
+    }
+
+    /// Returns the maximal slot of the cached on-chain client, see [`Self::cached_minimal_slot`].
+    pub fn cached_maximal_slot(&self) -> Option<Slot> {
+        self.cached_onchain_client(None)
+            .map(|client| client.maximal_slot().unpack())
+    }
+
+    /// Estimates the time until the cached on-chain client reaches `target_slot`, from the
+    /// durations of the recent update cycles.
+    pub fn estimate_sync_eta(&self, target_slot: Slot) -> Result<Duration, Error> {
+        let Some(maximal_slot) = self.cached_maximal_slot() else {
+            return Err(Error::other_error(
+                "no cached on-chain client to estimate the sync from".to_owned(),
+            ));
+        };
+        let recent_cycles = self
+            .recent_update_cycles
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        Ok(utils::estimate_sync_eta(
+            target_slot.saturating_sub(maximal_slot),
+            self.config.minimal_updates_count as u64,
+            &recent_cycles,
+        ))
+    }
+
+    /// Re-fetches the latest on-chain client into the cache, which is otherwise only refreshed
+    /// by the creations and updates of this relayer.
+    pub fn refresh_cached_client(&mut self) -> Result<(), Error> {
+        self.refresh_client_cache(None)
+    }
+
+    fn refresh_client_cache(&mut self, client_key: Option<&str>) -> Result<(), Error> {
+        let Some(update_cells) = self.block_on(self.fetch_update_cells_of(client_key))? else {
+            return Err(Error::other_error("no multi-client cells found".to_owned()));
+        };
+        let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+        self.set_cached_onchain_client(client_key, latest_client);
+        self.publish_status()
+    }
+
+    /// Returns the headers MMR root committed by the latest on-chain client of the default
+    /// multi-client, to cross-check with the native storage or outside, returns `None` if no
+    /// client has been created yet.
+    pub fn onchain_client_root(&self) -> Result<Option<EthH256>, Error> {
+        let update_cells_opt = self.block_on(self.fetch_update_cells_of(None))?;
+        Ok(update_cells_opt.map(|update_cells| {
+            let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+            utils::client_headers_mmr_root(&latest_client)
+        }))
+    }
+
+    /// Returns the id which the next update of the default multi-client targets, i.e. the id
+    /// of the oldest client, which is rotated into the updated one.
+    pub fn next_update_client_id(&self) -> Result<u8, Error> {
+        let Some(update_cells) = self.block_on(self.fetch_update_cells_of(None))? else {
+            return Err(Error::other_error("no multi-client cells found".to_owned()));
+        };
+        let oldest_client = PackedClient::new_unchecked(update_cells.oldest.output_data);
+        Ok(u8::from(oldest_client.id().as_reader()))
+    }
+
+    /// Returns the raw data of the on-chain client cell of `id` in the default multi-client,
+    /// for debugging and verifying it outside.
+    pub fn raw_client_bytes(&self, id: u8) -> Result<Vec<u8>, Error> {
+        let client_type_args = self.created_client_type_args(None)?;
+        let Some((clients, _)) = self.block_on(self.rpc_client.fetch_clients_and_info(
+            &self.config.lightclient_contract_typeargs,
+            &client_type_args,
+        ))?
+        else {
+            return Err(Error::other_error("no multi-client cells found".to_owned()));
+        };
+        clients
+            .into_iter()
+            .find(|client| u8::from(client.id().as_reader()) == id)
+            .map(|client| client.as_slice().to_vec())
+            .ok_or_else(|| Error::other_error(format!("no on-chain client of id {id}")))
+    }
+
+    /// Audits that the client info of the default multi-client points at the newest client,
+    /// an inconsistency indicates a corrupted update history, which the updates refuse to extend.
+    pub fn audit_last_id(&self) -> Result<(), Error> {
+        let client_type_args = self.created_client_type_args(None)?;
+        let clients_and_info_opt = self.block_on(self.rpc_client.fetch_clients_and_info(
+            &self.config.lightclient_contract_typeargs,
+            &client_type_args,
+        ))?;
+        match clients_and_info_opt {
+            Some((clients, info)) => utils::check_last_id(&clients, &info),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the minimal and maximal slots of the cached on-chain client.
+    pub fn onchain_client_slot_range(&self) -> Option<(Slot, Slot)> {
+        self.cached_minimal_slot().zip(self.cached_maximal_slot())
+    }
+
+    /// Returns the relay status of the default client, which is also served by the status server.
+    pub fn status_json(&self) -> Result<serde_json::Value, Error> {
+        Ok(serde_json::json!({
+            "chain_id": self.id().to_string(),
+            "native_slot_range": self.native_slot_range()?,
+            "onchain_client_slot_range": self.onchain_client_slot_range(),
+            "paused": self.is_paused(),
+        }))
+    }
+
+    /// Checks whether the native storage is able to build a proof for `slot`.
+    pub fn can_prove_slot(&self, slot: Slot) -> Result<bool, Error> {
+        self.client_can_prove_slot(None, slot)
+    }
+
+    fn client_can_prove_slot(&self, client_key: Option<&str>, slot: Slot) -> Result<bool, Error> {
+        utils::can_prove_slot(self.client_storage(client_key)?, slot)
+    }
+
+    /// Prunes the digests left by the rollbacks and compacts the native storages of all clients,
+    /// it's a manual maintenance which is safe to call while idle. Nothing up to the tip of a
+    /// storage or the maximal slot of its on-chain client is pruned, so the slots of the
+    /// on-chain client are still provable.
+    pub fn compact_storage(&self) -> Result<CompactionStats, Error> {
+        let client_keys = std::iter::once(None).chain(
+            self.config
+                .keyed_client_type_args
+                .keys()
+                .map(|key| Some(key.as_str())),
+        );
+        let mut stats = CompactionStats::default();
+        for client_key in client_keys {
+            let storage = self.client_storage(client_key)?;
+            stats.bytes_before += storage.approximate_size()?;
+            if let Some(tip_slot) = storage.get_tip_beacon_header_slot()? {
+                let kept_slot = match self.cached_onchain_client(client_key) {
+                    Some(client) => tip_slot.max(client.maximal_slot().unpack()),
+                    None => tip_slot,
+                };
+                stats.pruned_digests += storage.prune_after(kept_slot)?;
+            }
+            storage.compact()?;
+            stats.bytes_after += storage.approximate_size()?;
+        }
+        tracing::info!(
+            "compacted the native storage from {} to {} bytes, {} stale digests are pruned",
+            stats.bytes_before,
+            stats.bytes_after,
+            stats.pruned_digests
+        );
+        Ok(stats)
+    }
+
+    /// Returns the count and the slot range of the headers in the native storage, with its
+    /// approximate size on disk, e.g. to alert before the disk fills up.
+    pub fn storage_stats(&self) -> Result<StorageStats, Error> {
+        let slot_range = self.client_native_slot_range(None)?;
+        let headers_count = slot_range.map_or(0, |(base_slot, tip_slot)| tip_slot - base_slot + 1);
+        let bytes = self.client_storage(None)?.approximate_size()?;
+        Ok(StorageStats {
+            headers_count,
+            slot_range,
+            bytes,
+        })
+    }
+
+    /// Halts sending messages without shutting down, the queries still work.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        tracing::info!("paused sending messages");
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        tracing::info!("resumed sending messages");
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Moves the chain onto a dedicated thread which sends the update batches from `batches`
+    /// in order, like `send_messages_and_wait_commit`, the chain is returned by
+    /// [`update_worker::UpdateWorker::join`] after `batches` is closed.
+    pub fn spawn_update_worker(
+        self,
+        batches: crossbeam_channel::Receiver<TrackedMsgs>,
+    ) -> update_worker::UpdateWorker {
+        update_worker::UpdateWorker::spawn(self, batches)
+    }
+
+    /// Returns the implemented operations, only the multi-clients are relayed to CKB.
+    pub fn capabilities(&self) -> ChainCapabilities {
+        ChainCapabilities {
+            health_check: true,
+            send_messages_and_wait_commit: true,
+            query_clients: true,
+            query_consensus_state: true,
+            query_consensus_state_heights: true,
+            query_txs: true,
+            subscribe: true,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a multi-client from synthetic updates and queries it back to check the round
+    /// trip, it's only allowed on a dev network.
+    ///
+    /// The client is built in a separate storage, which is removed afterwards, so the config
+    /// and the relayed clients are untouched. Its cells are left on the dev chain, since a
+    /// multi-client can't be destroyed, see the type id in the report.
+    pub fn self_test(&mut self) -> Result<SelfTestReport, Error> {
+        let network = self.network()?;
+        if network != NetworkType::Dev {
+            return Err(Error::unsupported(format!(
+                "self-test on the {network:?} network"
+            )));
+        }
+        let storage_dir = self
+            .instance_lock_path
+            .with_file_name(SELF_TEST_STORAGE_DIR);
+        if storage_dir.exists() {
+            fs::remove_dir_all(&storage_dir).map_err(Error::io)?;
+        }
+        let storage = Storage::new_with_compression(&storage_dir, self.config.compress_storage)
+            .map_err(|e| Error::storage_open(storage_dir.display().to_string(), e))?;
+        let client = KeyedClient {
+            storage,
+            cached_onchain_packed_client: None,
+            proof_state: RwLock::new(None),
+        };
+
+        let result = self.run_self_test(&client);
+
+        drop(client);
+        if let Err(err) = fs::remove_dir_all(&storage_dir) {
+            tracing::warn!(
+                "failed to remove the self-test storage {}: {err}",
+                storage_dir.display()
+            );
+        }
+        result
+    }
+
+    fn run_self_test(&mut self, client: &KeyedClient) -> Result<SelfTestReport, Error> {
+        let chain_id = self.id().to_string();
+        let minimal_updates_count = self.resolve_minimal_updates_count()?;
+        let updates_count = SELF_TEST_MIN_UPDATES_COUNT.max(minimal_updates_count as u64);
+        let mut updates = utils::synthetic_updates(SELF_TEST_START_SLOT, updates_count);
+        let tip_header = updates
+            .last()
+            .map(|update| update.finalized_header.clone())
+            .expect("synthetic updates");
+
+        // the synthetic updates aren't from any beacon chain, so their origin isn't checked
+        self.wait_indexer_passed_last_committed_block()?;
+        let (packed_client, packed_proof_update, _, _) = self.get_new_client_and_proof(
+            &chain_id,
+            client.view(),
+            &mut updates,
+            minimal_updates_count,
+        )?;
+        let cells_count = self.config.client_type_args.cells_count;
+        let client_count = cells_count.checked_sub(1).expect("invalid cells_count");
+        let (clients, client_info) =
+            utils::new_multi_client(&packed_client, client_count, minimal_updates_count);
+        let tx_assembler_address = self.tx_assembler_address()?;
+        let (type_id, block_number) = self.create_eth_multi_client_in_one_tx(
+            &tx_assembler_address,
+            clients,
+            client_info,
+            packed_proof_update,
+        )?;
+        tracing::info!("self-test client is created, type_id: {type_id:#x}");
+
+        // the cells of the new client are searched after the indexer has passed them
+        self.wait_indexer_passed_last_committed_block()?;
+        let client_type_args = utils::pack_client_type_args(&type_id, cells_count);
+        let Some(update_cells) = self.block_on(self.rpc_client.fetch_update_cells(
+            &self.config.lightclient_contract_typeargs,
+            &client_type_args,
+        ))?
+        else {
+            return Err(Error::other_error(
+                "no multi-client cells found for the self-test".to_owned(),
+            ));
+        };
+        let onchain_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+        let minimal_slot: Slot = onchain_client.minimal_slot().unpack();
+        let maximal_slot: Slot = onchain_client.maximal_slot().unpack();
+        let tip_root = H256(tip_header.tree_hash_root().0);
+        let tip_root_matched =
+            utils::verify_checkpoint(&client.storage, &onchain_client, tip_header.slot, &tip_root)?;
+        if minimal_slot != SELF_TEST_START_SLOT
+            || maximal_slot != tip_header.slot
+            || !tip_root_matched
+        {
+            return Err(Error::other_error(format!(
+                "the self-test client of slots [{minimal_slot}, {maximal_slot}] doesn't match \
+                 the synthetic updates of slots [{SELF_TEST_START_SLOT}, {}]",
+                tip_header.slot
+            )));
+        }
+        Ok(SelfTestReport {
+            type_id,
+            minimal_slot,
+            maximal_slot,
+            block_number: Some(block_number),
+        })
+    }
+
+    /// Returns the token to abort the in-progress update cycle from another thread, the native
+    /// storage is rolled back and the cycle returns a cancellation error. Once it's set, the
+    /// following cycles are aborted as well until it's cleared.
+    pub fn cancellation_token(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Checks whether the on-chain client commits the trusted checkpoint, i.e. the header whose
+    /// root is `expected_root` at `slot`, so the deployed client can be confirmed before relaying.
+    pub fn verify_onchain_against_checkpoint(
+        &self,
+        slot: Slot,
+        expected_root: &H256,
+    ) -> Result<bool, Error> {
+        self.client_verify_onchain_against_checkpoint(None, slot, expected_root)
+    }
+
+    fn client_verify_onchain_against_checkpoint(
+        &self,
+        client_key: Option<&str>,
+        slot: Slot,
+        expected_root: &H256,
+    ) -> Result<bool, Error> {
+        let Some(update_cells) = self.block_on(self.fetch_update_cells_of(client_key))? else {
+            return Err(Error::other_error("no multi-client cells found".to_owned()));
+        };
+        let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+        let storage = self.client_storage(client_key)?;
+        utils::verify_checkpoint(storage, &latest_client, slot, expected_root)
+    }
+
+    /// Returns the fee paid by the transaction which committed the on-chain client covering
+    /// `slot`, i.e. the client with the lowest maximal slot among those covering it.
+    pub fn update_tx_fee(&self, slot: Slot) -> Result<Capacity, Error> {
+        self.client_update_tx_fee(None, slot)
+    }
+
+    fn client_update_tx_fee(
+        &self,
+        client_key: Option<&str>,
+        slot: Slot,
+    ) -> Result<Capacity, Error> {
+        let client_type_args = self.created_client_type_args(client_key)?;
+        let Some((client_cells, _)) = self.block_on(self.rpc_client.fetch_multi_client_cells(
+            &self.config.lightclient_contract_typeargs,
+            &client_type_args,
+        ))?
+        else {
+            return Err(Error::other_error("no multi-client cells found".to_owned()));
+        };
+        let covering_cell = client_cells
+            .into_iter()
+            .filter_map(|cell| {
+                let client = PackedClient::new_unchecked(cell.output_data.clone());
+                let minimal_slot: Slot = client.minimal_slot().unpack();
+                let maximal_slot: Slot = client.maximal_slot().unpack();
+                (minimal_slot <= slot && slot <= maximal_slot).then_some((maximal_slot, cell))
+            })
+            .min_by_key(|(maximal_slot, _)| *maximal_slot)
+            .map(|(_, cell)| cell);
+        let Some(cell) = covering_cell else {
+            return Err(Error::other_error(format!(
+                "no on-chain client covers slot {slot}"
+            )));
+        };
+        let tx_hash = H256::from_slice(cell.out_point.tx_hash().as_slice()).expect("tx hash");
+        self.block_on(utils::calculate_tx_fee(self.rpc_client.as_ref(), &tx_hash))
+    }
+
+    /// Resolves a CKB transaction to the maximal slot of the multi-client which it committed,
+    /// returns `None` if the transaction is not found or doesn't commit the multi-client.
+    pub fn client_slot_for_tx(&self, tx_hash: &H256) -> Result<Option<Slot>, Error> {
+        let client_type_args = self.created_client_type_args(None)?;
+        let tx_opt = self.block_on(utils::fetch_transaction_opt(
+            self.rpc_client.as_ref(),
+            tx_hash,
+        ))?;
+        Ok(tx_opt.and_then(|tx| utils::committed_client_slot(&tx, &client_type_args)))
+    }
+
+    /// Returns the cell-inclusion proof that the cell at `out_point` is committed. It isn't
+    /// attached by the query methods, since an IBC `MerkleProof` can't express the CKB merkle
+    /// tree, whose nodes are hashed by the personalized blake2b.
+    pub fn query_cell_proof(&self, out_point: &OutPoint) -> Result<CommitmentProofBytes, Error> {
+        self.block_on(utils::fetch_cell_proof(self.rpc_client.as_ref(), out_point))
+    }
+
+    /// Fetches at most `limit` updates which follow the native storage from the configured
+    /// beacon API nodes.
+    pub fn fetch_beacon_updates(&self, limit: u64) -> Result<Vec<EthUpdate>, Error> {
+        let Some(beacon_client) = self.beacon_client.as_ref() else {
+            return Err(Error::other_error(
+                "no `beacon_api_urls` is configured".to_owned(),
+            ));
+        };
+        let start_slot = self.native_slot_range()?.map(|(_, tip_slot)| tip_slot + 1);
+        self.block_on(beacon_client.fetch_updates(start_slot, limit))
+    }
+
+    /// Whether the default on-chain client is caught up with the finalized slot of the beacon
+    /// chain within `tolerance_slots`, e.g. for a readiness probe. The cached on-chain client
+    /// is compared, and it's not synced before a client is created or cached.
+    pub fn is_synced(&self, tolerance_slots: u64) -> Result<bool, Error> {
+        let Some(beacon_client) = self.beacon_client.as_ref() else {
+            return Err(Error::other_error(
+                "no `beacon_api_urls` is configured".to_owned(),
+            ));
+        };
+        let Some(maximal_slot) = self.cached_maximal_slot() else {
+            return Ok(false);
+        };
+        let finalized_slot = self.block_on(beacon_client.fetch_finalized_slot())?;
+        Ok(utils::is_caught_up(
+            maximal_slot,
+            finalized_slot.saturating_sub(self.config.finality_margin_slots),
+            tolerance_slots,
+        ))
+    }
+
+    fn wait_indexer_passed_last_committed_block(&self) -> Result<(), Error> {
+        let Some(block_number) = self.last_committed_block else {
+            return Ok(());
+        };
+        self.block_on(utils::wait_ckb_indexer_synced(
+            self.rpc_client.as_ref(),
+            block_number,
+            Duration::from_millis(500),
+            Duration::from_secs(60),
+        ))
+    }
+
+    /// Returns the `minimal_updates_count` of a new multi-client.
+    ///
+    /// If `inherit_minimal_updates_count` is set, the info cell of the first existing deployment,
+    /// the default one and then the keyed ones in the order of the keys, takes precedence over
+    /// `minimal_updates_count` of the config, which is used only if no info cell is found.
+    fn resolve_minimal_updates_count(&self) -> Result<u8, Error> {
+        if !self.config.inherit_minimal_updates_count {
+            return Ok(self.config.minimal_updates_count);
+        }
+        let client_keys = std::iter::once(None).chain(
+            self.config
+                .keyed_client_type_args
+                .keys()
+                .map(|key| Some(key.as_str())),
+        );
+        for client_key in client_keys {
+            let update_cells = self.block_on(self.fetch_update_cells_of(client_key))?;
+            if let Some(update_cells) = update_cells {
+                let client_info = PackedClientInfo::new_unchecked(update_cells.info.output_data);
+                let minimal_updates_count =
+                    u8::from(client_info.minimal_updates_count().as_reader());
+                tracing::info!(
+                    "inherit minimal_updates_count {minimal_updates_count} from the deployment <{}>",
+                    client_key.unwrap_or("default")
+                );
+                return Ok(minimal_updates_count);
+            }
+        }
+        Ok(self.config.minimal_updates_count)
+    }
+
     fn create_eth_multi_client(
+        &mut self,
+        header_updates: Vec<EthUpdate>,
+        client_key: Option<&str>,
+    ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        self.try_create_eth_multi_client(header_updates, client_key)
+            .map_err(|e| utils::with_chain_context(&self.id(), e))
+    }
+
+    /// Checks that the updates come from a beacon chain of `accepted_genesis_validators_root`,
+    /// which is asked to the `beacon_api_urls` nodes, any updates are accepted without it.
+    fn check_updates_origin(&self, header_updates: &[EthUpdate]) -> Result<(), Error> {
+        let accepted = &self.config.accepted_genesis_validators_root;
+        if accepted.is_empty() {
+            return Ok(());
+        }
+        let Some(beacon_client) = self.beacon_client.as_ref() else {
+            return Err(Error::other_error(
+                "no beacon_api_urls to check the genesis validators root".to_owned(),
+            ));
+        };
+        let accepted = accepted
+            .iter()
+            .map(|root| EthH256::from_slice(root.as_bytes()))
+            .collect::<Vec<_>>();
+        self.block_on(beacon_client.check_updates_origin(header_updates, &accepted))
+    }
+
+    fn try_create_eth_multi_client(
         &mut self,
         mut header_updates: Vec<EthUpdate>,
+        client_key: Option<&str>,
     ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        utils::sort_and_dedup_updates(&mut header_updates)?;
+        self.check_updates_origin(&header_updates)?;
+        self.wait_indexer_passed_last_committed_block()?;
         let chain_id = self.id().to_string();
-        let minimal_updates_count = self.config.minimal_updates_count;
-        let client_type_args = &self.config.client_type_args;
+        let minimal_updates_count = self.resolve_minimal_updates_count()?;
+        let client_type_args = self.client_type_args(client_key)?;
 
         if let Some(type_id) = client_type_args.type_id.as_ref() {
-            let client_type_args: PackedClientTypeArgs = {
-                let type_id = PackedHash::from_slice(type_id.0.as_slice()).expect("build type id");
-                PackedClientTypeArgs::new_builder()
-                    .cells_count(client_type_args.cells_count.into())
-                    .type_id(type_id)
-                    .build()
-            };
+            let cells_count = client_type_args.cells_count;
+            let client_type_args = utils::pack_client_type_args(type_id, cells_count);
 
-            let update_cells = self.rt.block_on(self.rpc_client.fetch_update_cells(
-                &self.config.lightclient_contract_typeargs,
-                &client_type_args,
-            ))?;
+            let created_clients =
+                self.block_on(self.rpc_client.fetch_partially_created_clients(
+                    &self.config.lightclient_contract_typeargs,
+                    &client_type_args,
+                ))?;
+            if let Some(created_clients) = created_clients {
+                let type_id = type_id.clone();
+                return self.resume_create_eth_multi_client(
+                    client_key,
+                    type_id,
+                    cells_count,
+                    created_clients,
+                    minimal_updates_count,
+                );
+            }
+
+            let update_cells = self.block_on(self.fetch_update_cells_of(client_key))?;
             if let Some(UpdateCells {
                 oldest: _,
                 latest,
@@ -154,7 +994,7 @@ impl CkbChain {
             }) = update_cells
             {
                 let latest_client = PackedClient::new_unchecked(latest.output_data);
-                self.cached_onchain_packed_client = Some(latest_client.clone());
+                self.set_cached_onchain_client(client_key, latest_client.clone());
 
                 let onchain_base_slot = latest_client.minimal_slot().unpack();
                 // This is for reporting that clients have been created at that slot.
@@ -164,9 +1004,7 @@ impl CkbChain {
                     LightClientError::missing_last_block_id(utils::into_height(onchain_base_slot)),
                 ));
             } else {
-                return Err(Error::other_error(
-                    "no multi-client cells found for config".to_owned(),
-                ));
+                return Err(Error::ckb_client_cells_gone(format!("{type_id:#x}")));
             }
         }
 
@@ -174,11 +1012,200 @@ impl CkbChain {
             let cells_count = client_type_args.cells_count;
             cells_count.checked_sub(1).expect("invalid cells_count")
         };
+        let genesis_client = match (client_key, self.config.genesis_client.as_deref()) {
+            (None, Some(encoded)) => Some(utils::decode_genesis_client(encoded)?),
+            _ => None,
+        };
+        let mut client = self.client_view(client_key)?;
+        // the client is built on top of the genesis client as an update of it
+        if let Some(genesis_client) = genesis_client.as_ref() {
+            utils::commit_genesis_client_headers(
+                client.storage,
+                genesis_client,
+                &mut header_updates,
+            )?;
+            client.cached_onchain_packed_client = Some(genesis_client);
+        }
 
-        let (packed_client, packed_proof_update, prev_slot_opt) =
-            self.get_new_client_and_proof(&chain_id, &mut header_updates, minimal_updates_count)?;
+        let (packed_client, packed_proof_update, _, budget_limit) = self.get_new_client_and_proof(
+            &chain_id,
+            client,
+            &mut header_updates,
+            minimal_updates_count,
+        )?;
+        if let Some(limit) = budget_limit {
+            tracing::warn!(
+                "multi-client is created partially by the {limit} of the budget, the rest \
+                 headers are left to the updates"
+            );
+        }
+        let (clients, client_info) =
+            utils::new_multi_client(&packed_client, client_count, minimal_updates_count);
+        let latest_client = clients[0].clone();
+
+        let storage = self.client_storage(client_key)?.clone();
+        // nothing is on chain yet, so the headers of the genesis client are dropped as well
+        let rollback = |err: Error| {
+            if let Err(err) = storage.rollback_to(None) {
+                return err.into();
+            }
+            err
+        };
+        let tx_assembler_address = self.tx_assembler_address().map_err(rollback)?;
+        let chunk_size = self.config.create_chunk_size;
+        let (type_id, block_number) = if chunk_size == 0 || chunk_size >= client_count {
+            self.create_eth_multi_client_in_one_tx(
+                &tx_assembler_address,
+                clients,
+                client_info,
+                packed_proof_update,
+            )
+            .map_err(rollback)?
+        } else {
+            self.create_eth_multi_client_in_chunks(
+                client_key,
+                &tx_assembler_address,
+                clients,
+                client_info,
+                client_count + 1,
+                None,
+                Some(packed_proof_update),
+                rollback,
+            )?
+        };
+
+        // TODO: Write back the type id to config.
+        tracing::info!("new type_id: {}", type_id);
+        if let Some(client_type_args) = self.config.select_client_type_args_mut(client_key) {
+            client_type_args.type_id = Some(type_id);
+        }
+        self.finish_create_eth_multi_client(client_key, latest_client, block_number)
+    }
+
+    /// Creates all the cells of a multi-client in one transaction, returns the type id of the
+    /// cells and the number of the block which they're committed in.
+    fn create_eth_multi_client_in_one_tx(
+        &mut self,
+        address: &Address,
+        clients: Vec<PackedClient>,
+        client_info: PackedClientInfo,
+        packed_proof_update: PackedProofUpdate,
+    ) -> Result<(H256, u64), Error> {
+        let (tx, inputs, type_id) =
+            self.block_on(self.rpc_client.assemble_create_multi_client_transaction(
+                address,
+                clients,
+                client_info,
+                &self.config.lightclient_lock_typeargs,
+                &self.config.lightclient_contract_typeargs,
+                packed_proof_update,
+                self.fee_options(),
+            ))?;
+        let block_number = self.try_sign_and_send_transaction(tx, inputs)?;
+        Ok((type_id, block_number))
+    }
+
+    /// Creates the cells of a multi-client chunk by chunk, each transaction is committed before
+    /// the next one, and the info cell is created after all the clients. The type id is kept
+    /// once the first chunk is committed, so an interrupted creation is resumed by creating the
+    /// client again, while `on_nothing_created` handles an error before that.
+    fn create_eth_multi_client_in_chunks(
+        &mut self,
+        client_key: Option<&str>,
+        address: &Address,
+        clients: Vec<PackedClient>,
+        client_info: PackedClientInfo,
+        cells_count: u8,
+        mut type_id: Option<H256>,
+        mut packed_proof_update: Option<PackedProofUpdate>,
+        on_nothing_created: impl FnOnce(Error) -> Error,
+    ) -> Result<(H256, u64), Error> {
+        let chunk_size = match self.config.create_chunk_size {
+            0 => clients.len().max(1),
+            chunk_size => chunk_size as usize,
+        };
+        let mut chunks = clients
+            .chunks(chunk_size)
+            .map(|chunk| (chunk.to_vec(), None))
+            .collect::<Vec<_>>();
+        chunks.push((vec![], Some(client_info)));
+
+        let chunks_count = chunks.len();
+        let mut block_number = 0;
+        for (index, (clients, client_info)) in chunks.into_iter().enumerate() {
+            let result = self
+                .block_on(
+                    self.rpc_client
+                        .assemble_create_multi_client_chunk_transaction(
+                            address,
+                            clients,
+                            client_info,
+                            cells_count,
+                            type_id.clone(),
+                            &self.config.lightclient_lock_typeargs,
+                            &self.config.lightclient_contract_typeargs,
+                            packed_proof_update.take(),
+                            self.fee_options(),
+                        ),
+                )
+                .and_then(|(tx, inputs, new_type_id)| {
+                    let block_number = self.try_sign_and_send_transaction(tx, inputs)?;
+                    Ok((new_type_id, block_number))
+                });
+            let (new_type_id, number) = match result {
+                Ok(created) => created,
+                Err(err) => {
+                    let Some(type_id) = type_id.as_ref() else {
+                        return Err(on_nothing_created(err));
+                    };
+                    tracing::warn!(
+                        "creation of the multi-client {type_id:#x} is interrupted at chunk \
+                         {}/{chunks_count}, create the client again to resume it",
+                        index + 1
+                    );
+                    return Err(err);
+                }
+            };
+            tracing::info!(
+                "chunk {}/{chunks_count} of the multi-client {new_type_id:#x} is created",
+                index + 1
+            );
+            if type_id.is_none() {
+                if let Some(args) = self.config.select_client_type_args_mut(client_key) {
+                    args.type_id = Some(new_type_id.clone());
+                }
+                type_id = Some(new_type_id);
+            }
+            block_number = number;
+        }
+        Ok((type_id.expect("type id of the created cells"), block_number))
+    }
+
+    /// Resumes an interrupted chunked creation, the missing clients are replicated from a
+    /// created one, then the info cell is created.
+    fn resume_create_eth_multi_client(
+        &mut self,
+        client_key: Option<&str>,
+        type_id: H256,
+        cells_count: u8,
+        created_clients: Vec<PackedClient>,
+        minimal_updates_count: u8,
+    ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        let client_count = cells_count.checked_sub(1).expect("invalid cells_count");
+        tracing::info!(
+            "resume creating the multi-client {type_id:#x}, {}/{client_count} clients are created",
+            created_clients.len()
+        );
+        let created_ids = created_clients
+            .iter()
+            .map(|client| u8::from(client.id().as_reader()))
+            .collect::<Vec<_>>();
+        // all clients are identical replicas at the creation, so any created one is the latest,
+        // and the info points to the client 0
+        let latest_client = created_clients[0].clone().as_builder().id(0.into()).build();
         let clients = (0..client_count)
-            .map(|i| packed_client.clone().as_builder().id(i.into()).build())
+            .filter(|id| !created_ids.contains(id))
+            .map(|id| latest_client.clone().as_builder().id(id.into()).build())
             .collect::<Vec<_>>();
         let client_info = PackedClientInfo::new_builder()
             .last_id(0.into())
@@ -186,65 +1213,133 @@ impl CkbChain {
             .build();
 
         let tx_assembler_address = self.tx_assembler_address()?;
-        let (tx, inputs, type_id) =
-            self.rt
-                .block_on(self.rpc_client.assemble_create_multi_client_transaction(
-                    &tx_assembler_address,
-                    clients,
-                    client_info,
-                    &self.config.lightclient_lock_typeargs,
-                    &self.config.lightclient_contract_typeargs,
-                    packed_proof_update,
-                ))?;
-        self.sign_and_send_transaction(tx, inputs).map_err(|err| {
-            if let Err(err) = self.storage.rollback_to(prev_slot_opt) {
-                return err.into();
-            }
-            err
-        })?;
-
-        // TODO: Write back the type id to config.
-        tracing::info!("new type_id: {}", type_id);
-        self.config.client_type_args.type_id = Some(type_id);
+        let (_, block_number) = self.create_eth_multi_client_in_chunks(
+            client_key,
+            &tx_assembler_address,
+            clients,
+            client_info,
+            cells_count,
+            Some(type_id),
+            None,
+            |err| err,
+        )?;
+        self.finish_create_eth_multi_client(client_key, latest_client, block_number)
+    }
 
-        self.print_status_log()?;
-        Ok(vec![])
+    fn finish_create_eth_multi_client(
+        &mut self,
+        client_key: Option<&str>,
+        latest_client: PackedClient,
+        block_number: u64,
+    ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        let base_slot: Slot = latest_client.minimal_slot().unpack();
+        let maximal_slot: Slot = latest_client.maximal_slot().unpack();
+        self.client_storage(client_key)?
+            .advance_verified_with(maximal_slot, self.correlation_id.as_deref())?;
+        self.set_cached_onchain_client(client_key, latest_client);
+
+        self.print_status_log(client_key)?;
+        let client_id = self.client_id_of(client_key)?;
+        let event = utils::new_create_event(client_id, base_slot, block_number)?;
+        let height = event.height;
+        let mut events = vec![event];
+        if let Some(correlation_id) = self.correlation_id.as_deref() {
+            events.push(utils::new_correlation_event(height, correlation_id));
+        }
+        Ok(events)
     }
 
     fn update_eth_multi_client(
+        &mut self,
+        header_updates: Vec<EthUpdate>,
+        client_key: Option<&str>,
+    ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        self.retry_update_eth_multi_client(header_updates, client_key)
+            .map_err(|e| utils::with_chain_context(&self.id(), e))
+    }
+
+    fn retry_update_eth_multi_client(
         &mut self,
         mut header_updates: Vec<EthUpdate>,
+        client_key: Option<&str>,
     ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        utils::sort_and_dedup_updates(&mut header_updates)?;
+        self.check_updates_origin(&header_updates)?;
+        let max_retries = self.config.max_cycle_retries;
+        let mut retries = 0;
+        let mut ctx = utils::CycleContext::new(self.config.cycle_retry_budget);
+        let started_at = Instant::now();
+        loop {
+            match self.try_update_eth_multi_client(header_updates.clone(), client_key, &mut ctx) {
+                Err(err)
+                    if retries < max_retries
+                        && !ctx.has_sent()
+                        && utils::is_transient_cycle_error(&err) =>
+                {
+                    retries += 1;
+                    let delay = utils::retry_backoff(retries);
+                    tracing::warn!(
+                        "update cycle failed, retry {retries}/{max_retries} after {delay:?}: {err}"
+                    );
+                    std::thread::sleep(delay);
+                }
+                Ok(events) => {
+                    let advanced_slots = events.first().and_then(utils::advanced_slots_of);
+                    if let (None, Some(advanced_slots)) = (client_key, advanced_slots) {
+                        if self.recent_update_cycles.len() == utils::RECENT_UPDATE_CYCLES_LIMIT {
+                            self.recent_update_cycles.pop_front();
+                        }
+                        self.recent_update_cycles.push_back(utils::UpdateCycle {
+                            advanced_slots,
+                            duration: started_at.elapsed(),
+                        });
+                    }
+                    return Ok(events);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // An update cycle which fetches the cells, then assembles, signs and sends the transaction,
+    // the native storage is rolled back if it fails after the proof is built. The retries of
+    // these calls are taken from the retry budget of `ctx`.
+    fn try_update_eth_multi_client(
+        &mut self,
+        mut header_updates: Vec<EthUpdate>,
+        client_key: Option<&str>,
+        ctx: &mut utils::CycleContext,
+    ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        self.check_exported_tx_settled()?;
+        self.wait_indexer_passed_last_committed_block()?;
         let chain_id = self.id().to_string();
-        let client_type_args: PackedClientTypeArgs = {
-            let Some(type_id) = self.config.client_type_args.type_id.as_ref()
-            else {
-                // TODO: better error
-                return Err(Error::other_error("no type id in client type args".to_owned()));
-            };
-            let type_id = PackedHash::from_slice(type_id.0.as_slice()).expect("build type id");
-            PackedClientTypeArgs::new_builder()
-                .cells_count(self.config.client_type_args.cells_count.into())
-                .type_id(type_id)
-                .build()
-        };
-
-        let Some(update_cells) = self
-            .rt
-            .block_on(
-                self
-                    .rpc_client
-                    .fetch_update_cells(
-                        &self.config.lightclient_contract_typeargs,
-                        &client_type_args
-                    )
-            )?
+        let client_type_args = self.created_client_type_args(client_key)?;
+        let Some((client_cells, client_info_cell)) =
+            self.block_on(ctx.retry("fetch_multi_client_cells", || {
+                self.rpc_client.fetch_multi_client_cells(
+                    &self.config.lightclient_contract_typeargs,
+                    &client_type_args,
+                )
+            }))?
         else {
             return Err(Error::other_error("no multi-client cells found".to_owned()));
         };
+        // a corrupted update history isn't extended
+        {
+            let clients = client_cells
+                .iter()
+                .map(|cell| PackedClient::new_unchecked(cell.output_data.clone()))
+                .collect::<Vec<_>>();
+            let client_info = PackedClientInfo::new_unchecked(client_info_cell.output_data.clone());
+            utils::check_last_id(&clients, &client_info)?;
+        }
+        let cells_count = u8::from(client_type_args.cells_count().as_reader());
+        let update_cells =
+            assembler::select_update_cells(client_cells, client_info_cell, cells_count);
 
         let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data.clone());
-        self.cached_onchain_packed_client = Some(latest_client);
+        let prev_maximal_slot: Slot = latest_client.maximal_slot().unpack();
+        self.set_cached_onchain_client(client_key, latest_client);
 
         let minimal_updates_count = {
             let client_info =
@@ -252,60 +1347,177 @@ impl CkbChain {
             u8::from(client_info.minimal_updates_count().as_reader())
         };
 
-        let (mut updated_client, packed_proof_update, prev_slot_opt) =
-            self.get_new_client_and_proof(&chain_id, &mut header_updates, minimal_updates_count)?;
+        let (mut updated_client, packed_proof_update, prev_slot_opt, budget_limit) = self
+            .get_new_client_and_proof(
+                &chain_id,
+                self.client_view(client_key)?,
+                &mut header_updates,
+                minimal_updates_count,
+            )?;
         updated_client = {
             let oldest_client =
                 PackedClient::new_unchecked(update_cells.oldest.output_data.clone());
             updated_client.as_builder().id(oldest_client.id()).build()
         };
+        let maximal_slot: Slot = updated_client.maximal_slot().unpack();
 
-        let tx_assembler_address = self.tx_assembler_address()?;
-        let (tx, inputs) =
-            self.rt
-                .block_on(self.rpc_client.assemble_update_multi_client_transaction(
+        let storage = self.client_storage(client_key)?.clone();
+        let rollback = |err: Error| {
+            if let Err(err) = storage.rollback_to(prev_slot_opt) {
+                return err.into();
+            }
+            err
+        };
+        let tx_assembler_address = self.tx_assembler_address().map_err(rollback)?;
+        let (tx, inputs) = self
+            .block_on(ctx.retry("assemble transaction", || {
+                self.rpc_client.assemble_update_multi_client_transaction(
                     &tx_assembler_address,
-                    update_cells,
-                    updated_client,
+                    update_cells.clone(),
+                    updated_client.clone(),
                     &client_type_args,
                     &self.config.lightclient_lock_typeargs,
+                    &self.config.retired_lock_typeargs,
                     &self.config.lightclient_contract_typeargs,
-                    packed_proof_update,
-                ))?;
-        self.sign_and_send_transaction(tx, inputs).map_err(|err| {
-            if let Err(err) = self.storage.rollback_to(prev_slot_opt) {
-                return err.into();
-            }
-            err
-        })?;
-
-        self.print_status_log()?;
-        Ok(vec![])
+                    packed_proof_update.clone(),
+                    self.fee_options(),
+                )
+            }))
+            .map_err(rollback)?;
+        utils::check_assembled_client(&tx, &client_type_args, &updated_client).map_err(rollback)?;
+        // the hash doesn't cover the witnesses, so it's unchanged by signing
+        let tx_hash = H256::from_slice(tx.hash().as_slice()).expect("tx hash");
+        if let Some(dir) = self.config.export_signed_tx_dir.as_ref() {
+            let tx = self.sign_transaction(tx, inputs).map_err(rollback)?;
+            let path = utils::export_signed_tx(dir, &tx).map_err(rollback)?;
+            // nothing is committed until the exported transaction is broadcast, then the native
+            // storage catches up with the on-chain client by the same headers
+            storage.rollback_to(prev_slot_opt)?;
+            tracing::info!(
+                "signed transaction of the update to slot {maximal_slot} is exported to {}",
+                path.display()
+            );
+            self.exported_tx = Some((tx_hash, path));
+            return Ok(vec![]);
+        }
+        let block_number = self
+            .try_sign_and_send_transaction_in_cycle(tx, inputs, ctx)
+            .map_err(rollback)?;
+        storage.advance_verified_with(maximal_slot, self.correlation_id.as_deref())?;
+        self.set_cached_onchain_client(client_key, updated_client);
+        self.notify_committed_update(utils::CommittedUpdate {
+            tx_hash: tx_hash.clone(),
+            maximal_slot,
+            block_number,
+        });
+
+        self.print_status_log(client_key)?;
+        let mut event = utils::new_update_event(
+            maximal_slot.saturating_sub(prev_maximal_slot),
+            maximal_slot,
+            budget_limit.is_some(),
+            block_number,
+        )?;
+        if let Some(correlation_id) = self.correlation_id.as_deref() {
+            utils::tag_correlation_id(&mut event, correlation_id);
+        }
+        if self.recent_committed_events.len() == utils::RECENT_COMMITTED_EVENTS_LIMIT {
+            self.recent_committed_events.pop_front();
+        }
+        self.recent_committed_events
+            .push_back((tx_hash, event.clone()));
+        Ok(vec![event])
     }
 
     fn get_new_client_and_proof(
         &self,
         chain_id: &str,
+        client: ClientView<'_>,
         header_updates: &mut Vec<EthUpdate>,
         minimal_updates_count: u8,
-    ) -> Result<(PackedClient, PackedProofUpdate, Option<Slot>), Error> {
+    ) -> Result<
+        (
+            PackedClient,
+            PackedProofUpdate,
+            Option<Slot>,
+            Option<utils::BudgetLimit>,
+        ),
+        Error,
+    > {
+        if self.is_cancelled() {
+            return Err(Error::cycle_cancelled());
+        }
+        let storage = client.storage;
+        let onchain_packed_client_opt = client.cached_onchain_packed_client;
         utils::align_native_and_onchain_updates(
             chain_id,
             header_updates,
-            &self.storage,
-            self.cached_onchain_packed_client.as_ref(),
+            storage,
+            onchain_packed_client_opt,
+        )?;
+        let budget_limit = {
+            let recent_cycles = self
+                .recent_update_cycles
+                .iter()
+                .copied()
+                .collect::<Vec<_>>();
+            let (count, limit) = utils::updates_within_budget(
+                header_updates,
+                &self.config.update_budget,
+                minimal_updates_count as u64,
+                &recent_cycles,
+            );
+            if let Some(limit) = limit {
+                tracing::info!(
+                    "update cycle is limited by the {limit} of the budget, take {count} of {} \
+                     headers, the rest are left to the next cycles",
+                    header_updates.len()
+                );
+                header_updates.truncate(count);
+            }
+            limit
+        };
+        let onchain_tip_slot_opt: Option<Slot> =
+            onchain_packed_client_opt.map(|client| client.maximal_slot().unpack());
+        utils::check_sync_committee_updates(
+            onchain_tip_slot_opt,
+            storage.get_announced_sync_committee_slot()?,
+            header_updates,
         )?;
-        let (prev_slot_opt, new_client, packed_proof_update) =
-            utils::get_verified_packed_client_and_proof_update(
+        if self.config.verify_updates_locally {
+            header_updates
+                .iter()
+                .try_for_each(utils::verify_update_locally)?;
+        }
+        // the new proof is built on top of the on-chain tip, which must be in the storage
+        if let Some(onchain_packed_client) = onchain_packed_client_opt {
+            let onchain_tip_slot: Slot = onchain_packed_client.maximal_slot().unpack();
+            if !utils::can_prove_slot(storage, onchain_tip_slot)? {
+                return Err(Error::light_client_verification(
+                    chain_id.to_owned(),
+                    LightClientError::missing_last_block_id(utils::into_height(onchain_tip_slot)),
+                ));
+            }
+        }
+        let (prev_slot_opt, new_client, packed_proof_update) = {
+            let mut proof_state = client.proof_state.write().expect("poisoned lock");
+            utils::get_verified_packed_client_and_proof_update_with_state(
                 chain_id,
                 header_updates,
-                &self.storage,
-                self.cached_onchain_packed_client.as_ref(),
-            )?;
-        if new_client.maximal_slot().unpack() - new_client.minimal_slot().unpack() + 1
-            < minimal_updates_count as u64
-        {
-            if let Err(err) = self.storage.rollback_to(prev_slot_opt) {
+                storage,
+                onchain_packed_client_opt,
+                &mut proof_state,
+            )?
+        };
+        let slots_count = match utils::client_slots_count(&new_client) {
+            Ok(slots_count) => slots_count,
+            Err(err) => {
+                storage.rollback_to(prev_slot_opt)?;
+                return Err(err);
+            }
+        };
+        if slots_count < minimal_updates_count as u64 {
+            if let Err(err) = storage.rollback_to(prev_slot_opt) {
                 return Err(err.into());
             }
             // TODO: This may require some handling outside to retry.
@@ -313,60 +1525,212 @@ impl CkbChain {
                 "not enough updates to update multi-client".to_owned(),
             ));
         }
-        Ok((new_client, packed_proof_update, prev_slot_opt))
+        if self.is_cancelled() {
+            storage.rollback_to(prev_slot_opt)?;
+            return Err(Error::cycle_cancelled());
+        }
+        tracing::info!(
+            "proof update: {}",
+            utils::describe_proof_update(&packed_proof_update)
+        );
+        Ok((new_client, packed_proof_update, prev_slot_opt, budget_limit))
     }
 
+    /// Signs and sends the transaction, then waits until it's committed, returns the number of
+    /// the block which it's committed in.
     pub fn sign_and_send_transaction(
         &mut self,
         tx: TransactionView,
         inputs: Vec<CellOutput>,
-    ) -> Result<(), Error> {
-        let key: Secp256k1KeyPair = self
-            .keybase
-            .get_key(&self.config.key_name)
-            .map_err(Error::key_base)?
-            .into_ckb_keypair(self.network()?);
-        let tx = signer::sign(tx, &inputs, vec![], key).map_err(Error::key_base)?;
+    ) -> Result<u64, Error> {
+        self.try_sign_and_send_transaction(tx, inputs)
+            .map_err(|e| utils::with_chain_context(&self.id(), e))
+    }
 
-        let task = async {
-            let send_res = self
-                .rpc_client
-                .send_transaction(&tx.data().into(), Some(OutputsValidator::Passthrough))
+    /// Returns the effective fee rate of the last transaction sent by
+    /// [`Self::sign_and_send_transaction`], in shannons per byte of the signed transaction.
+    pub fn last_fee_rate(&self) -> Option<u64> {
+        self.last_fee_rate
+    }
+
+    /// Sends a signed transaction which is exported by the `export_signed_tx_dir` mode, then
+    /// waits until it's committed, returns the number of the block which it's committed in.
+    pub fn broadcast_signed_tx(&mut self, path: &Path) -> Result<u64, Error> {
+        let tx = utils::load_signed_tx(path)
+            .map_err(|e| utils::with_chain_context(&self.id(), e))?
+            .into_view();
+        let tx_hash = H256::from_slice(tx.hash().as_slice()).expect("tx hash");
+        let block_number = self
+            .send_signed_transaction(tx, &mut utils::CycleContext::default())
+            .map_err(|e| utils::with_chain_context(&self.id(), e))?;
+        if matches!(&self.exported_tx, Some((exported_hash, _)) if *exported_hash == tx_hash) {
+            self.exported_tx = None;
+        }
+        Ok(block_number)
+    }
+
+    // An exported transaction spends the same cells as the next update, so the updates wait
+    // until it's broadcast, or it's discarded by removing its file.
+    fn check_exported_tx_settled(&mut self) -> Result<(), Error> {
+        let Some((_, path)) = self.exported_tx.as_ref() else {
+            return Ok(());
+        };
+        if path.exists() {
+            return Err(Error::ckb_exported_tx_pending(path.display().to_string()));
+        }
+        tracing::warn!("the exported transaction {} is discarded", path.display());
+        self.exported_tx = None;
+        Ok(())
+    }
+
+    fn try_sign_and_send_transaction(
+        &mut self,
+        tx: TransactionView,
+        inputs: Vec<CellOutput>,
+    ) -> Result<u64, Error> {
+        self.try_sign_and_send_transaction_in_cycle(tx, inputs, &mut utils::CycleContext::default())
+    }
+
+    fn try_sign_and_send_transaction_in_cycle(
+        &mut self,
+        tx: TransactionView,
+        inputs: Vec<CellOutput>,
+        ctx: &mut utils::CycleContext,
+    ) -> Result<u64, Error> {
+        let tx = self.sign_transaction(tx, inputs.clone())?;
+        let fee_rate = utils::TxSummary::new(&tx, &inputs).fee_rate();
+        let block_number = self.send_signed_transaction(tx, ctx)?;
+        self.last_fee_rate = Some(fee_rate);
+        Ok(block_number)
+    }
+
+    fn get_signing_key(&self, key_name: &str) -> Result<Secp256k1KeyPair, Error> {
+        self.keybase
+            .get_key(key_name)
+            .map_err(|e| utils::keyring_error(key_name, e))
+    }
+
+    fn sign_transaction(
+        &self,
+        tx: TransactionView,
+        inputs: Vec<CellOutput>,
+    ) -> Result<TransactionView, Error> {
+        let network = self.network()?;
+        let key = self
+            .get_signing_key(&self.config.key_name)?
+            .into_ckb_keypair(network);
+        // the fee payer signs the inputs which are locked by its own address
+        let lock_signers = match self.config.fee_payer_key.as_ref() {
+            Some(key_name) => {
+                let fee_payer = self.get_signing_key(key_name)?.into_ckb_keypair(network);
+                let lock_script: Script = self
+                    .address_for_pubkey(&fee_payer.public_key)?
+                    .payload()
+                    .into();
+                vec![(lock_script.calc_script_hash(), fee_payer)]
+            }
+            None => vec![],
+        };
+        tracing::info!(
+            "assembled transaction: {}",
+            utils::TxSummary::new(&tx, &inputs)
+        );
+        let lock_args = AddressPayload::from_pubkey(&key.public_key).args();
+        let lock_signers_args = lock_signers
+            .iter()
+            .map(|(lock_hash, key)| {
+                let args = AddressPayload::from_pubkey(&key.public_key).args();
+                (lock_hash.clone(), args)
+            })
+            .collect::<Vec<_>>();
+        let tx = signer::sign_with_keys(tx, &inputs, vec![], key, &lock_signers)
+            .map_err(Error::key_base)?;
+        if self.config.verify_signatures {
+            signer::verify_with_keys(&tx, &inputs, &lock_args, &lock_signers_args)?;
+        }
+        utils::check_tx_size(&tx, self.config.max_tx_size)?;
+        utils::check_tx_balance(
+            &utils::TxSummary::new(&tx, &inputs),
+            self.config.min_fee_rate,
+        )?;
+        Ok(tx)
+    }
+
+    fn send_signed_transaction(
+        &mut self,
+        tx: TransactionView,
+        ctx: &mut utils::CycleContext,
+    ) -> Result<u64, Error> {
+        let (rpc_client, tx_ref) = (self.rpc_client.as_ref(), &tx);
+        // retried by the budget of the cycle, while the waiting below isn't
+        let send = || async move {
+            let send_res = rpc_client
+                .send_transaction(&tx_ref.data().into(), Some(OutputsValidator::Passthrough))
                 .await;
-            let hash = match send_res {
+            match send_res {
                 Ok(hash) => Ok(hash),
                 Err(e) => {
-                    let pool_log = utils::collect_ckb_tx_pool_info_on_duplicate_tx(
-                        self.rpc_client.as_ref(),
-                        &e,
-                    )
-                    .await
-                    .unwrap_or_default();
+                    let pool_log = utils::collect_ckb_tx_pool_info_on_duplicate_tx(rpc_client, &e)
+                        .await
+                        .unwrap_or_default();
                     let tx_info = format!(
                         "== transaction for debugging is below ==\n{}",
-                        serde_json::to_string(&JsonTx::from(tx)).expect("jsonify ckb tx")
+                        serde_json::to_string(&JsonTx::from(tx_ref.clone()))
+                            .expect("jsonify ckb tx")
                     );
-                    Err(Error::send_tx(format!("{e}\n{pool_log}\n{tx_info}\n")))
+                    let kind = utils::classify_send_tx_error(&e.to_string());
+                    Err(Error::ckb_send_tx(
+                        kind,
+                        format!("{e}\n{pool_log}\n{tx_info}\n"),
+                    ))
                 }
-            }?;
+            }
+        };
+        let task = async {
+            let hash = ctx.retry("send_transaction", send).await?;
+            ctx.mark_sent();
 
             tracing::info!(
                 "ckb send_transaction success: {}, wait committed to block",
                 hex::encode(&hash)
             );
 
-            utils::wait_ckb_transaction_committed(
+            // the fee bumping isn't supported yet, so a stuck transaction is only reported
+            let mut on_stuck = |time_used: Duration| {
+                tracing::warn!(
+                    "transaction {} isn't committed after {time_used:?}",
+                    hex::encode(&hash)
+                );
+            };
+            let stuck_handler = utils::StuckHandler {
+                stuck_after: self.config.stuck_after,
+                on_stuck: &mut on_stuck,
+            };
+            let block_number = utils::wait_ckb_transaction_committed(
                 &self.rpc_client,
-                hash,
+                hash.clone(),
                 Duration::from_secs(3),
-                0,
-                Duration::from_secs(60),
+                self.config.tx_confirmations,
+                self.config.tx_commit_timeout,
+                Some(self.cancelled.as_ref()),
+                Some(stuck_handler),
             )
             .await?;
-            tracing::info!("transaction committed to block");
-            Ok(())
+            tracing::info!("transaction committed to block {block_number}");
+            Ok(block_number)
+        };
+        let block_number = self.block_on(task)?;
+        self.last_committed_block = Some(block_number);
+        Ok(block_number)
+    }
+
+    fn notify_committed_update(&self, update: utils::CommittedUpdate) {
+        let Some(url) = self.config.commit_webhook_url.as_ref() else {
+            return;
         };
-        self.rt.block_on(task)
+        if let Err(err) = self.block_on(utils::notify_committed_update(url, &update)) {
+            tracing::warn!("failed to notify the committed update {update:?}: {err}");
+        }
     }
 
     pub fn network(&self) -> Result<NetworkType, Error> {
@@ -377,7 +1741,6 @@ impl CkbChain {
         } else {
             let network = {
                 let chain_info = self
-                    .rt
                     .block_on(self.rpc_client.get_blockchain_info())
                     .map_err(|e| Error::rpc_response(e.to_string()))?;
                 if chain_info.chain == "ckb" {
@@ -394,6 +1757,21 @@ impl CkbChain {
         Ok(network)
     }
 
+    fn fee_options(&self) -> FeeOptions {
+        FeeOptions {
+            fee_rate: self.config.fee_rate,
+            min_fee_rate: self.config.min_fee_rate,
+            min_change_capacity: self.config.min_change_capacity,
+            lock_cell_dep: self
+                .config
+                .suffixed_lock
+                .as_ref()
+                .map(|lock| lock.cell_dep.clone().into()),
+        }
+    }
+
+    /// Returns the address which funds the fees and receives the changes, it's the address of
+    /// `fee_payer_key` if it's configured.
     pub fn tx_assembler_address(&self) -> Result<Address, Error> {
         let cached_address = self
             .cached_tx_assembler_address
@@ -403,13 +1781,13 @@ impl CkbChain {
         let address = if let Some(address) = cached_address {
             address
         } else {
-            let network = self.network()?;
-            let key: Secp256k1KeyPair = self
-                .keybase
-                .get_key(&self.config.key_name)
-                .map_err(Error::key_base)?;
-            let address_payload = AddressPayload::from_pubkey(&key.public_key);
-            let address = Address::new(network, address_payload, true);
+            let key_name = self
+                .config
+                .fee_payer_key
+                .as_ref()
+                .unwrap_or(&self.config.key_name);
+            let key = self.get_signing_key(key_name)?;
+            let address = self.address_for_pubkey(&key.public_key)?;
             *self
                 .cached_tx_assembler_address
                 .write()
@@ -419,26 +1797,107 @@ impl CkbChain {
         Ok(address)
     }
 
-    fn print_status_log(&self) -> Result<(), Error> {
+    /// Warns if the relayer's clock differs from the timestamp of the CKB tip block by more than
+    /// `max_clock_skew`, since a large skew causes premature timeouts.
+    pub fn check_clock_skew(&self) {
+        let tip = match self.block_on(self.rpc_client.get_tip_header()) {
+            Ok(tip) => tip,
+            Err(err) => {
+                tracing::warn!("failed to fetch the tip header to check the clock skew: {err}");
+                return;
+            }
+        };
+        let tip_timestamp: u64 = tip.inner.timestamp.into();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let skew = utils::clock_skew(tip_timestamp, now);
+        if skew > self.config.max_clock_skew {
+            tracing::warn!(
+                "the relayer's clock differs from the tip block timestamp {tip_timestamp} of CKB \
+                 by {skew:?}, which exceeds {:?}",
+                self.config.max_clock_skew
+            );
+        }
+    }
+
+    fn key_passphrase(&self, key_name: &str) -> Result<String, Error> {
+        let key_error =
+            |reason: &str| Error::ckb_key_backup(key_name.to_owned(), reason.to_owned());
+        let Some(env_var) = &self.config.key_passphrase_env else {
+            return Err(key_error("`key_passphrase_env` is not configured"));
+        };
+        std::env::var(env_var).map_err(|_| key_error("the passphrase is not set"))
+    }
+
+    /// Exports the key of the keybase in an encrypted form, for backup and migration.
+    pub fn export_key(&self, key_name: &str) -> Result<utils::EncryptedKey, Error> {
+        let passphrase = self.key_passphrase(key_name)?;
+        let key: Secp256k1KeyPair = self
+            .keybase
+            .get_key(key_name)
+            .map_err(|e| utils::keyring_error(key_name, e))?;
+        utils::encrypt_key(key_name, &key.private_key, &passphrase)
+    }
+
+    /// Imports an exported key into the keybase under its original name.
+    pub fn import_encrypted_key(
+        &mut self,
+        encrypted_key: &utils::EncryptedKey,
+    ) -> Result<(), Error> {
+        let passphrase = self.key_passphrase(&encrypted_key.key_name)?;
+        let private_key = utils::decrypt_key(encrypted_key, &passphrase)?;
+        let key = Secp256k1KeyPair::from_ckb_private_key(private_key, self.network()?);
+        self.keybase
+            .add_key(&encrypted_key.key_name, key)
+            .map_err(Error::key_base)?;
+        tracing::info!("key `{}` is imported", encrypted_key.key_name);
+        Ok(())
+    }
+
+    /// Returns the full address of the secp256k1 sighash lock of `pubkey` on this network, or
+    /// the one of the configured `suffixed_lock` whose args are appended `lock_args_suffix`.
+    pub fn address_for_pubkey(&self, pubkey: &PublicKey) -> Result<Address, Error> {
+        let network = self.network()?;
+        let mut address_payload = AddressPayload::from_pubkey(pubkey);
+        if let Some(lock) = self.config.suffixed_lock.as_ref() {
+            let suffix = self.config.lock_args_suffix.as_bytes();
+            let args = [address_payload.args().as_ref(), suffix].concat();
+            address_payload = AddressPayload::new_full(
+                lock.hash_type.clone().into(),
+                lock.code_hash.pack(),
+                Bytes::from(args),
+            );
+        }
+        Ok(Address::new(network, address_payload, true))
+    }
+
+    fn print_status_log(&self, client_key: Option<&str>) -> Result<(), Error> {
         let contract_typeid_args = &self.config.lightclient_contract_typeargs;
-        let client_type_args = &self.config.client_type_args;
 
         let mut status_log = String::new();
 
-        if let Some(type_id) = client_type_args.type_id.as_ref() {
-            let packed_client_type_args: PackedClientTypeArgs = {
-                let type_id = PackedHash::from_slice(type_id.0.as_slice()).expect("build type id");
-                PackedClientTypeArgs::new_builder()
-                    .cells_count(client_type_args.cells_count.into())
-                    .type_id(type_id)
-                    .build()
-            };
-            let clients_and_info_opt = self.rt.block_on(
+        if let Some(packed_client_type_args) = self.packed_client_type_args(client_key)? {
+            let clients_and_info_opt = self.block_on(
                 self.rpc_client
                     .fetch_clients_and_info(contract_typeid_args, &packed_client_type_args),
             )?;
             if let Some((mut clients, info)) = clients_and_info_opt {
                 clients.sort_by_key(|c| u8::from(c.id().as_reader()));
+                for client in &clients {
+                    let minimal_slot: Slot = client.minimal_slot().unpack();
+                    let maximal_slot: Slot = client.maximal_slot().unpack();
+                    let headers_mmr_root = utils::client_headers_mmr_root(client);
+                    tracing::info!(
+                        client_key = client_key.unwrap_or_default(),
+                        id = u8::from(client.id().as_reader()),
+                        minimal_slot,
+                        maximal_slot,
+                        headers_mmr_root = format!("{headers_mmr_root:#x}"),
+                        "[STATUS] on-chain client"
+                    );
+                }
                 let clients_msg = clients
                     .iter()
                     .map(|c| format!("{}", c.unpack()))
@@ -454,15 +1913,30 @@ impl CkbChain {
             status_log += "on-chain status: NONE, ";
         }
 
-        if let (Some(start_slot), Some(end_slot)) = (
-            self.storage.get_base_beacon_header_slot()?,
-            self.storage.get_tip_beacon_header_slot()?,
-        ) {
+        if let Some((start_slot, end_slot)) = self.client_native_slot_range(client_key)? {
             status_log += &format!("native status: [{start_slot}, {end_slot}]");
         } else {
             status_log += "native status: NONE";
         }
-        tracing::info!("[STATUS] {status_log}");
+        if let Some(key) = client_key {
+            tracing::info!("[STATUS] <{key}> {status_log}");
+        } else {
+            tracing::info!("[STATUS] {status_log}");
+        }
+        self.publish_status()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "ckb-status-server")]
+    fn publish_status(&self) -> Result<(), Error> {
+        if let Some(server) = &self.status_server {
+            server.publish(self.status_json()?);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "ckb-status-server"))]
+    fn publish_status(&self) -> Result<(), Error> {
         Ok(())
     }
 }
@@ -480,50 +1954,138 @@ impl ChainEndpoint for CkbChain {
 
     fn bootstrap(config: ChainConfig, rt: Arc<TokioRuntime>) -> Result<Self, Error> {
         let config: CkbChainConfig = config.try_into()?;
-        let rpc_client = Arc::new(RpcClient::new(&config.ckb_rpc, &config.ckb_indexer_rpc));
-        let storage = Storage::new(&config.data_dir)?;
+        config.validate().map_err(Error::config)?;
+        let rpc_client = Arc::new(
+            RpcClient::new(&config.ckb_rpc, &config.ckb_indexer_rpc)
+                .with_max_concurrent_requests(config.max_concurrent_requests)
+                .with_timeouts(config.rpc_timeout, config.rpc_method_timeouts.clone()),
+        );
+        let storage_dir = utils::namespaced_storage_dir(&config.data_dir, &config.id)?;
+        // held before the storage is opened, so a second instance is refused by it
+        fs::create_dir_all(&storage_dir).map_err(Error::io)?;
+        let instance_lock_path = storage_dir.join(INSTANCE_LOCK_FILE);
+        let instance_lock = utils::InstanceLock::acquire(&instance_lock_path)?;
+        let open_storage = |path: PathBuf| {
+            Storage::new_with_compression(&path, config.compress_storage)
+                .map_err(|e| Error::storage_open(path.display().to_string(), e))
+        };
+        let storage = open_storage(storage_dir.clone())?;
+        let keyed_clients = config
+            .keyed_client_type_args
+            .keys()
+            .map(|key| {
+                let storage = open_storage(storage_dir.join("clients").join(key))?;
+                let keyed = KeyedClient {
+                    storage,
+                    cached_onchain_packed_client: None,
+                    proof_state: RwLock::new(None),
+                };
+                Ok((key.to_owned(), keyed))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+        let beacon_client = if config.beacon_api_urls.is_empty() {
+            None
+        } else {
+            Some(
+                BeaconClient::new(&config.beacon_api_urls)
+                    .with_finality_margin(config.finality_margin_slots),
+            )
+        };
 
         #[cfg(not(test))]
         {
             use ckb_sdk::constants::TYPE_ID_CODE_HASH;
-            use prelude::CellSearcher;
             use sighash::init_sighash_celldep;
 
-            rt.block_on(init_sighash_celldep(rpc_client.as_ref()))?;
+            utils::block_on(&rt, init_sighash_celldep(rpc_client.as_ref()))?;
 
             // check if contract and lock type_id_args are on-chain deployed
-            let contract_cell = rt.block_on(rpc_client.search_cell_by_typescript(
-                &TYPE_ID_CODE_HASH.pack(),
-                &config.lightclient_contract_typeargs.as_bytes().to_owned(),
-            ))?;
-            if contract_cell.is_none() {
+            let Some(contract_cell) = utils::block_on(
+                &rt,
+                rpc_client.search_cell_by_typescript(
+                    &TYPE_ID_CODE_HASH.pack(),
+                    &config.lightclient_contract_typeargs.as_bytes().to_owned(),
+                ),
+            )?
+            else {
                 return Err(Error::other_error(
                     "invalid `lightclient_contract_typeargs` option".to_owned(),
                 ));
-            }
-            let lock_cell = rt.block_on(rpc_client.search_cell_by_typescript(
-                &TYPE_ID_CODE_HASH.pack(),
-                &config.lightclient_lock_typeargs.as_bytes().to_owned(),
-            ))?;
-            if lock_cell.is_none() {
+            };
+            utils::check_type_id_cell(
+                &contract_cell.output,
+                &config.lightclient_contract_typeargs,
+                "lightclient_contract_typeargs",
+            )?;
+            let Some(lock_cell) = utils::block_on(
+                &rt,
+                rpc_client.search_cell_by_typescript(
+                    &TYPE_ID_CODE_HASH.pack(),
+                    &config.lightclient_lock_typeargs.as_bytes().to_owned(),
+                ),
+            )?
+            else {
                 return Err(Error::other_error(
                     "invalid `lightclient_lock_typeargs` conig".to_owned(),
                 ));
+            };
+            utils::check_type_id_cell(
+                &lock_cell.output,
+                &config.lightclient_lock_typeargs,
+                "lightclient_lock_typeargs",
+            )?;
+            for lock_typeargs in &config.retired_lock_typeargs {
+                let Some(lock_cell) = utils::block_on(
+                    &rt,
+                    rpc_client.search_cell_by_typescript(
+                        &TYPE_ID_CODE_HASH.pack(),
+                        &lock_typeargs.as_bytes().to_owned(),
+                    ),
+                )?
+                else {
+                    return Err(Error::other_error(format!(
+                        "invalid `retired_lock_typeargs` config: {lock_typeargs:#x}"
+                    )));
+                };
+                utils::check_type_id_cell(
+                    &lock_cell.output,
+                    lock_typeargs,
+                    "retired_lock_typeargs",
+                )?;
             }
         }
 
         #[cfg(test)]
         let keybase = KeyRing::new(Store::Memory, "ckb", &config.id).map_err(Error::key_base)?;
 
+        // the key from the environment or a key file is only kept in memory
         #[cfg(not(test))]
-        let keybase =
-            KeyRing::new(Default::default(), "ckb", &config.id).map_err(Error::key_base)?;
+        let keybase = if config.key_from_env.is_some() || config.key_file.is_some() {
+            KeyRing::new(Store::Memory, "ckb", &config.id).map_err(Error::key_base)?
+        } else {
+            KeyRing::new(Default::default(), "ckb", &config.id).map_err(Error::key_base)?
+        };
 
         // check out the existence of the secret key
         #[cfg(not(test))]
-        let _: Secp256k1KeyPair = keybase.get_key(&config.key_name).map_err(Error::key_base)?;
+        if config.key_from_env.is_none() && config.key_file.is_none() {
+            let _: Secp256k1KeyPair = keybase
+                .get_key(&config.key_name)
+                .map_err(|e| utils::keyring_error(&config.key_name, e))?;
+        }
+        #[cfg(not(test))]
+        if let Some(key_name) = &config.fee_payer_key {
+            let _: Secp256k1KeyPair = keybase
+                .get_key(key_name)
+                .map_err(|e| utils::keyring_error(key_name, e))?;
+        }
 
-        let ckb = CkbChain {
+        let event_stream = subscription::EventStream::new(
+            config.id.clone(),
+            config.subscribe_capacity,
+            config.subscribe_overflow,
+        );
+        let mut ckb = CkbChain {
             rt,
             rpc_client,
             config,
@@ -532,13 +2094,78 @@ impl ChainEndpoint for CkbChain {
             cached_network: RwLock::new(None),
             cached_tx_assembler_address: RwLock::new(None),
             cached_onchain_packed_client: None,
+            keyed_clients,
+            proof_state: RwLock::new(None),
+            last_committed_block: None,
+            last_fee_rate: None,
+            exported_tx: None,
+            recent_update_cycles: VecDeque::new(),
+            recent_committed_events: VecDeque::new(),
+            correlation_id: None,
+            beacon_client,
+            instance_lock_path,
+            instance_lock: Some(instance_lock),
+            paused: AtomicBool::new(false),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "ckb-status-server")]
+            status_server: None,
+            event_stream: Arc::new(Mutex::new(event_stream)),
+            tip_watcher: None,
+            watched_client: Default::default(),
         };
-        ckb.print_status_log()?;
+        if let Some(env_var) = &ckb.config.key_from_env {
+            let key = utils::key_from_env(env_var, ckb.network()?)?;
+            ckb.keybase
+                .add_key(&ckb.config.key_name, key)
+                .map_err(Error::key_base)?;
+            tracing::info!(
+                "signing key `{}` is loaded from `{env_var}`",
+                ckb.config.key_name
+            );
+        }
+        if let Some(path) = &ckb.config.key_file {
+            let key = utils::key_from_pkcs8_file(path, ckb.network()?)?;
+            ckb.keybase
+                .add_key(&ckb.config.key_name, key)
+                .map_err(Error::key_base)?;
+            tracing::info!(
+                "signing key `{}` is loaded from file {}",
+                ckb.config.key_name,
+                path.display()
+            );
+        }
+        if let Some(addr) = ckb.config.status_server_addr {
+            #[cfg(feature = "ckb-status-server")]
+            {
+                ckb.status_server = Some(status_server::StatusServer::spawn(addr)?);
+            }
+            #[cfg(not(feature = "ckb-status-server"))]
+            tracing::warn!(
+                "`status_server_addr` {addr} is ignored without the `ckb-status-server` feature"
+            );
+        }
+        ckb.check_clock_skew();
+        ckb.print_status_log(None)?;
+        for client_key in ckb.config.keyed_client_type_args.keys() {
+            ckb.print_status_log(Some(client_key))?;
+        }
 
         Ok(ckb)
     }
 
-    fn shutdown(self) -> Result<(), Error> {
+    fn shutdown(mut self) -> Result<(), Error> {
+        // the storages are closed when the chain is dropped, but the buffered writes are
+        // flushed explicitly, so the recent headers survive a crash afterwards
+        self.storage.flush()?;
+        for (key, keyed) in &self.keyed_clients {
+            keyed.storage.flush()?;
+            tracing::debug!("storage of keyed client {key} is flushed");
+        }
+        tracing::info!("storage of ckb chain {} is flushed", self.id());
+        // release the lock explicitly, it's also released if the chain is dropped
+        self.instance_lock.take();
+        #[cfg(feature = "ckb-status-server")]
+        self.status_server.take();
         tracing::debug!("runtime of ckb chain endpoint shutdown");
         Ok(())
     }
@@ -572,6 +2199,17 @@ impl ChainEndpoint for CkbChain {
         &mut self,
         tracked_msgs: TrackedMsgs,
     ) -> Result<Vec<IbcEventWithHeight>, Error> {
+        let correlation_id = tracked_msgs.tracking_id.to_string();
+        let _span =
+            tracing::info_span!("send_messages", correlation_id = %correlation_id).entered();
+        if self.is_paused() {
+            tracing::info!(
+                "paused, skip sending {} messages of {}",
+                tracked_msgs.msgs.len(),
+                tracked_msgs.tracking_id
+            );
+            return Ok(vec![]);
+        }
         let updates = tracked_msgs
             .msgs
             .into_iter()
@@ -582,15 +2220,28 @@ impl ChainEndpoint for CkbChain {
             .map(|client| client.lightclient_update)
             .collect();
 
-        match tracked_msgs.tracking_id {
+        self.correlation_id = Some(correlation_id);
+        let result = match tracked_msgs.tracking_id {
             TrackingId::Static(NonCosmos::ETH_CREATE_CLIENT) => {
-                self.create_eth_multi_client(updates)
+                self.create_eth_multi_client(updates, None)
             }
             TrackingId::Static(NonCosmos::ETH_UPDATE_CLIENT) => {
-                self.update_eth_multi_client(updates)
+                self.update_eth_multi_client(updates, None)
             }
             _ => Err(Error::send_tx("unknown msg".to_owned())),
+        };
+        self.correlation_id = None;
+        let events = result?;
+        if let Some(last) = events.last() {
+            let mut event_stream = self.event_stream.lock().map_err(Error::other)?;
+            event_stream.broadcast(EventBatch {
+                chain_id: self.id(),
+                tracking_id: tracked_msgs.tracking_id,
+                height: last.height,
+                events: events.clone(),
+            });
         }
+        Ok(events)
     }
 
     fn send_messages_and_wait_check_tx(
@@ -617,12 +2268,27 @@ impl ChainEndpoint for CkbChain {
         todo!()
     }
 
-    fn query_balance(
-        &self,
-        _key_name: Option<&str>,
-        _denom: Option<&str>,
-    ) -> Result<Balance, Error> {
-        todo!()
+    /// Queries the balance of the key, or the tx-assembler by default. The denom is `ckb` (by
+    /// default) for the capacity in shannons, or the hex hash of an sUDT type script for the
+    /// amount of the sUDT, see [`utils::BalanceDenom`].
+    fn query_balance(&self, key_name: Option<&str>, denom: Option<&str>) -> Result<Balance, Error> {
+        let denom = utils::BalanceDenom::parse(denom)?;
+        let address = match key_name {
+            Some(key_name) => {
+                let key: Secp256k1KeyPair = self
+                    .keybase
+                    .get_key(key_name)
+                    .map_err(|e| utils::keyring_error(key_name, e))?;
+                self.address_for_pubkey(&key.public_key)?
+            }
+            None => self.tx_assembler_address()?,
+        };
+        let lock_script: Script = address.payload().into();
+        let cells = self.block_on(self.rpc_client.search_all_cells_by_lock(&lock_script))?;
+        Ok(Balance {
+            amount: denom.balance_of(&cells).to_string(),
+            denom: denom.to_string(),
+        })
     }
 
     fn query_all_balances(&self, _key_name: Option<&str>) -> Result<Vec<Balance>, Error> {
@@ -655,6 +2321,18 @@ impl ChainEndpoint for CkbChain {
             };
             clients.push(client_state);
         }
+        for client_key in self.config.keyed_client_type_args.keys() {
+            if self.cached_onchain_client(Some(client_key)).is_some() {
+                let client_id = self.client_id_of(Some(client_key))?;
+                let client_state = IdentifiedAnyClientState {
+                    client_id,
+                    client_state: AnyClientState::Ckb(CkbClientState {
+                        chain_id: self.id(),
+                    }),
+                };
+                clients.push(client_state);
+            }
+        }
         Ok(clients)
     }
 
@@ -666,33 +2344,65 @@ impl ChainEndpoint for CkbChain {
         todo!()
     }
 
+    /// Queries the consensus state of a multi-client at the slot of `consensus_height`, which
+    /// exists for each slot in the range of the latest client, as `query_consensus_state_heights`
+    /// reports.
     fn query_consensus_state(
         &self,
-        _request: QueryConsensusStateRequest,
-        _include_proof: IncludeProof,
+        request: QueryConsensusStateRequest,
+        include_proof: IncludeProof,
     ) -> Result<(AnyConsensusState, Option<MerkleProof>), Error> {
-        todo!()
+        if matches!(include_proof, IncludeProof::Yes) {
+            return Err(Error::unsupported("proof of consensus state".to_owned()));
+        }
+        let client_key = self.client_key_of(&request.client_id)?;
+        let slot = utils::from_height(request.consensus_height);
+        let not_found =
+            || Error::ckb_consensus_state_not_found(request.client_id.to_string(), slot);
+        let Some(update_cells) = self.block_on(self.fetch_update_cells_of(client_key))? else {
+            return Err(not_found());
+        };
+        let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+        let minimal_slot: Slot = latest_client.minimal_slot().unpack();
+        let maximal_slot: Slot = latest_client.maximal_slot().unpack();
+        if !(minimal_slot..=maximal_slot).contains(&slot) {
+            return Err(not_found());
+        }
+        Ok((AnyConsensusState::Eth(EthConsensusState {}), None))
     }
 
     fn query_consensus_state_heights(
         &self,
-        _request: QueryConsensusStateHeightsRequest,
+        request: QueryConsensusStateHeightsRequest,
     ) -> Result<Vec<ICSHeight>, Error> {
-        todo!()
+        let client_key = self.client_key_of(&request.client_id)?;
+        let Some(update_cells) = self.block_on(self.fetch_update_cells_of(client_key))? else {
+            return Ok(vec![]);
+        };
+        // the multi-client keeps a consensus state of each slot in its range
+        let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+        utils::paginate_slots(
+            latest_client.minimal_slot().unpack(),
+            latest_client.maximal_slot().unpack(),
+            request.pagination.as_ref(),
+        )
+        .map(utils::slot_to_height)
+        .collect()
     }
 
     fn query_upgraded_client_state(
         &self,
         _request: QueryUpgradedClientStateRequest,
     ) -> Result<(AnyClientState, MerkleProof), Error> {
-        todo!()
+        // CKB multi-client cells have no upgrade plan
+        Err(Error::unsupported("upgraded client state".to_owned()))
     }
 
     fn query_upgraded_consensus_state(
         &self,
         _request: QueryUpgradedConsensusStateRequest,
     ) -> Result<(AnyConsensusState, MerkleProof), Error> {
-        todo!()
+        Err(Error::unsupported("upgraded consensus state".to_owned()))
     }
 
     fn query_connections(
@@ -806,11 +2516,22 @@ impl ChainEndpoint for CkbChain {
         todo!()
     }
 
-    fn query_txs(
-        &self,
-        _request: super::requests::QueryTxRequest,
-    ) -> Result<Vec<IbcEventWithHeight>, Error> {
-        todo!()
+    /// Queries the events of a recent committed update by its transaction hash, which carry
+    /// the committed slot and the correlation id of the update.
+    fn query_txs(&self, request: QueryTxRequest) -> Result<Vec<IbcEventWithHeight>, Error> {
+        let QueryTxRequest::Transaction(QueryTxHash(tx_hash)) = request else {
+            return Err(Error::unsupported("query the events of clients".to_owned()));
+        };
+        let Ok(tx_hash) = H256::from_slice(tx_hash.as_bytes()) else {
+            return Ok(vec![]);
+        };
+        let events = self
+            .recent_committed_events
+            .iter()
+            .filter(|(hash, _)| hash == &tx_hash)
+            .map(|(_, event)| event.clone())
+            .collect();
+        Ok(events)
     }
 
     fn query_packet_events(
@@ -868,7 +2589,22 @@ impl ChainEndpoint for CkbChain {
     }
 
     fn subscribe(&mut self) -> Result<super::handle::Subscription, Error> {
-        todo!()
+        if self.tip_watcher.is_none() {
+            if let Some(type_args) = self.packed_client_type_args(None)? {
+                self.watched_client.watch(type_args);
+            }
+            let check = tip_watcher::ClientCheck {
+                chain_id: self.id(),
+                rpc_client: Arc::clone(&self.rpc_client),
+                contract_typeargs: self.config.lightclient_contract_typeargs.clone(),
+                client: self.watched_client.clone(),
+                event_stream: Arc::clone(&self.event_stream),
+            };
+            let url = self.config.ckb_ws_rpc.clone();
+            self.tip_watcher = Some(tip_watcher::TipWatcher::spawn(&self.rt, url, check));
+        }
+        let mut event_stream = self.event_stream.lock().map_err(Error::other)?;
+        Ok(event_stream.subscribe())
     }
 
     fn query_incentivized_packet(
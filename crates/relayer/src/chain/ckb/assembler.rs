@@ -3,7 +3,8 @@
 use async_trait::async_trait;
 use ckb_sdk::{
     constants::TYPE_ID_CODE_HASH,
-    traits::{LiveCell, PrimaryScriptType},
+    rpc::ckb_indexer::SearchKey,
+    traits::{CellQueryOptions, LiveCell, PrimaryScriptType, ValueRangeOption},
     Address,
 };
 use ckb_types::{
@@ -17,6 +18,7 @@ use eth_light_client_in_ckb_verification::types::packed::{
     ClientInfoReader as PackedClientInfoReader, ClientReader as PackedClientReader,
     ClientTypeArgs as PackedClientTypeArgs, Hash as PackedHash, ProofUpdate as PackedProofUpdate,
 };
+use tracing::{debug, info};
 
 use super::{
     prelude::{CellSearcher, TxCompleter},
@@ -41,6 +43,8 @@ fn make_lightclient_script(script_typehash: packed::Byte32, args: Vec<u8>) -> pa
         .build()
 }
 
+/// Searches the live contract cell, which is checked at every assembly since the cell found by
+/// `bootstrap` may be consumed or moved later.
 async fn search_contract_cell<S: CellSearcher + Sync + ?Sized>(
     searcher: &S,
     script: &packed::Script,
@@ -52,23 +56,90 @@ async fn search_contract_cell<S: CellSearcher + Sync + ?Sized>(
     let cell = match contract {
         Some(cell) => cell,
         None => {
-            return Err(Error::rpc_response(format!(
-                "contract not found: {}",
-                hex::encode(typeid_args)
-            )));
+            return Err(Error::contract_celldep_missing(hex::encode(typeid_args)));
         }
     };
     Ok(cell)
 }
 
+/// Fee related options of the assembled transactions, the fee rates are in shannons per byte.
+#[derive(Clone, Debug)]
+pub struct FeeOptions {
+    pub fee_rate: u64,
+    pub min_fee_rate: u64,
+    pub min_change_capacity: u64,
+    // Cell dep of the lock of the cells which pay the fee, if it isn't the sighash lock
+    pub lock_cell_dep: Option<packed::CellDep>,
+}
+
+#[derive(Clone)]
 pub struct UpdateCells {
     pub oldest: LiveCell,
     pub latest: LiveCell,
     pub info: LiveCell,
 }
 
+/// Selects the cells which an update of a multi-client of `cells_count` cells consumes from all
+/// its cells, i.e. the oldest client which is rotated, the latest client and the client info.
+pub fn select_update_cells(
+    client_cells: Vec<LiveCell>,
+    client_info_cell: LiveCell,
+    cells_count: u8,
+) -> UpdateCells {
+    let client_info = PackedClientInfo::new_unchecked(client_info_cell.output_data.clone());
+    let latest_id = u8::from(client_info.last_id().as_reader());
+
+    let oldest_id = if latest_id + 2 < cells_count {
+        latest_id + 1
+    } else {
+        0
+    };
+
+    let mut oldest = None;
+    let mut latest = None;
+
+    for cell in client_cells {
+        let client = PackedClient::new_unchecked(cell.output_data.clone());
+        let client_id = u8::from(client.id().as_reader());
+        if client_id == latest_id {
+            latest.replace(cell.clone());
+        }
+        if client_id == oldest_id {
+            oldest.replace(cell);
+        }
+    }
+    let (Some(oldest), Some(latest)) = (oldest, latest) else {
+        panic!("on-chain data corrupted: oldest or latest client not found");
+    };
+    UpdateCells {
+        oldest,
+        latest,
+        info: client_info_cell,
+    }
+}
+
 #[async_trait]
 pub trait TxAssembler: CellSearcher + TxCompleter {
+    /// Selects the fee rate from the suggestion of the node and the configured ones.
+    async fn select_fee_rate(&self, fee_options: &FeeOptions) -> u64 {
+        let suggested_fee_rate = match self.get_fee_rate_statistics().await {
+            // shannons per kilobyte to shannons per byte
+            Ok(Some(statistics)) => Some((statistics.median.value() + 999) / 1000),
+            Ok(None) => None,
+            Err(err) => {
+                debug!("no suggested fee rate: {err}");
+                None
+            }
+        };
+        let (fee_rate, source) = utils::choose_fee_rate(
+            suggested_fee_rate,
+            fee_options.min_fee_rate,
+            fee_options.fee_rate,
+        );
+        info!("fee rate {fee_rate} shannons/byte is chosen from the {source}");
+        fee_rate
+    }
+
     async fn fetch_multi_client_cells(
         &self,
         contract_typeid_args: &H256,
@@ -76,22 +147,20 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
     ) -> Result<Option<(Vec<LiveCell>, LiveCell)>, Error> {
         let contract_typescript = make_typeid_script(contract_typeid_args.as_bytes().to_vec());
         let type_hash = contract_typescript.calc_script_hash();
-        // There are at most 255 cells
+        // There are at most 255 cells, one more is searched to catch the extra cells
         let cells_count = u8::from(client_type_args.cells_count().as_reader());
         let cells = self
-            .search_cells_by_typescript(&type_hash, client_type_args.as_slice(), cells_count as u32)
+            .search_cells_by_typescript(
+                &type_hash,
+                client_type_args.as_slice(),
+                cells_count as u32 + 1,
+            )
             .await?;
 
         // As for the error handling here, the only "allowable" error is that user supply a wrong client type args,
         // and we can't find any cells for it on chain. Otherwise, it means the on-chain data is corrupted.
         if cells.is_empty() {
             return Ok(None);
-        } else if cells.len() != cells_count as usize {
-            panic!(
-                "fetched client cells count not match: expect {}, actual {}",
-                cells_count,
-                cells.len()
-            );
         }
 
         let mut client_cells = vec![];
@@ -116,6 +185,25 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         let Some(client_info_cell) = client_info_cell_opt else {
             panic!("on-chain data corrupted: client info cell not found");
         };
+        // an update targets the oldest client, which doesn't exist if there are fewer cells
+        if client_cells.len() + 1 != cells_count as usize {
+            return Err(Error::cells_count_mismatch(
+                cells_count.saturating_sub(1),
+                client_cells.len(),
+            ));
+        }
+
+        // The cells are fetched both before and after an update, so a dropped or duplicated
+        // client id is caught on both sides.
+        let client_ids = client_cells
+            .iter()
+            .map(|cell| {
+                let client = PackedClient::new_unchecked(cell.output_data.clone());
+                u8::from(client.id().as_reader())
+            })
+            .collect::<Vec<_>>();
+        utils::check_client_id_set(&client_ids, cells_count)?;
+
         Ok(Some((client_cells, client_info_cell)))
     }
 
@@ -142,6 +230,43 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         Ok(Some((clients, client_info)))
     }
 
+    /// Fetches only the client info cell of a multi-client, which is told apart from the client
+    /// cells by the size of its data, so the client cells are not fetched.
+    async fn fetch_client_info(
+        &self,
+        contract_typeid_args: &H256,
+        client_type_args: &PackedClientTypeArgs,
+    ) -> Result<Option<PackedClientInfo>, Error> {
+        let contract_typescript = make_typeid_script(contract_typeid_args.as_bytes().to_vec());
+        let type_script = make_lightclient_script(
+            contract_typescript.calc_script_hash(),
+            client_type_args.as_slice().to_vec(),
+        );
+        let mut query = CellQueryOptions::new(type_script, PrimaryScriptType::Type);
+        let info_size = PackedClientInfo::default().as_slice().len() as u64;
+        query.data_len_range = Some(ValueRangeOption::new_exact(info_size));
+        let search: SearchKey = query.into();
+        // 2 at most to catch the duplicated info cells
+        let cells = self
+            .fetch_live_cells(search, 2, None)
+            .await
+            .map_err(|e| Error::rpc_response(e.to_string()))?
+            .objects;
+        let cell: LiveCell = match <[_; 1]>::try_from(cells) {
+            Ok([cell]) => cell.into(),
+            Err(cells) if cells.is_empty() => return Ok(None),
+            Err(_) => {
+                return Err(Error::other_error(
+                    "multi client cell has more than one client info".to_owned(),
+                ))
+            }
+        };
+        let client_info = PackedClientInfo::from_slice(&cell.output_data).map_err(|e| {
+            Error::other_error(format!("multi client cell has invalid client info: {e}"))
+        })?;
+        Ok(Some(client_info))
+    }
+
     async fn fetch_update_cells(
         &self,
         contract_typeid_args: &H256,
@@ -156,38 +281,36 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         };
 
         let cells_count = u8::from(client_type_args.cells_count().as_reader());
-        let client_info = PackedClientInfo::new_unchecked(client_info_cell.output_data.clone());
-        let latest_id = u8::from(client_info.last_id().as_reader());
-
-        let oldest_id = if latest_id + 2 < cells_count {
-            latest_id + 1
-        } else {
-            0
-        };
-
-        let mut oldest = None;
-        let mut latest = None;
+        let update_cells = select_update_cells(client_cells, client_info_cell, cells_count);
+        Ok(Some(update_cells))
+    }
 
-        for cell in client_cells {
-            let client = PackedClient::new_unchecked(cell.output_data.clone());
-            let client_id = u8::from(client.id().as_reader());
-            if client_id == latest_id {
-                latest.replace(cell.clone());
-            }
-            if client_id == oldest_id {
-                oldest.replace(cell);
-            }
+    /// Fetches the clients of a multi-client whose chunked creation is interrupted, i.e. some
+    /// client cells are created while the info cell isn't, `None` if there is no such cell or
+    /// the info cell is found.
+    async fn fetch_partially_created_clients(
+        &self,
+        contract_typeid_args: &H256,
+        client_type_args: &PackedClientTypeArgs,
+    ) -> Result<Option<Vec<PackedClient>>, Error> {
+        let contract_typescript = make_typeid_script(contract_typeid_args.as_bytes().to_vec());
+        let type_hash = contract_typescript.calc_script_hash();
+        let cells_count = u8::from(client_type_args.cells_count().as_reader());
+        let cells = self
+            .search_cells_by_typescript(&type_hash, client_type_args.as_slice(), cells_count as u32)
+            .await?;
+        if cells.is_empty()
+            || cells
+                .iter()
+                .any(|cell| PackedClientReader::verify(&cell.output_data, false).is_err())
+        {
+            return Ok(None);
         }
-        let (Some(oldest), Some(latest)) = (oldest, latest) else {
-            panic!("on-chain data corrupted: oldest or latest client not found");
-        };
-        let update_cells = UpdateCells {
-            oldest,
-            latest,
-            info: client_info_cell,
-        };
-
-        Ok(Some(update_cells))
+        let clients = cells
+            .into_iter()
+            .map(|cell| PackedClient::new_unchecked(cell.output_data))
+            .collect();
+        Ok(Some(clients))
     }
 
     async fn fetch_packed_client(
@@ -242,6 +365,39 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         lock_typeid_args: &H256,
         contract_typeid_args: &H256,
         packed_proof_update: PackedProofUpdate,
+        fee_options: FeeOptions,
+    ) -> Result<(TransactionView, Vec<packed::CellOutput>, H256), Error> {
+        let cells_count = (clients.len() + 1) as u8;
+        self.assemble_create_multi_client_chunk_transaction(
+            address,
+            clients,
+            Some(client_info),
+            cells_count,
+            None,
+            lock_typeid_args,
+            contract_typeid_args,
+            Some(packed_proof_update),
+            fee_options,
+        )
+        .await
+    }
+
+    /// Assembles a transaction which creates a part of the cells of a multi-client. The type id
+    /// is calculated from the first input if `type_id` is `None`, i.e. for the first chunk, and
+    /// the info cell is only created with `client_info`, i.e. after all the clients.
+    ///
+    /// The proof update is only carried by the first chunk, the later ones replicate its client.
+    async fn assemble_create_multi_client_chunk_transaction(
+        &self,
+        address: &Address,
+        clients: Vec<PackedClient>,
+        client_info: Option<PackedClientInfo>,
+        cells_count: u8,
+        type_id: Option<H256>,
+        lock_typeid_args: &H256,
+        contract_typeid_args: &H256,
+        packed_proof_update: Option<PackedProofUpdate>,
+        fee_options: FeeOptions,
     ) -> Result<(TransactionView, Vec<packed::CellOutput>, H256), Error> {
         // Build lock script
         let (lock_script, lock_contract_celldep) = self.build_lock_script(lock_typeid_args).await?;
@@ -257,10 +413,13 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
                 .build()
         };
         // We have to get one input cell to calculate the type id for those new cells.
-        let mut _excessive_capacity = 0;
-        let input_cells = self
-            .search_cells_by_address_and_capacity(address, 1, &mut _excessive_capacity)
-            .await?;
+        let input_cells = if type_id.is_none() {
+            let mut _excessive_capacity = 0;
+            self.search_cells_by_address_and_capacity(address, 1, &mut _excessive_capacity)
+                .await?
+        } else {
+            vec![]
+        };
         let inputs_capacity: u64 = input_cells
             .iter()
             .map(|c| Unpack::<u64>::unpack(&c.output.capacity()))
@@ -277,12 +436,11 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
             })
             .unzip();
 
-        let cells_count = (clients.len() + 1) as u8;
-        let new_cells_type_id = {
+        let new_cells_type_id = type_id.unwrap_or_else(|| {
             let first = inputs.first().expect("input cell not found");
             let type_id = utils::calculate_type_id(first, cells_count as usize);
             H256(type_id)
-        };
+        });
         let type_script: packed::Script = {
             let packed_type_id = PackedHash::new_builder()
                 .set(new_cells_type_id.0.map(packed::Byte::new))
@@ -302,7 +460,9 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
             .into_iter()
             .map(|client| client.as_slice().pack())
             .collect::<Vec<_>>();
-        outputs_data.push(client_info.as_slice().pack());
+        if let Some(client_info) = client_info {
+            outputs_data.push(client_info.as_slice().pack());
+        }
         let outputs = outputs_data
             .iter()
             .map(|data| {
@@ -314,7 +474,7 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
             })
             .collect::<Vec<_>>();
 
-        let witness = {
+        let witnesses = packed_proof_update.map(|packed_proof_update| {
             let input_type_args = packed::BytesOpt::new_builder()
                 .set(Some(packed_proof_update.as_slice().pack()))
                 .build();
@@ -322,19 +482,26 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
                 .input_type(input_type_args)
                 .build();
             witness_args.as_bytes().pack()
-        };
+        });
         let tx = TransactionView::new_advanced_builder()
             .inputs(inputs)
             .outputs(outputs)
             .outputs_data(outputs_data)
-            .witness(witness)
+            .witnesses(witnesses)
             .cell_dep(lc_contract_celldep)
             .cell_dep(lock_contract_celldep)
+            .cell_deps(fee_options.lock_cell_dep.clone())
             .build();
 
-        let fee_rate = 3000;
+        let fee_rate = self.select_fee_rate(&fee_options).await;
         let (tx, mut new_inputs_as_cell_outputs) = self
-            .complete_tx_with_secp256k1_change(tx, address, inputs_capacity, fee_rate)
+            .complete_tx_with_secp256k1_change(
+                tx,
+                address,
+                inputs_capacity,
+                fee_rate,
+                fee_options.min_change_capacity,
+            )
             .await?;
         inputs_as_cell_outputs.append(&mut new_inputs_as_cell_outputs);
         Ok((tx, inputs_as_cell_outputs, new_cells_type_id))
@@ -347,8 +514,10 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         updated_client: PackedClient,
         client_type_args: &PackedClientTypeArgs,
         lock_typeid_args: &H256,
+        retired_lock_typeid_args: &[H256],
         contract_typeid_args: &H256,
         packed_proof_update: PackedProofUpdate,
+        fee_options: FeeOptions,
     ) -> Result<(TransactionView, Vec<packed::CellOutput>), Error> {
         let UpdateCells {
             oldest: oldest_cell,
@@ -363,6 +532,31 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
         // Build lock script
         let (lock_script, lock_contract_celldep) = self.build_lock_script(lock_typeid_args).await?;
 
+        // The inputs may be still locked by a retired lock during a lock-script rotation, which
+        // requires the retired lock contract as a cell dep, while the outputs use the new lock.
+        let mut retired_lock_celldeps: Vec<packed::CellDep> = vec![];
+        for cell in [&info_cell, &oldest_cell] {
+            let lock_code_hash = cell.output.lock().code_hash();
+            if lock_code_hash.as_slice() == lock_script.code_hash().as_slice() {
+                continue;
+            }
+            let Some(retired_args) = retired_lock_typeid_args.iter().find(|args| {
+                let lock_contract = make_typeid_script(args.as_bytes().to_vec());
+                lock_contract.calc_script_hash().as_slice() == lock_code_hash.as_slice()
+            }) else {
+                return Err(Error::other_error(format!(
+                    "multi-client cell is locked by an unaccepted lock {lock_code_hash}"
+                )));
+            };
+            let (_, celldep) = self.build_lock_script(retired_args).await?;
+            if !retired_lock_celldeps
+                .iter()
+                .any(|dep| dep.as_slice() == celldep.as_slice())
+            {
+                retired_lock_celldeps.push(celldep);
+            }
+        }
+
         // Build type script
         let (type_script, lc_contract_celldep) = {
             let lc_contract = make_typeid_script(contract_typeid_args.as_bytes().to_vec());
@@ -448,11 +642,19 @@ pub trait TxAssembler: CellSearcher + TxCompleter {
             .cell_dep(latest_client_cell_dep)
             .cell_dep(lc_contract_celldep)
             .cell_dep(lock_contract_celldep)
+            .cell_deps(retired_lock_celldeps)
+            .cell_deps(fee_options.lock_cell_dep.clone())
             .build();
 
-        let fee_rate = 3000;
+        let fee_rate = self.select_fee_rate(&fee_options).await;
         let (tx, mut new_inputs_as_cell_outputs) = self
-            .complete_tx_with_secp256k1_change(tx, address, inputs_capacity, fee_rate)
+            .complete_tx_with_secp256k1_change(
+                tx,
+                address,
+                inputs_capacity,
+                fee_rate,
+                fee_options.min_change_capacity,
+            )
             .await?;
         inputs_as_cell_outputs.append(&mut new_inputs_as_cell_outputs);
         Ok((tx, inputs_as_cell_outputs))
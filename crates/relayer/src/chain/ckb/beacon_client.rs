@@ -0,0 +1,354 @@
+use ibc_relayer_storage::Slot;
+use ibc_relayer_types::clients::ics07_eth::{
+    header::Header,
+    types::{Update as EthUpdate, H256},
+};
+use tracing::warn;
+
+use crate::error::Error;
+use crate::light_client::eth::{ConsensusRpc, NimbusRpc};
+
+// 32 slots per epoch, 256 epochs per sync committee period
+const SLOTS_PER_SYNC_PERIOD: u64 = 8192;
+
+/// Fetches the finalized beacon headers from beacon API nodes, so the CKB chain is able to
+/// drive the updates of the multi-client by itself.
+pub struct BeaconClient<R = NimbusRpc> {
+    rpc: R,
+    finality_margin_slots: u64,
+}
+
+impl<R: ConsensusRpc> BeaconClient<R> {
+    pub fn new(urls: &[String]) -> Self {
+        Self {
+            rpc: R::new(urls),
+            finality_margin_slots: 0,
+        }
+    }
+
+    /// Only fetches the updates which are at least `slots` below the latest finalized slot.
+    pub fn with_finality_margin(mut self, slots: u64) -> Self {
+        self.finality_margin_slots = slots;
+        self
+    }
+
+    /// Returns at most `limit` continuous updates which start from `start_slot` and end before
+    /// or at the target slot, i.e. the latest finalized slot minus the finality margin. Skipped
+    /// slots are filled with empty headers.
+    ///
+    /// Without `start_slot`, only the update of the target slot is returned.
+    pub async fn fetch_updates(
+        &self,
+        start_slot: Option<Slot>,
+        limit: u64,
+    ) -> Result<Vec<EthUpdate>, Error> {
+        let finality_update = self
+            .rpc
+            .get_finality_update()
+            .await
+            .map_err(|e| Error::rpc_response(format!("beacon finality update: {e}")))?;
+        let finalized_slot = finality_update.finalized_header.slot;
+        let target_slot = finalized_slot.saturating_sub(self.finality_margin_slots);
+        let start_slot = start_slot.unwrap_or(target_slot);
+        if limit == 0 || start_slot > target_slot {
+            return Ok(vec![]);
+        }
+        let end_slot = target_slot.min(start_slot + limit - 1);
+
+        let mut updates = Vec::with_capacity((end_slot - start_slot + 1) as usize);
+        for slot in start_slot..end_slot {
+            let header = self.fetch_header(slot).await?;
+            updates.push(EthUpdate::from_finalized_header(header));
+        }
+        if end_slot == finalized_slot {
+            let period = finalized_slot / SLOTS_PER_SYNC_PERIOD;
+            let committee_update = self
+                .rpc
+                .get_updates(period, 1)
+                .await
+                .map_err(|e| Error::rpc_response(format!("beacon committee updates: {e}")))?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    Error::rpc_response(format!("no beacon committee update of period {period}"))
+                })?;
+            updates.push(EthUpdate::from_finality_update(
+                finality_update,
+                committee_update.next_sync_committee,
+                committee_update.next_sync_committee_branch,
+            ));
+        } else {
+            let header = self.fetch_header(end_slot).await?;
+            updates.push(EthUpdate::from_finalized_header(header));
+        }
+        Ok(updates)
+    }
+
+    /// Returns the latest finalized slot of the beacon chain.
+    pub async fn fetch_finalized_slot(&self) -> Result<Slot, Error> {
+        let finality_update = self
+            .rpc
+            .get_finality_update()
+            .await
+            .map_err(|e| Error::rpc_response(format!("beacon finality update: {e}")))?;
+        Ok(finality_update.finalized_header.slot)
+    }
+
+    /// Checks that the updates come from an accepted beacon chain: the genesis validators root
+    /// reported by the beacon API nodes must be in `accepted`, and the last finalized header of
+    /// the updates must be the one of the nodes at its slot.
+    pub async fn check_updates_origin(
+        &self,
+        updates: &[EthUpdate],
+        accepted: &[H256],
+    ) -> Result<(), Error> {
+        let genesis_validators_root = self
+            .rpc
+            .get_genesis_validators_root()
+            .await
+            .map_err(|e| Error::rpc_response(format!("beacon genesis: {e}")))?;
+        if !accepted.contains(&genesis_validators_root) {
+            return Err(Error::genesis_mismatch(format!(
+                "{genesis_validators_root:#x}"
+            )));
+        }
+        let Some(header) = updates
+            .iter()
+            .rev()
+            .map(|update| &update.finalized_header)
+            .find(|header| !header.is_empty())
+        else {
+            return Ok(());
+        };
+        let found = self
+            .rpc
+            .get_header(header.slot)
+            .await
+            .map_err(|e| Error::rpc_response(format!("beacon header: {e}")))?;
+        if found.as_ref() != Some(header) {
+            return Err(Error::beacon_header_mismatch(header.slot));
+        }
+        Ok(())
+    }
+
+    async fn fetch_header(&self, slot: Slot) -> Result<Header, Error> {
+        let header_opt = self
+            .rpc
+            .get_header(slot)
+            .await
+            .map_err(|e| Error::rpc_response(format!("beacon header: {e}")))?;
+        let header = header_opt.unwrap_or_else(|| {
+            warn!("slot {slot} forked or skipped, replace with empty");
+            Header {
+                slot,
+                ..Default::default()
+            }
+        });
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+
+    use async_trait::async_trait;
+    use eyre::Result;
+    use ibc_relayer_types::clients::ics07_eth::{
+        header::Header,
+        types::{Bootstrap, FinalityUpdate, Update, H256},
+    };
+
+    use super::{BeaconClient, ConsensusRpc, SLOTS_PER_SYNC_PERIOD};
+    use crate::error::ErrorDetail;
+
+    // The slot which is skipped in the recorded responses
+    const SKIPPED_SLOT: u64 = 3818110;
+    // The genesis validators root of the beacon chain which the responses are recorded from
+    const GENESIS_VALIDATORS_ROOT: &str =
+        "4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95";
+
+    struct RecordedRpc {
+        testdata: PathBuf,
+    }
+
+    #[async_trait]
+    impl ConsensusRpc for RecordedRpc {
+        fn new(path: &[String]) -> Self {
+            RecordedRpc {
+                testdata: PathBuf::from(path.get(0).unwrap()),
+            }
+        }
+
+        async fn get_bootstrap(&self, _block_root: &[u8]) -> Result<Bootstrap> {
+            unimplemented!()
+        }
+
+        async fn get_updates(&self, period: u64, count: u8) -> Result<Vec<Update>> {
+            let updates = read_to_string(self.testdata.join("updates.json"))?;
+            let updates: Vec<Update> = serde_json::from_str(&updates)?;
+            Ok(updates
+                .into_iter()
+                .filter(|update| update.finalized_header.slot / SLOTS_PER_SYNC_PERIOD >= period)
+                .take(count as usize)
+                .collect())
+        }
+
+        async fn get_finality_update(&self) -> Result<FinalityUpdate> {
+            let finality = read_to_string(self.testdata.join("finality.json"))?;
+            Ok(serde_json::from_str(&finality)?)
+        }
+
+        async fn get_header(&self, slot: u64) -> Result<Option<Header>> {
+            let header = read_to_string(self.testdata.join("header.json"))?;
+            let response: Vec<serde_json::Value> = serde_json::from_str(&header)?;
+            // the first response is found, and the second one is not found
+            if slot == SKIPPED_SLOT {
+                assert_eq!(response[1]["code"], 404);
+                return Ok(None);
+            }
+            let message = response[0]["data"]["header"]["message"].clone();
+            let header: Header = serde_json::from_value(message)?;
+            Ok(Some(Header { slot, ..header }))
+        }
+
+        async fn get_genesis_validators_root(&self) -> Result<H256> {
+            Ok(GENESIS_VALIDATORS_ROOT.parse()?)
+        }
+    }
+
+    fn new_client() -> BeaconClient<RecordedRpc> {
+        BeaconClient::new(&["src/testdata/".to_owned()])
+    }
+
+    #[tokio::test]
+    async fn test_fetch_updates_till_finalized_slot() {
+        let client = new_client();
+        let finality = client.rpc.get_finality_update().await.unwrap();
+        let finalized_slot = finality.finalized_header.slot;
+        let start_slot = finalized_slot - 3;
+
+        let updates = client.fetch_updates(Some(start_slot), 10).await.unwrap();
+        let slots = updates
+            .iter()
+            .map(|update| update.finalized_header.slot)
+            .collect::<Vec<_>>();
+        assert_eq!(slots, (start_slot..=finalized_slot).collect::<Vec<_>>());
+
+        let skipped = &updates[(SKIPPED_SLOT - start_slot) as usize];
+        assert!(skipped.is_finalized_empty());
+
+        let last = updates.last().unwrap();
+        assert_eq!(last.finalized_header, finality.finalized_header);
+        assert_eq!(last.attested_header, finality.attested_header);
+        let period = finalized_slot / SLOTS_PER_SYNC_PERIOD;
+        let committee = client.rpc.get_updates(period, 1).await.unwrap();
+        assert_eq!(last.next_sync_committee, committee[0].next_sync_committee);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_updates_with_limit() {
+        let client = new_client();
+        let finality = client.rpc.get_finality_update().await.unwrap();
+        let finalized_slot = finality.finalized_header.slot;
+
+        let updates = client
+            .fetch_updates(Some(finalized_slot - 100), 2)
+            .await
+            .unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1].finalized_header.slot, finalized_slot - 99);
+        assert!(!updates
+            .iter()
+            .any(|update| update.finalized_header == finality.finalized_header));
+
+        let updates = client.fetch_updates(None, 2).await.unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].finalized_header, finality.finalized_header);
+
+        let updates = client
+            .fetch_updates(Some(finalized_slot + 1), 2)
+            .await
+            .unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_updates_below_finality_margin() {
+        let client = new_client().with_finality_margin(2);
+        let finality = client.rpc.get_finality_update().await.unwrap();
+        let target_slot = finality.finalized_header.slot - 2;
+
+        // clamped to the target slot, and the last one is a plain header
+        let updates = client
+            .fetch_updates(Some(target_slot - 3), 10)
+            .await
+            .unwrap();
+        let slots = updates
+            .iter()
+            .map(|update| update.finalized_header.slot)
+            .collect::<Vec<_>>();
+        assert_eq!(slots, (target_slot - 3..=target_slot).collect::<Vec<_>>());
+        assert_ne!(
+            updates.last().unwrap().finalized_header,
+            finality.finalized_header
+        );
+
+        let updates = client.fetch_updates(None, 2).await.unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].finalized_header.slot, target_slot);
+
+        // the slots within the margin are not fetched yet
+        let updates = client
+            .fetch_updates(Some(target_slot + 1), 2)
+            .await
+            .unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_finalized_slot() {
+        let client = new_client();
+        let finality = client.rpc.get_finality_update().await.unwrap();
+        assert_eq!(
+            client.fetch_finalized_slot().await.unwrap(),
+            finality.finalized_header.slot
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_updates_origin() {
+        let client = new_client();
+        let finality = client.rpc.get_finality_update().await.unwrap();
+        let finalized_slot = finality.finalized_header.slot;
+        let updates = client
+            .fetch_updates(Some(finalized_slot - 3), 10)
+            .await
+            .unwrap();
+        let accepted: H256 = GENESIS_VALIDATORS_ROOT.parse().unwrap();
+        client
+            .check_updates_origin(&updates, &[H256::repeat_byte(1), accepted])
+            .await
+            .expect("accepted updates");
+
+        let err = client
+            .check_updates_origin(&updates, &[H256::repeat_byte(1)])
+            .await
+            .expect_err("unaccepted genesis");
+        assert!(matches!(err.detail(), ErrorDetail::GenesisMismatch(_)));
+
+        // the last header isn't in the beacon chain
+        let mut forged_updates = updates;
+        forged_updates
+            .last_mut()
+            .unwrap()
+            .finalized_header
+            .state_root = H256::repeat_byte(2);
+        let err = client
+            .check_updates_origin(&forged_updates, &[accepted])
+            .await
+            .expect_err("forged header");
+        assert!(matches!(err.detail(), ErrorDetail::BeaconHeaderMismatch(_)));
+    }
+}
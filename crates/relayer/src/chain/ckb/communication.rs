@@ -1,15 +1,99 @@
 use ckb_jsonrpc_types::{
     BlockNumber, BlockView, CellWithStatus, ChainInfo, HeaderView, JsonBytes, OutPoint,
-    OutputsValidator, RawTxPool, Transaction, TransactionWithStatusResponse, TxPoolInfo,
+    OutputsValidator, RawTxPool, Transaction, TransactionWithStatusResponse, TxPoolInfo, Uint64,
 };
-use ckb_sdk::rpc::ckb_indexer::{Cell, Pagination, SearchKey};
+use ckb_sdk::rpc::ckb_indexer::{Cell, Pagination, SearchKey, Tip, Tx};
 use ckb_types::H256;
-use std::{future::Future, pin::Pin};
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::BTreeMap, future::Future, pin::Pin, sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
 
 use crate::error::Error;
 
 pub type Response<T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'static>>;
 
+/// Limits the number of in-flight requests, the requests beyond the limit are queued
+/// until a previous one is finished.
+#[derive(Clone)]
+pub struct RequestLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RequestLimiter {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        let permits = max_concurrent_requests.clamp(1, Semaphore::MAX_PERMITS);
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(Semaphore::MAX_PERMITS)
+    }
+
+    pub fn limit<T, F>(&self, request: F) -> Response<T>
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+    {
+        let semaphore = Arc::clone(&self.semaphore);
+        Box::pin(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("request limiter is never closed");
+            request.await
+        })
+    }
+}
+
+/// Limits the time of each request, by the timeout of its method if there is one, or the
+/// default timeout otherwise.
+#[derive(Clone)]
+pub struct RequestTimeouts {
+    default: Duration,
+    methods: Arc<BTreeMap<String, Duration>>,
+}
+
+impl RequestTimeouts {
+    pub fn new(default: Duration, methods: BTreeMap<String, Duration>) -> Self {
+        Self {
+            default,
+            methods: Arc::new(methods),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(Duration::MAX, BTreeMap::new())
+    }
+
+    pub fn timeout_of(&self, method: &str) -> Duration {
+        self.methods.get(method).copied().unwrap_or(self.default)
+    }
+
+    pub fn limit<T, F>(&self, method: &str, request: F) -> Response<T>
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+    {
+        let timeout = self.timeout_of(method);
+        let method = method.to_owned();
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, request).await {
+                Ok(result) => result,
+                Err(_) => Err(Error::rpc_response(format!(
+                    "request of `{method}` timed out after {timeout:?}"
+                ))),
+            }
+        })
+    }
+}
+
+/// The fee rates of the recent transactions, in shannons per kilobyte.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeRateStatistics {
+    pub mean: Uint64,
+    pub median: Uint64,
+}
+
 pub trait CkbReader {
     fn get_blockchain_info(&self) -> Response<ChainInfo>;
 
@@ -35,6 +119,19 @@ pub trait CkbReader {
         cursor: Option<JsonBytes>,
     ) -> Response<Pagination<Cell>>;
 
+    /// Searches the transactions which create or consume the cells of `search_key`, in the
+    /// order they are committed.
+    fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> Response<Pagination<Tx>>;
+
+    fn get_indexer_tip(&self) -> Response<Option<Tip>>;
+
+    fn get_fee_rate_statistics(&self) -> Response<Option<FeeRateStatistics>>;
+
     // For debugging purposes.
     fn get_raw_tx_pool(&self, verbose: bool) -> Response<RawTxPool>;
 
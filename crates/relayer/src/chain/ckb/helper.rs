@@ -1,6 +1,7 @@
 use async_trait::async_trait;
+use ckb_jsonrpc_types::OutPoint;
 use ckb_sdk::{
-    rpc::ckb_indexer::SearchKey,
+    rpc::ckb_indexer::{Cell, SearchKey},
     traits::{CellQueryOptions, LiveCell, PrimaryScriptType},
     Address,
 };
@@ -10,10 +11,53 @@ use ckb_types::{
     packed,
     prelude::*,
 };
+use tracing::warn;
 
-use super::{prelude::CkbReader, rpc_client::RpcClient, sighash::get_secp256k1_celldep};
+use super::{
+    prelude::CkbReader, rpc_client::RpcClient, sighash::get_secp256k1_celldep,
+    signer::SIGNATURE_SIZE,
+};
 use crate::error::Error;
 
+// Sorts the cells by capacity in descending order, the ties are broken by out points, so the
+// selection doesn't depend on the order returned by the indexer.
+fn sort_cells_by_capacity(cells: &mut [Cell]) {
+    cells.sort_by(|a, b| {
+        let capacity_a = a.output.capacity.value();
+        let capacity_b = b.output.capacity.value();
+        capacity_b
+            .cmp(&capacity_a)
+            .then_with(|| a.out_point.tx_hash.cmp(&b.out_point.tx_hash))
+            .then_with(|| a.out_point.index.value().cmp(&b.out_point.index.value()))
+    });
+}
+
+// Estimates the size of `tx` in a block once it's signed. The inputs before `first_new_input`
+// and the ones from it are assumed to be signed by different lock groups, so the estimation is
+// never less than the signed transaction.
+fn estimate_signed_tx_size(tx: &TransactionView, first_new_input: usize) -> u64 {
+    let inputs_count = tx.inputs().len();
+    let mut witnesses = (0..inputs_count)
+        .map(|i| tx.witnesses().get(i).unwrap_or_else(|| Bytes::new().pack()))
+        .collect::<Vec<_>>();
+    for i in [0, first_new_input] {
+        if let Some(witness) = witnesses.get_mut(i) {
+            let witness_args = packed::WitnessArgs::from_slice(&witness.raw_data())
+                .unwrap_or_default()
+                .as_builder()
+                .lock(Some(Bytes::from(vec![0u8; SIGNATURE_SIZE])).pack())
+                .build();
+            *witness = witness_args.as_bytes().pack();
+        }
+    }
+    witnesses.extend(tx.witnesses().into_iter().skip(inputs_count));
+    tx.as_advanced_builder()
+        .set_witnesses(witnesses)
+        .build()
+        .data()
+        .serialized_size_in_block() as u64
+}
+
 #[async_trait]
 pub trait CellSearcher: CkbReader {
     async fn search_cell(
@@ -71,6 +115,43 @@ pub trait CellSearcher: CkbReader {
             .await
     }
 
+    /// Searches all the cells locked by `lock_script` page by page.
+    async fn search_all_cells_by_lock(
+        &self,
+        lock_script: &packed::Script,
+    ) -> Result<Vec<LiveCell>, Error> {
+        let mut next = None;
+        let mut cells = vec![];
+        loop {
+            let search: SearchKey =
+                CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+            let result = self
+                .fetch_live_cells(search, 64, next)
+                .await
+                .map_err(|e| Error::rpc_response(e.to_string()))?;
+            if result.objects.is_empty() {
+                return Ok(cells);
+            }
+            cells.extend(result.objects.into_iter().map(LiveCell::from));
+            next = Some(result.last_cursor);
+        }
+    }
+
+    /// Checks whether the cell is still live on the node, since the indexer may lag behind the
+    /// node and report a spent cell as live.
+    async fn is_live_on_node(&self, out_point: &OutPoint) -> Result<bool, Error> {
+        let cell = self
+            .get_live_cell(out_point, false)
+            .await
+            .map_err(|e| Error::rpc_response(e.to_string()))?;
+        Ok(cell.status == "live")
+    }
+
+    /// Searches the cells of `address` until `need_capacity` is reached. The cells of each page
+    /// returned by the indexer are sorted by capacity in descending order before the selection,
+    /// so the selected cells are deterministic whatever the order of the indexer is.
+    ///
+    /// Each selected cell is cross-checked by the node, the ones already spent are dropped.
     async fn search_cells_by_address_and_capacity(
         &self,
         address: &Address,
@@ -96,19 +177,23 @@ pub trait CellSearcher: CkbReader {
                 return Err(Error::send_tx(errmsg));
             }
 
-            let mut live_cells = result
-                .objects
-                .into_iter()
-                .filter_map(|cell| {
-                    if searched_capacity < need_capacity {
-                        searched_capacity += Into::<u64>::into(cell.output.capacity);
-                        Some(cell.into())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>();
-            searched_cells.append(&mut live_cells);
+            let mut cells = result.objects;
+            sort_cells_by_capacity(&mut cells);
+            for cell in cells {
+                if searched_capacity >= need_capacity {
+                    break;
+                }
+                if !self.is_live_on_node(&cell.out_point).await? {
+                    warn!(
+                        "skip cell {:#x}:{} which is live in the indexer but dead on the node",
+                        cell.out_point.tx_hash,
+                        cell.out_point.index.value()
+                    );
+                    continue;
+                }
+                searched_capacity += Into::<u64>::into(cell.output.capacity);
+                searched_cells.push(cell.into());
+            }
             next = Some(result.last_cursor);
         }
         *excessive_capacity = searched_capacity - need_capacity;
@@ -118,37 +203,71 @@ pub trait CellSearcher: CkbReader {
 
 #[async_trait]
 pub trait TxCompleter: CellSearcher {
+    /// Completes the transaction with the capacity of `address` and the fee at `fee_rate` of the
+    /// signed transaction, the rest is put into a change cell, which is at least `min_change_capacity` and its occupied capacity. If there is no
+    /// enough capacity for such a change cell, the rest is merged into the fee instead.
     async fn complete_tx_with_secp256k1_change(
         &self,
-        mut tx: TransactionView,
+        tx: TransactionView,
         address: &Address,
         inputs_capacity: u64,
         fee_rate: u64,
+        min_change_capacity: u64,
     ) -> Result<(TransactionView, Vec<packed::CellOutput>), Error> {
         let lock_script: packed::Script = address.payload().into();
-        let mut change_cell = packed::CellOutput::new_builder()
+        let change_cell = packed::CellOutput::new_builder()
             .lock(lock_script.clone())
             .build_exact_capacity(Capacity::zero())
             .unwrap();
-        let outputs_capacity = {
-            let capacity = tx
-                .outputs_capacity()
-                .map_err(|err| Error::send_tx(err.to_string()))?
-                .as_u64();
-            let fee = tx.data().as_bytes().len() as u64 * fee_rate;
-            capacity + fee + Unpack::<u64>::unpack(&change_cell.capacity())
-        };
-        let mut excessive_capacity = 0;
-        let mut inputs_cell_as_output = vec![];
-        if outputs_capacity > inputs_capacity {
-            let need_capacity = outputs_capacity - inputs_capacity;
-            let live_cells = self
-                .search_cells_by_address_and_capacity(
-                    address,
-                    need_capacity,
-                    &mut excessive_capacity,
-                )
-                .await?;
+        let min_change_capacity =
+            min_change_capacity.max(Unpack::<u64>::unpack(&change_cell.capacity()));
+        let outputs_capacity = tx
+            .outputs_capacity()
+            .map_err(|err| Error::send_tx(err.to_string()))?
+            .as_u64();
+        let first_new_input = tx.inputs().len();
+        // The fee is sized on the signed transaction, whose inputs and change depend on the fee,
+        // so it's completed again until the fee covers it.
+        let mut fee = 0;
+        loop {
+            let capacity = outputs_capacity + fee;
+            let mut live_cells = vec![];
+            let change_capacity = if inputs_capacity >= capacity + min_change_capacity {
+                Some(inputs_capacity - capacity)
+            } else {
+                let mut excessive_capacity = 0;
+                let need_capacity = capacity + min_change_capacity - inputs_capacity;
+                match self
+                    .search_cells_by_address_and_capacity(
+                        address,
+                        need_capacity,
+                        &mut excessive_capacity,
+                    )
+                    .await
+                {
+                    Ok(cells) => {
+                        live_cells = cells;
+                        Some(min_change_capacity + excessive_capacity)
+                    }
+                    Err(err) => {
+                        // No enough capacity for a change cell, only cover the outputs and the
+                        // fee, then the rest is merged into the fee.
+                        if capacity > inputs_capacity {
+                            let need_capacity = capacity - inputs_capacity;
+                            live_cells = self
+                                .search_cells_by_address_and_capacity(
+                                    address,
+                                    need_capacity,
+                                    &mut excessive_capacity,
+                                )
+                                .await
+                                .map_err(|_| err)?;
+                        }
+                        None
+                    }
+                }
+            };
+            let mut inputs_cell_as_output = vec![];
             let inputs_cell = live_cells
                 .into_iter()
                 .map(|cell| {
@@ -158,21 +277,25 @@ pub trait TxCompleter: CellSearcher {
                         .build()
                 })
                 .collect::<Vec<_>>();
-            tx = tx.as_advanced_builder().inputs(inputs_cell).build();
-        } else {
-            excessive_capacity = inputs_capacity - outputs_capacity;
-        };
-        change_cell = change_cell
-            .as_builder()
-            .build_exact_capacity(Capacity::shannons(excessive_capacity))
-            .unwrap();
-        tx = tx
-            .as_advanced_builder()
-            .output(change_cell)
-            .output_data(Bytes::new().pack())
-            .cell_dep(get_secp256k1_celldep(address.network()))
-            .build();
-        Ok((tx, inputs_cell_as_output))
+            let mut builder = tx.as_advanced_builder();
+            builder.inputs(inputs_cell);
+            if let Some(change_capacity) = change_capacity {
+                let change_cell = change_cell
+                    .clone()
+                    .as_builder()
+                    .capacity(change_capacity.pack())
+                    .build();
+                builder.output(change_cell).output_data(Bytes::new().pack());
+            }
+            let completed_tx = builder
+                .cell_dep(get_secp256k1_celldep(address.network()))
+                .build();
+            let need_fee = estimate_signed_tx_size(&completed_tx, first_new_input) * fee_rate;
+            if need_fee <= fee {
+                return Ok((completed_tx, inputs_cell_as_output));
+            }
+            fee = need_fee;
+        }
     }
 }
 
@@ -6,17 +6,31 @@ use ckb_jsonrpc_types::{
     OutputsValidator, RawTxPool, ResponseFormat, Transaction, TransactionView,
     TransactionWithStatusResponse, TxPoolInfo, TxStatus,
 };
-use ckb_sdk::rpc::ckb_indexer::{Cell, Pagination, SearchKey};
+use ckb_sdk::rpc::ckb_indexer::{Cell, Pagination, SearchKey, Tip, Tx};
 use ckb_types::{packed, prelude::*, H256};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use tendermint_rpc::Url;
 
+use super::communication::FeeRateStatistics;
 use super::prelude::{CkbReader, CkbWriter, Response as Rpc};
 use crate::error::Error;
 
+// A dev chain is answered until `set_blockchain_info` is called, since the network is required
+// while bootstrapping.
+const DEFAULT_CHAIN_INFO: &str = r#"
+    {
+      "alerts": [],
+      "chain": "ckb-dev",
+      "difficulty": "0x10000",
+      "epoch": "0x100",
+      "is_initial_block_download": true,
+      "median_time": "0x5cd2b105"
+    }"#;
+
 #[derive(Clone)]
 pub struct RpcClient {
     data: Arc<RwLock<RpcData>>,
@@ -28,16 +42,118 @@ struct RpcData {
 
     cells: HashMap<String, Vec<Cell>>,
 
+    // Transactions which create or consume the cells, by the search keys of the cells
+    indexed_transactions: HashMap<String, Vec<Tx>>,
+
     transactions: Vec<Transaction>,
+
+    // Transactions which are committed without being sent, e.g. the previous transactions of
+    // the inputs
+    committed_transactions: HashMap<H256, Transaction>,
+
+    // `None` means the indexer is always synced
+    indexer_tip: Option<u64>,
+
+    // Median fee rate in shannons per kilobyte, `None` means no statistics
+    fee_rate: Option<u64>,
+
+    // Called with each sent transaction, to inject events in the middle of a cycle
+    send_transaction_hook: Option<Arc<dyn Fn(&Transaction) + Send + Sync>>,
+
+    // Number of the following transactions which fail to be sent
+    failing_sends: usize,
+
+    // Number of the following searches of live cells or transactions which fail
+    failing_fetches: usize,
+
+    // Number of the following queries of transactions which answer they are still pending
+    pending_polls: usize,
+
+    // Timestamp of the tip block in milliseconds
+    tip_timestamp: u64,
+
+    // Timestamps of the blocks by their numbers in milliseconds, the others are 0
+    block_timestamps: HashMap<u64, u64>,
+
+    // `None` means the tip is always far beyond any committed block
+    tip_number: Option<u64>,
+
+    // Number of the following queries which answer a transaction is committed, it's reorged
+    // out after them, `None` means it's never reorged out
+    reorg_after_polls: Option<usize>,
+
+    // Cells which are spent on the node, even if the indexer still reports them as live
+    dead_out_points: Vec<OutPoint>,
 }
 
 impl RpcClient {
     pub fn new(_ckb_uri: &Url, _indexer_uri: &Url) -> Self {
+        let data = RpcData {
+            chain_info: Some(DEFAULT_CHAIN_INFO.to_owned()),
+            ..Default::default()
+        };
         Self {
-            data: Arc::new(RwLock::new(RpcData::default())),
+            data: Arc::new(RwLock::new(data)),
         }
     }
 
+    // The mock client answers immediately, so there is nothing to limit.
+    pub fn with_max_concurrent_requests(self, _max_concurrent_requests: usize) -> Self {
+        self
+    }
+
+    pub fn with_timeouts(
+        self,
+        _rpc_timeout: Duration,
+        _method_timeouts: BTreeMap<String, Duration>,
+    ) -> Self {
+        self
+    }
+
+    pub fn set_indexer_tip(&self, block_number: Option<u64>) {
+        self.data.write().unwrap().indexer_tip = block_number;
+    }
+
+    pub fn set_fee_rate_statistics(&self, median_fee_rate: Option<u64>) {
+        self.data.write().unwrap().fee_rate = median_fee_rate;
+    }
+
+    pub fn set_send_transaction_hook(&self, hook: impl Fn(&Transaction) + Send + Sync + 'static) {
+        self.data.write().unwrap().send_transaction_hook = Some(Arc::new(hook));
+    }
+
+    pub fn fail_next_sends(&self, count: usize) {
+        self.data.write().unwrap().failing_sends = count;
+    }
+
+    pub fn fail_next_fetches(&self, count: usize) {
+        self.data.write().unwrap().failing_fetches = count;
+    }
+
+    pub fn set_pending_polls(&self, count: usize) {
+        self.data.write().unwrap().pending_polls = count;
+    }
+
+    pub fn set_tip_number(&self, block_number: Option<u64>) {
+        self.data.write().unwrap().tip_number = block_number;
+    }
+
+    pub fn set_reorg_after_polls(&self, count: Option<usize>) {
+        self.data.write().unwrap().reorg_after_polls = count;
+    }
+
+    pub fn set_tip_timestamp(&self, timestamp: u64) {
+        self.data.write().unwrap().tip_timestamp = timestamp;
+    }
+
+    pub fn set_block_timestamp(&self, block_number: u64, timestamp: u64) {
+        self.data
+            .write()
+            .unwrap()
+            .block_timestamps
+            .insert(block_number, timestamp);
+    }
+
     pub fn set_blockchain_info(&self, chain_info: Option<&str>) {
         self.data.write().unwrap().chain_info = chain_info.map(ToOwned::to_owned);
     }
@@ -53,10 +169,47 @@ impl RpcClient {
             .or_insert_with(|| vec![cell]);
     }
 
+    pub fn remove_cells(&self, key: &SearchKey) {
+        let key_string = serde_json::to_string(key).unwrap();
+        self.data.write().unwrap().cells.remove(&key_string);
+    }
+
+    pub fn add_indexed_transaction(&self, key: &SearchKey, tx: Tx) {
+        let key_string = serde_json::to_string(key).unwrap();
+        self.data
+            .write()
+            .unwrap()
+            .indexed_transactions
+            .entry(key_string)
+            .or_default()
+            .push(tx);
+    }
+
+    pub fn consume_cells(&self, out_points: &[OutPoint]) {
+        for cells in self.data.write().unwrap().cells.values_mut() {
+            cells.retain(|cell| !out_points.contains(&cell.out_point));
+        }
+    }
+
+    pub fn mark_dead_on_node(&self, out_point: OutPoint) {
+        self.data.write().unwrap().dead_out_points.push(out_point);
+    }
+
     pub fn clear_cells(&self) {
         self.data.write().unwrap().cells = HashMap::default();
     }
 
+    pub fn add_committed_transaction(&self, tx: Transaction) -> H256 {
+        let packed_tx: packed::Transaction = tx.clone().into();
+        let tx_hash: H256 = packed_tx.calc_tx_hash().unpack();
+        self.data
+            .write()
+            .unwrap()
+            .committed_transactions
+            .insert(tx_hash.clone(), tx);
+        tx_hash
+    }
+
     pub fn get_transaction_by_index(&self, index: usize) -> Option<Transaction> {
         self.data.read().unwrap().transactions.get(index).cloned()
     }
@@ -64,6 +217,51 @@ impl RpcClient {
     pub fn get_transactions_len(&self) -> usize {
         self.data.read().unwrap().transactions.len()
     }
+
+    // Pages the objects which the indexer finds, the cursor is the big-endian index of the next
+    // object, or `u32::MAX` after the last one.
+    fn search_indexer<T>(
+        &self,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+        find: impl FnOnce(&RpcData) -> Vec<T>,
+    ) -> Result<Pagination<T>, Error> {
+        let mut data = self.data.write().unwrap();
+        if data.failing_fetches > 0 {
+            data.failing_fetches -= 1;
+            return Err(Error::rpc_response("connection reset by peer".to_owned()));
+        }
+        let index = cursor
+            .map(|json_bytes| {
+                let bytes = json_bytes.as_bytes();
+                let mut u32_be_bytes = [0u8; 4];
+                u32_be_bytes[..].copy_from_slice(&bytes[..4]);
+                u32::from_be_bytes(u32_be_bytes)
+            })
+            .unwrap_or(0);
+        let mut found = find(&data);
+        let found_count = found.len() as u32;
+        let resp = if found_count > index {
+            let mut objects = found.split_off(index as usize);
+            objects.truncate(limit as usize);
+            let new_index = index + limit;
+            let new_index = if found_count > new_index {
+                new_index
+            } else {
+                u32::MAX
+            };
+            Pagination {
+                objects,
+                last_cursor: JsonBytes::from_vec(new_index.to_be_bytes().to_vec()),
+            }
+        } else {
+            Pagination {
+                objects: Default::default(),
+                last_cursor: JsonBytes::from_vec(u32::MAX.to_be_bytes().to_vec()),
+            }
+        };
+        Ok(resp)
+    }
 }
 
 impl CkbReader for RpcClient {
@@ -77,7 +275,27 @@ impl CkbReader for RpcClient {
     }
 
     fn get_block_by_number(&self, number: BlockNumber) -> Rpc<BlockView> {
-        todo!()
+        let block_number: u64 = number.into();
+        let timestamp = self
+            .data
+            .read()
+            .unwrap()
+            .block_timestamps
+            .get(&block_number)
+            .copied()
+            .unwrap_or_default();
+        let resp = BlockView {
+            header: HeaderView {
+                inner: Header {
+                    number,
+                    timestamp: timestamp.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Box::pin(async { Ok(resp) })
     }
 
     fn get_block(&self, hash: &H256) -> Rpc<BlockView> {
@@ -95,9 +313,11 @@ impl CkbReader for RpcClient {
     }
 
     fn get_tip_header(&self) -> Rpc<HeaderView> {
+        let data = self.data.read().unwrap();
         let resp = HeaderView {
             inner: Header {
-                number: u64::MAX.into(),
+                number: data.tip_number.unwrap_or(u64::MAX).into(),
+                timestamp: data.tip_timestamp.into(),
                 ..Default::default()
             },
             ..Default::default()
@@ -106,7 +326,45 @@ impl CkbReader for RpcClient {
     }
 
     fn get_transaction(&self, hash: &H256) -> Rpc<Option<TransactionWithStatusResponse>> {
-        let transaction = ResponseFormat::<TransactionView>::json(Default::default());
+        let found_tx = {
+            let mut data = self.data.write().unwrap();
+            if data.pending_polls > 0 {
+                data.pending_polls -= 1;
+                let resp = TransactionWithStatusResponse {
+                    transaction: None,
+                    tx_status: TxStatus::pending(),
+                    cycles: None,
+                };
+                return Box::pin(async { Ok(Some(resp)) });
+            }
+            match data.reorg_after_polls.as_mut() {
+                Some(0) => {
+                    let resp = TransactionWithStatusResponse {
+                        transaction: None,
+                        tx_status: TxStatus::unknown(),
+                        cycles: None,
+                    };
+                    return Box::pin(async { Ok(Some(resp)) });
+                }
+                Some(polls) => *polls -= 1,
+                None => {}
+            }
+            data.committed_transactions.get(hash).cloned().or_else(|| {
+                data.transactions.iter().find_map(|tx| {
+                    let packed_tx: packed::Transaction = tx.clone().into();
+                    let tx_hash: H256 = packed_tx.calc_tx_hash().unpack();
+                    (&tx_hash == hash).then(|| tx.clone())
+                })
+            })
+        };
+        // any unknown transaction is committed with an empty body
+        let tx_view = found_tx
+            .map(|tx| {
+                let packed_tx: packed::Transaction = tx.into();
+                packed_tx.into_view().into()
+            })
+            .unwrap_or_default();
+        let transaction = ResponseFormat::<TransactionView>::json(tx_view);
         let resp = TransactionWithStatusResponse {
             transaction: Some(transaction),
             tx_status: TxStatus::committed(hash.clone()),
@@ -115,8 +373,19 @@ impl CkbReader for RpcClient {
         Box::pin(async { Ok(Some(resp)) })
     }
 
+    // Only the status is answered, the cell itself is never used.
     fn get_live_cell(&self, out_point: &OutPoint, with_data: bool) -> Rpc<CellWithStatus> {
-        todo!()
+        let dead = self
+            .data
+            .read()
+            .unwrap()
+            .dead_out_points
+            .contains(out_point);
+        let resp = CellWithStatus {
+            cell: None,
+            status: if dead { "dead" } else { "live" }.to_owned(),
+        };
+        Box::pin(async { Ok(resp) })
     }
 
     fn get_txs_by_hashes(
@@ -133,43 +402,26 @@ impl CkbReader for RpcClient {
         cursor: Option<JsonBytes>,
     ) -> Rpc<Pagination<Cell>> {
         let key_string = serde_json::to_string(&search_key).unwrap();
-        let index = cursor
-            .map(|json_bytes| {
-                let bytes = json_bytes.as_bytes();
-                let mut u32_be_bytes = [0u8; 4];
-                u32_be_bytes[..].copy_from_slice(&bytes[..4]);
-                u32::from_be_bytes(u32_be_bytes)
-            })
-            .unwrap_or(0);
-        let mut cells = self
-            .data
-            .read()
-            .unwrap()
-            .cells
-            .get(&key_string)
-            .map(ToOwned::to_owned)
-            .unwrap_or_default();
-        let cells_count = cells.len() as u32;
-        let resp = if cells_count > index {
-            let mut objects = cells.split_off(index as usize);
-            objects.truncate(limit as usize);
-            let new_index = index + limit;
-            let new_index = if cells_count > new_index {
-                new_index
-            } else {
-                u32::MAX
-            };
-            Pagination {
-                objects,
-                last_cursor: JsonBytes::from_vec(new_index.to_be_bytes().to_vec()),
-            }
-        } else {
-            Pagination {
-                objects: Default::default(),
-                last_cursor: JsonBytes::from_vec(u32::MAX.to_be_bytes().to_vec()),
-            }
-        };
-        Box::pin(async { Ok(resp) })
+        let resp = self.search_indexer(limit, cursor, |data| {
+            data.cells.get(&key_string).cloned().unwrap_or_default()
+        });
+        Box::pin(async { resp })
+    }
+
+    fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> Rpc<Pagination<Tx>> {
+        let key_string = serde_json::to_string(&search_key).unwrap();
+        let resp = self.search_indexer(limit, cursor, |data| {
+            data.indexed_transactions
+                .get(&key_string)
+                .cloned()
+                .unwrap_or_default()
+        });
+        Box::pin(async { resp })
     }
 
     fn get_raw_tx_pool(&self, verbose: bool) -> Rpc<RawTxPool> {
@@ -179,6 +431,28 @@ impl CkbReader for RpcClient {
     fn tx_pool_info(&self) -> Rpc<TxPoolInfo> {
         todo!()
     }
+
+    fn get_indexer_tip(&self) -> Rpc<Option<Tip>> {
+        let block_number = self.data.read().unwrap().indexer_tip.unwrap_or(u64::MAX);
+        let resp = Tip {
+            block_hash: Default::default(),
+            block_number: block_number.into(),
+        };
+        Box::pin(async { Ok(Some(resp)) })
+    }
+
+    fn get_fee_rate_statistics(&self) -> Rpc<Option<FeeRateStatistics>> {
+        let resp = self
+            .data
+            .read()
+            .unwrap()
+            .fee_rate
+            .map(|fee_rate| FeeRateStatistics {
+                mean: fee_rate.into(),
+                median: fee_rate.into(),
+            });
+        Box::pin(async { Ok(resp) })
+    }
 }
 
 impl CkbWriter for RpcClient {
@@ -189,7 +463,20 @@ impl CkbWriter for RpcClient {
     ) -> Rpc<H256> {
         let packed_tx: packed::Transaction = tx.clone().into();
         let tx_hash = packed_tx.calc_tx_hash();
-        self.data.write().unwrap().transactions.push(tx.clone());
+        let hook = {
+            let mut data = self.data.write().unwrap();
+            if data.failing_sends > 0 {
+                data.failing_sends -= 1;
+                return Box::pin(async {
+                    Err(Error::rpc_response("connection reset by peer".to_owned()))
+                });
+            }
+            data.transactions.push(tx.clone());
+            data.send_transaction_hook.clone()
+        };
+        if let Some(hook) = hook {
+            hook(tx);
+        }
         Box::pin(async move { Ok(tx_hash.unpack()) })
     }
 }
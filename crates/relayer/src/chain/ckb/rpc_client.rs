@@ -4,14 +4,17 @@ use ckb_jsonrpc_types::{
     BlockNumber, BlockView, CellWithStatus, ChainInfo, HeaderView, JsonBytes, OutPoint,
     OutputsValidator, RawTxPool, Transaction, TransactionWithStatusResponse, TxPoolInfo, Uint32,
 };
-use ckb_sdk::rpc::ckb_indexer::{Cell, Order, Pagination, SearchKey};
+use ckb_sdk::rpc::ckb_indexer::{Cell, Order, Pagination, SearchKey, Tip, Tx};
 use ckb_types::H256;
 use futures::FutureExt;
 use reqwest::Client;
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tendermint_rpc::{Error as TmError, Url};
 
+use super::communication::{FeeRateStatistics, RequestLimiter, RequestTimeouts};
 use super::prelude::{CkbReader, CkbWriter, Response as Rpc};
 use crate::error::Error;
 
@@ -39,7 +42,8 @@ macro_rules! jsonrpc {
         };
         let reqwest_url = reqwest::Url::parse(&url.to_string()).unwrap();
         let c = $self.raw.post(reqwest_url).json(&req_json);
-        async {
+        // the time queued in the limiter isn't counted in the timeout
+        $self.limiter.limit($self.timeouts.limit($method, async {
             let resp = c
                 .send()
                 .await
@@ -57,7 +61,7 @@ macro_rules! jsonrpc {
                     Err(Error::rpc_response(format!("{:?}", e)))
                 }
             }
-        }
+        }))
     }}
 }
 
@@ -67,6 +71,8 @@ pub struct RpcClient {
     ckb_uri: Url,
     indexer_uri: Url,
     id: Arc<AtomicU64>,
+    limiter: RequestLimiter,
+    timeouts: RequestTimeouts,
 }
 
 impl RpcClient {
@@ -76,25 +82,41 @@ impl RpcClient {
             ckb_uri: ckb_uri.clone(),
             indexer_uri: indexer_uri.clone(),
             id: Arc::new(AtomicU64::new(0)),
+            limiter: RequestLimiter::unlimited(),
+            timeouts: RequestTimeouts::unlimited(),
         }
     }
+
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.limiter = RequestLimiter::new(max_concurrent_requests);
+        self
+    }
+
+    pub fn with_timeouts(
+        mut self,
+        rpc_timeout: Duration,
+        method_timeouts: BTreeMap<String, Duration>,
+    ) -> Self {
+        self.timeouts = RequestTimeouts::new(rpc_timeout, method_timeouts);
+        self
+    }
 }
 
 impl CkbReader for RpcClient {
     fn get_blockchain_info(&self) -> Rpc<ChainInfo> {
-        jsonrpc!("get_blockchain_info", Target::CKB, self, ChainInfo).boxed()
+        jsonrpc!("get_blockchain_info", Target::CKB, self, ChainInfo)
     }
 
     fn get_block_by_number(&self, number: BlockNumber) -> Rpc<BlockView> {
-        jsonrpc!("get_block_by_number", Target::CKB, self, BlockView, number).boxed()
+        jsonrpc!("get_block_by_number", Target::CKB, self, BlockView, number)
     }
 
     fn get_block(&self, hash: &H256) -> Rpc<BlockView> {
-        jsonrpc!("get_block", Target::CKB, self, BlockView, hash).boxed()
+        jsonrpc!("get_block", Target::CKB, self, BlockView, hash)
     }
 
     fn get_tip_header(&self) -> Rpc<HeaderView> {
-        jsonrpc!("get_tip_header", Target::CKB, self, HeaderView).boxed()
+        jsonrpc!("get_tip_header", Target::CKB, self, HeaderView)
     }
 
     fn get_transaction(&self, hash: &H256) -> Rpc<Option<TransactionWithStatusResponse>> {
@@ -105,7 +127,6 @@ impl CkbReader for RpcClient {
             Option<TransactionWithStatusResponse>,
             hash
         )
-        .boxed()
     }
 
     fn get_live_cell(&self, out_point: &OutPoint, with_data: bool) -> Rpc<CellWithStatus> {
@@ -117,7 +138,6 @@ impl CkbReader for RpcClient {
             out_point,
             with_data
         )
-        .boxed()
     }
 
     fn get_txs_by_hashes(
@@ -160,15 +180,48 @@ impl CkbReader for RpcClient {
             limit,
             cursor,
         )
-        .boxed()
+    }
+
+    fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> Rpc<Pagination<Tx>> {
+        let order = Order::Asc;
+        let limit = Uint32::from(limit);
+
+        jsonrpc!(
+            "get_transactions",
+            Target::Indexer,
+            self,
+            Pagination<Tx>,
+            search_key,
+            order,
+            limit,
+            cursor,
+        )
+    }
+
+    fn get_indexer_tip(&self) -> Rpc<Option<Tip>> {
+        jsonrpc!("get_indexer_tip", Target::Indexer, self, Option<Tip>)
+    }
+
+    fn get_fee_rate_statistics(&self) -> Rpc<Option<FeeRateStatistics>> {
+        jsonrpc!(
+            "get_fee_rate_statistics",
+            Target::CKB,
+            self,
+            Option<FeeRateStatistics>
+        )
     }
 
     fn get_raw_tx_pool(&self, verbose: bool) -> Rpc<RawTxPool> {
-        jsonrpc!("get_raw_tx_pool", Target::CKB, self, RawTxPool, verbose).boxed()
+        jsonrpc!("get_raw_tx_pool", Target::CKB, self, RawTxPool, verbose)
     }
 
     fn tx_pool_info(&self) -> Rpc<TxPoolInfo> {
-        jsonrpc!("tx_pool_info", Target::CKB, self, TxPoolInfo).boxed()
+        jsonrpc!("tx_pool_info", Target::CKB, self, TxPoolInfo)
     }
 }
 
@@ -186,6 +239,5 @@ impl CkbWriter for RpcClient {
             tx,
             outputs_validator
         )
-        .boxed()
     }
 }
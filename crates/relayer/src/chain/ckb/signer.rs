@@ -1,15 +1,22 @@
-use ckb_hash::new_blake2b;
+use ckb_hash::{blake2b_256, new_blake2b};
 use ckb_types::{
     bytes::Bytes,
     core::TransactionView,
     packed::{self, Byte32, CellOutput, WitnessArgs},
     prelude::*,
 };
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, Secp256k1,
+};
 use std::collections::HashMap;
 
+use crate::error::Error as RelayerError;
 use crate::keyring::errors::Error;
 use crate::keyring::SigningKeyPair;
 
+pub(crate) const SIGNATURE_SIZE: usize = 65;
+
 // sign a whole [tx] using private [key], the [extra_witnesses] is some external args which just placed into witness part
 // the function just supposes two or more cells that are in one group are all close together
 pub fn sign<S: SigningKeyPair + Clone>(
@@ -17,6 +24,18 @@ pub fn sign<S: SigningKeyPair + Clone>(
     inputs: &[CellOutput],
     extra_witnesses: Vec<WitnessArgs>,
     signer: S,
+) -> Result<TransactionView, Error> {
+    sign_with_keys(tx, inputs, extra_witnesses, signer, &[])
+}
+
+// sign a whole [tx] like `sign`, but the inputs whose lock hashes are in [lock_signers] are signed
+// by the paired keys, e.g. the fee inputs of a fee payer, the others are signed by [signer]
+pub fn sign_with_keys<S: SigningKeyPair + Clone>(
+    tx: TransactionView,
+    inputs: &[CellOutput],
+    extra_witnesses: Vec<WitnessArgs>,
+    signer: S,
+    lock_signers: &[(Byte32, S)],
 ) -> Result<TransactionView, Error> {
     #[allow(clippy::mutable_key_type)]
     let mut last_lockhashes: HashMap<Byte32, (WitnessArgs, usize, Vec<packed::Bytes>)> =
@@ -50,10 +69,15 @@ pub fn sign<S: SigningKeyPair + Clone>(
             witness
         })
         .collect::<Vec<_>>();
-    for (_, (witness, i, group_witnesses)) in last_lockhashes {
+    for (lockhash, (witness, i, group_witnesses)) in last_lockhashes {
+        let signer = lock_signers
+            .iter()
+            .find(|(h, _)| h == &lockhash)
+            .map(|(_, s)| s.clone())
+            .unwrap_or_else(|| signer.clone());
         signed_witnesses[i] = sign_input(
             tx.hash(),
-            signer.clone(),
+            signer,
             &witness,
             &group_witnesses,
             &extra_witnesses,
@@ -78,33 +102,122 @@ fn sign_input(
     group_witnesses: &Vec<packed::Bytes>,
     extra_witnesses: &Vec<WitnessArgs>,
 ) -> Result<packed::Bytes, Error> {
-    let mut blake2b = new_blake2b();
-    blake2b.update(&tx_hash.raw_data());
     let signed_witness = witness
         .clone()
         .as_builder()
-        .lock(Some(Bytes::from(vec![0u8; 65])).pack())
+        .lock(Some(Bytes::from(vec![0u8; SIGNATURE_SIZE])).pack())
         .build();
-    let witness_len = signed_witness.as_bytes().len() as u64;
+    let extra_witnesses = extra_witnesses
+        .iter()
+        .map(|witness| witness.as_bytes())
+        .collect::<Vec<_>>();
+    let digest = signing_digest(tx_hash, &signed_witness, group_witnesses, &extra_witnesses);
+    let signature = signer.sign(&digest)?;
+    Ok(signed_witness
+        .as_builder()
+        .lock(Some(Bytes::from(signature)).pack())
+        .build()
+        .as_bytes()
+        .pack())
+}
+
+// calculate the digest to sign, the lock of [witness] should be filled with zeros
+fn signing_digest(
+    tx_hash: Byte32,
+    witness: &WitnessArgs,
+    group_witnesses: &[packed::Bytes],
+    extra_witnesses: &[Bytes],
+) -> [u8; 32] {
+    let mut blake2b = new_blake2b();
+    blake2b.update(&tx_hash.raw_data());
+    let witness_len = witness.as_bytes().len() as u64;
     blake2b.update(&witness_len.to_le_bytes());
-    blake2b.update(&signed_witness.as_bytes());
+    blake2b.update(&witness.as_bytes());
     for group_witness in group_witnesses {
         let witness_len = group_witness.raw_data().len() as u64;
         blake2b.update(&witness_len.to_le_bytes());
         blake2b.update(&group_witness.raw_data());
     }
     for extra_witness in extra_witnesses {
-        let witness_len = extra_witness.as_bytes().len() as u64;
+        let witness_len = extra_witness.len() as u64;
         blake2b.update(&witness_len.to_le_bytes());
-        blake2b.update(&extra_witness.as_bytes());
+        blake2b.update(extra_witness);
     }
     let mut digest = [0u8; 32];
     blake2b.finalize(&mut digest);
-    let signature = signer.sign(&digest)?;
-    Ok(signed_witness
-        .as_builder()
-        .lock(Some(Bytes::from(signature)).pack())
-        .build()
-        .as_bytes()
-        .pack())
+    digest
+}
+
+// verify a signed [tx] by recovering the pubkey from the first witness of every lock group,
+// its blake160 hash must be equal to [lock_args] of the signer
+pub fn verify(
+    tx: &TransactionView,
+    inputs: &[CellOutput],
+    lock_args: &[u8],
+) -> Result<(), RelayerError> {
+    verify_with_keys(tx, inputs, lock_args, &[])
+}
+
+// verify a signed [tx] like `verify`, but the lock groups whose lock hashes are in
+// [lock_signers_args] must be signed by the signers of the paired lock args
+pub fn verify_with_keys(
+    tx: &TransactionView,
+    inputs: &[CellOutput],
+    lock_args: &[u8],
+    lock_signers_args: &[(Byte32, Bytes)],
+) -> Result<(), RelayerError> {
+    let witnesses = tx.witnesses();
+    // the extra witnesses are placed after the witnesses of inputs
+    let extra_witnesses = (inputs.len()..witnesses.len())
+        .filter_map(|i| witnesses.get(i))
+        .map(|witness| witness.raw_data())
+        .collect::<Vec<_>>();
+    let mut groups: Vec<(Byte32, usize, Vec<packed::Bytes>)> = vec![];
+    for (i, input) in inputs.iter().enumerate() {
+        let lockhash = input.lock().calc_script_hash();
+        let witness = witnesses.get(i).unwrap_or_else(|| Bytes::new().pack());
+        if let Some((_, _, group_witnesses)) = groups.iter_mut().find(|(h, _, _)| h == &lockhash) {
+            group_witnesses.push(witness);
+        } else {
+            groups.push((lockhash, i, vec![]));
+        }
+    }
+    let secp = Secp256k1::verification_only();
+    for (lockhash, i, group_witnesses) in groups {
+        let lock_args = lock_signers_args
+            .iter()
+            .find(|(h, _)| h == &lockhash)
+            .map(|(_, args)| args.as_ref())
+            .unwrap_or(lock_args);
+        let invalid = |reason: &str| RelayerError::ckb_signature_verification(i, reason.to_owned());
+        let witness = witnesses.get(i).ok_or_else(|| invalid("missing witness"))?;
+        let witness_args = WitnessArgs::from_slice(&witness.raw_data())
+            .map_err(|_| invalid("malformed witness args"))?;
+        let signature: Bytes = witness_args
+            .lock()
+            .to_opt()
+            .ok_or_else(|| invalid("missing signature"))?
+            .unpack();
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(invalid("wrong signature size"));
+        }
+        let placeholder = witness_args
+            .as_builder()
+            .lock(Some(Bytes::from(vec![0u8; SIGNATURE_SIZE])).pack())
+            .build();
+        let digest = signing_digest(tx.hash(), &placeholder, &group_witnesses, &extra_witnesses);
+        let pubkey = {
+            let message = Message::from_slice(&digest).expect("32 bytes digest");
+            let recovery_id = RecoveryId::from_i32(signature[64] as i32)
+                .map_err(|_| invalid("wrong recovery id"))?;
+            let signature = RecoverableSignature::from_compact(&signature[0..64], recovery_id)
+                .map_err(|_| invalid("malformed signature"))?;
+            secp.recover_ecdsa(&message, &signature)
+                .map_err(|_| invalid("unrecoverable signature"))?
+        };
+        if &blake2b_256(pubkey.serialize())[0..20] != lock_args {
+            return Err(invalid("signer mismatch"));
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,114 @@
+use std::io::{BufRead as _, BufReader, ErrorKind, Read as _, Write as _};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, info, warn};
+
+use crate::error::Error;
+
+pub const STATUS_PATH: &str = "/status";
+
+// Interval of checking the shutdown flag while no request is coming.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+// Limits of reading a request and writing its response, a stalled or oversized request is
+// dropped, since the requests are served one by one.
+const IO_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_REQUEST_SIZE: u64 = 8 * 1024;
+
+/// Serves the latest relay status as JSON to `GET /status`, the status is published by the
+/// chain after it's changed. The server is stopped when it's dropped.
+pub struct StatusServer {
+    local_addr: SocketAddr,
+    status: Arc<RwLock<serde_json::Value>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatusServer {
+    pub fn spawn(addr: SocketAddr) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).map_err(Error::io)?;
+        listener.set_nonblocking(true).map_err(Error::io)?;
+        let local_addr = listener.local_addr().map_err(Error::io)?;
+        let status = Arc::new(RwLock::new(serde_json::Value::Null));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let status = Arc::clone(&status);
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || serve(listener, status, stopped))
+        };
+        info!("status server of ckb chain is listening on {local_addr}");
+        Ok(Self {
+            local_addr,
+            status,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn publish(&self, status: serde_json::Value) {
+        *self.status.write().expect("poisoned lock") = status;
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("status server of ckb chain panicked");
+            }
+        }
+    }
+}
+
+fn serve(listener: TcpListener, status: Arc<RwLock<serde_json::Value>>, stopped: Arc<AtomicBool>) {
+    while !stopped.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(err) = respond(stream, &status) {
+                    debug!("failed to respond the status to {peer}: {err}");
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(err) => warn!("status server of ckb chain failed to accept: {err}"),
+        }
+    }
+}
+
+fn respond(stream: TcpStream, status: &RwLock<serde_json::Value>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_SIZE));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not used
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status_line, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(STATUS_PATH)) => {
+            let body = status.read().expect("poisoned lock").to_string();
+            ("200 OK", body)
+        }
+        _ => ("404 Not Found", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status_line}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\
+         connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    (&stream).write_all(response.as_bytes())
+}
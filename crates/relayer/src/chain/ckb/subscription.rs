@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam_channel as channel;
+use ibc_relayer_types::core::ics24_host::identifier::ChainId;
+use tracing::warn;
+
+use crate::chain::handle::Subscription;
+use crate::config::ckb::SubscribeOverflow;
+use crate::event::monitor::{EventBatch, Result as MonitorResult};
+
+type Item = Arc<MonitorResult<EventBatch>>;
+
+/// Broadcasts the event batches of the committed transactions to the subscribers, each one
+/// holds at most `capacity` batches, and a full subscriber is handled by the overflow policy.
+///
+/// With `SubscribeOverflow::DropOldest`, the batches of a subscriber are buffered by a relay
+/// thread, which hands them over one by one, so the stream never holds a receiver of the
+/// subscriber. A dropped subscriber ends its relay once a batch is relayed to it, and then it's
+/// pruned by the following broadcast.
+pub struct EventStream {
+    chain_id: ChainId,
+    capacity: usize,
+    overflow: SubscribeOverflow,
+    // The channel of each subscriber for `SubscribeOverflow::Block`, or the channel to its relay
+    // for `SubscribeOverflow::DropOldest`.
+    subscribers: Vec<channel::Sender<Item>>,
+}
+
+impl EventStream {
+    pub fn new(chain_id: ChainId, capacity: usize, overflow: SubscribeOverflow) -> Self {
+        Self {
+            chain_id,
+            capacity,
+            overflow,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self) -> Subscription {
+        // a zero capacity never holds a batch to drop, so it has one slot at least
+        let capacity = self.capacity.max(1);
+        match self.overflow {
+            SubscribeOverflow::Block => {
+                let (tx, rx) = channel::bounded(capacity);
+                self.subscribers.push(tx);
+                rx
+            }
+            SubscribeOverflow::DropOldest => {
+                let (relay_tx, relay_rx) = channel::unbounded();
+                let (tx, rx) = channel::bounded(0);
+                let chain_id = self.chain_id.clone();
+                thread::spawn(move || relay_dropping_oldest(chain_id, capacity, relay_rx, tx));
+                self.subscribers.push(relay_tx);
+                rx
+            }
+        }
+    }
+
+    /// Returns the number of the subscribers which are not pruned yet.
+    pub fn subscribers_count(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    pub fn broadcast(&mut self, batch: EventBatch) {
+        let item = Arc::new(Ok(batch));
+        // with `SubscribeOverflow::Block`, it blocks until the subscriber has room for it
+        self.subscribers
+            .retain(|subscriber| subscriber.send(Arc::clone(&item)).is_ok());
+    }
+}
+
+// Hands over the batches received from the stream to the subscriber, at most `capacity` of them
+// are buffered and the oldest one is dropped to make room for a new one. It returns once the
+// subscriber is dropped, or the stream is dropped and the buffered batches are handed over.
+fn relay_dropping_oldest(
+    chain_id: ChainId,
+    capacity: usize,
+    relay_rx: channel::Receiver<Item>,
+    tx: channel::Sender<Item>,
+) {
+    let mut buffer = VecDeque::with_capacity(capacity);
+    let mut stream_dropped = false;
+    loop {
+        if stream_dropped && buffer.is_empty() {
+            return;
+        }
+        let mut select = channel::Select::new();
+        let recv_index = (!stream_dropped).then(|| select.recv(&relay_rx));
+        let send_index = (!buffer.is_empty()).then(|| select.send(&tx));
+        let oper = select.select();
+        if Some(oper.index()) == recv_index {
+            let Ok(item) = oper.recv(&relay_rx) else {
+                stream_dropped = true;
+                continue;
+            };
+            if buffer.len() == capacity {
+                let height = match buffer.pop_front().as_deref() {
+                    Some(Ok(batch)) => batch.height.to_string(),
+                    _ => "unknown".to_owned(),
+                };
+                warn!(
+                    "subscriber of ckb chain {chain_id} is full, dropped the oldest event batch \
+                     at height {height}"
+                );
+            }
+            buffer.push_back(item);
+        } else if Some(oper.index()) == send_index {
+            let item = buffer.pop_front().expect("a buffered batch to send");
+            if oper.send(&tx, item).is_err() {
+                // the subscriber is dropped
+                return;
+            }
+        }
+    }
+}
@@ -1,31 +1,94 @@
-use std::{fs, str::FromStr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead as _, BufReader, Read as _, Write as _},
+    net::TcpListener,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+use ckb_jsonrpc_types::JsonBytes;
 use ckb_sdk::{
     constants::TYPE_ID_CODE_HASH,
     rpc::ckb_indexer::{Cell, SearchKey},
-    traits::{CellQueryOptions, PrimaryScriptType},
-    NetworkType,
+    traits::{CellQueryOptions, PrimaryScriptType, ValueRangeOption},
+    AddressPayload, NetworkType,
 };
 use ckb_types::{
-    core::{BlockNumber, Capacity, ScriptHashType},
+    bytes::Bytes,
+    core::{BlockNumber, Capacity, HeaderBuilder, ScriptHashType, TransactionBuilder},
     h256, packed,
     prelude::*,
+    H256,
+};
+use eth2_types::MainnetEthSpec;
+use eth_light_client_in_ckb_verification::types::packed::{
+    Client as PackedClient, ClientInfo as PackedClientInfo, ClientTypeArgs as PackedClientTypeArgs,
 };
 use hdpath::StandardHDPath;
+use ibc_relayer_storage::{
+    prelude::{StorageAsMMRStore as _, StorageReader as _, StorageWriter as _},
+    Storage,
+};
 use ibc_relayer_types::{
-    clients::ics07_eth::types::{Header as EthHeader, Update as EthUpdate},
-    core::ics24_host::identifier::ChainId,
+    clients::{
+        ics07_ckb::client_state::ClientState as CkbClientState,
+        ics07_eth::{
+            client_state::ClientState as EthClientState,
+            types::{FixedVector, Header as EthHeader, Update as EthUpdate, H256 as EthH256},
+        },
+    },
+    core::ics24_host::identifier::{ChainId, ChannelId, ClientId, PortId},
+    events::IbcEvent,
+    Height,
 };
-use rand::{thread_rng, Rng as _};
+use rand::{seq::SliceRandom as _, thread_rng, Rng as _};
+use secp256k1::SecretKey;
 use tempfile::TempDir;
+use tendermint::Hash as TxHash;
 use tendermint_rpc::Url;
 use tokio::runtime::Runtime as TokioRuntime;
+use tree_hash::TreeHash as _;
 
-use super::{CkbChain, HD_PATH};
+use super::{
+    beacon_client::BeaconClient,
+    prelude::{
+        CellSearcher as _, FeeOptions, RequestLimiter, RequestTimeouts, TxAssembler as _,
+        TxCompleter as _,
+    },
+    rpc_client::RpcClient,
+    signer,
+    subscription::EventStream,
+    utils, ChainCapabilities, CkbChain, HD_PATH,
+};
 use crate::{
-    chain::endpoint::ChainEndpoint,
-    config::{ckb::ChainConfig as CkbChainConfig, ckb::ClientTypeArgs, AddressType, ChainConfig},
-    keyring::{Secp256k1KeyPair, SigningKeyPair},
+    chain::{
+        endpoint::ChainEndpoint,
+        requests::{
+            IncludeProof, PageRequest, QueryChannelsRequest, QueryClientStateRequest,
+            QueryClientStatesRequest, QueryConnectionsRequest, QueryConsensusStateHeightsRequest,
+            QueryConsensusStateRequest, QueryHeight, QueryPacketCommitmentsRequest, QueryTxHash,
+            QueryTxRequest, QueryUpgradedClientStateRequest, QueryUpgradedConsensusStateRequest,
+        },
+        tracking::{NonCosmosTrackingId, TrackedMsgs, TrackingId},
+    },
+    client_state::AnyClientState,
+    config::{
+        ckb::ChainConfig as CkbChainConfig, ckb::ClientTypeArgs, ckb::SubscribeOverflow,
+        ckb::SuffixedLock, ckb::UpdateBudget, error::ErrorDetail as ConfigErrorDetail, AddressType,
+        ChainConfig,
+    },
+    consensus_state::AnyConsensusState,
+    error::{Error, ErrorDetail},
+    event::monitor::EventBatch,
+    keyring::{KeyRing, Secp256k1KeyPair, SigningKeyPair, Test as KeyStoreTest},
 };
 
 const TESTDATA_DIR: &str = "src/testdata/test_update_eth_client";
@@ -90,66 +153,99 @@ pub(crate) fn load_updates_from_file(dir: &str, file: &str) -> Vec<EthUpdate> {
     updates
 }
 
-#[test]
-fn test_create_eth_multi_client_case_1() {
-    test_create_eth_multi_client(1);
+fn new_test_key(is_mainnet: bool) -> Secp256k1KeyPair {
+    let mnemonic =
+        "feed label choose question decrease slab regular humor salmon wheel slab inform";
+    let hd_path = StandardHDPath::from_str(HD_PATH).unwrap();
+    let account_prefix = if is_mainnet { "ckb" } else { "ckt" };
+    let address_type = AddressType::Ckb { is_mainnet };
+    Secp256k1KeyPair::from_mnemonic(mnemonic, &hd_path, &address_type, account_prefix).unwrap()
 }
 
-#[test]
-fn test_create_eth_multi_client_case_2() {
-    test_create_eth_multi_client(2);
+fn new_ckb_config(data_dir: &Path) -> CkbChainConfig {
+    CkbChainConfig {
+        id: ChainId::new("chainA".to_string(), 10),
+        ckb_rpc: Url::from_str("http://ckb_rpc").unwrap(),
+        ckb_indexer_rpc: Url::from_str("http://ckb_indexer_rpc").unwrap(),
+        ckb_ws_rpc: None,
+        lightclient_contract_typeargs: h256!("0x123"),
+        lightclient_lock_typeargs: h256!("0x123"),
+        retired_lock_typeargs: vec![],
+        client_type_args: ClientTypeArgs {
+            type_id: None,
+            cells_count: 3,
+            client_id: None,
+        },
+        genesis_client: None,
+        keyed_client_type_args: Default::default(),
+        max_cells_count: 64,
+        create_chunk_size: 0,
+        minimal_updates_count: 1,
+        inherit_minimal_updates_count: false,
+        accepted_genesis_validators_root: vec![],
+        verify_updates_locally: false,
+        key_name: "ckb-chain-test".to_string(),
+        key_from_env: None,
+        key_file: None,
+        key_passphrase_env: None,
+        fee_payer_key: None,
+        lock_args_suffix: Default::default(),
+        suffixed_lock: None,
+        data_dir: data_dir.to_path_buf(),
+        compress_storage: false,
+        max_concurrent_requests: 4,
+        rpc_timeout: Duration::from_secs(30),
+        rpc_method_timeouts: BTreeMap::new(),
+        verify_signatures: true,
+        min_change_capacity: 0,
+        fee_rate: 3000,
+        min_fee_rate: 0,
+        max_tx_size: 597_000,
+        max_cycle_retries: 0,
+        cycle_retry_budget: 0,
+        update_budget: Default::default(),
+        tx_commit_timeout: Duration::from_secs(60),
+        tx_confirmations: 0,
+        stuck_after: Duration::from_secs(30),
+        max_clock_skew: Duration::from_secs(60),
+        commit_webhook_url: None,
+        beacon_api_urls: vec![],
+        finality_margin_slots: 0,
+        status_server_addr: None,
+        export_signed_tx_dir: None,
+        subscribe_capacity: 256,
+        subscribe_overflow: SubscribeOverflow::DropOldest,
+    }
 }
 
-fn test_create_eth_multi_client(case_id: usize) {
-    let tmp_dir = TempDir::new().unwrap();
-    let testdata_dir = format!("{}/case-{}", TESTDATA_DIR, case_id);
-
-    let mut chain = {
-        let ckb_config = CkbChainConfig {
-            id: ChainId::new("chainA".to_string(), 10),
-            ckb_rpc: Url::from_str("http://ckb_rpc").unwrap(),
-            ckb_indexer_rpc: Url::from_str("http://ckb_indexer_rpc").unwrap(),
-            lightclient_contract_typeargs: h256!("0x123"),
-            lightclient_lock_typeargs: h256!("0x123"),
-            client_type_args: ClientTypeArgs {
-                type_id: None,
-                cells_count: 3,
-            },
-            minimal_updates_count: 1,
-            key_name: "ckb-chain-test".to_string(),
-            data_dir: tmp_dir.path().to_path_buf(),
-        };
-        let config = ChainConfig::Ckb(ckb_config);
-        let rt = Arc::new(TokioRuntime::new().unwrap());
+fn bootstrap_chain(ckb_config: CkbChainConfig) -> CkbChain {
+    let config = ChainConfig::Ckb(ckb_config);
+    let rt = Arc::new(TokioRuntime::new().unwrap());
+    CkbChain::bootstrap(config, rt).unwrap()
+}
 
-        CkbChain::bootstrap(config, rt).unwrap()
-    };
+fn multi_client_type_script(
+    contract_type_args: &H256,
+    client_type_args: &PackedClientTypeArgs,
+) -> packed::Script {
+    let contract = packed::Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.0.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(contract_type_args.as_bytes().to_vec().pack())
+        .build();
+    packed::Script::new_builder()
+        .code_hash(contract.calc_script_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .args(client_type_args.as_slice().pack())
+        .build()
+}
 
+fn prepare_key_and_cells(chain: &mut CkbChain) {
     let rpc_client = Arc::clone(&chain.rpc_client);
 
-    let chain_info = r#"
-        {
-          "alerts": [],
-          "chain": "ckb-dev",
-          "difficulty": "0x10000",
-          "epoch": "0x100",
-          "is_initial_block_download": true,
-          "median_time": "0x5cd2b105"
-        }"#;
-    rpc_client.set_blockchain_info(Some(chain_info));
-
     {
-        let key = {
-            let mnemonic =
-                "feed label choose question decrease slab regular humor salmon wheel slab inform";
-            let hd_path = StandardHDPath::from_str(HD_PATH).unwrap();
-            let network = chain.network().unwrap();
-            let is_mainnet = network == NetworkType::Mainnet;
-            let account_prefix = if is_mainnet { "ckb" } else { "ckt" };
-            let address_type = AddressType::Ckb { is_mainnet };
-            Secp256k1KeyPair::from_mnemonic(mnemonic, &hd_path, &address_type, account_prefix)
-                .unwrap()
-        };
+        let is_mainnet = chain.network().unwrap() == NetworkType::Mainnet;
+        let key = new_test_key(is_mainnet);
         let key_name = chain.config.key_name.clone();
         chain.keybase_mut().add_key(&key_name, key).unwrap();
     }
@@ -185,16 +281,3935 @@ fn test_create_eth_multi_client(case_id: usize) {
         let key: SearchKey = CellQueryOptions::new(lock_script, PrimaryScriptType::Lock).into();
         rpc_client.add_cell(&key, cell);
     }
+}
+
+// Consumes the inputs of a sent update transaction and commits its multi-client cells.
+fn apply_multi_client_update(rpc_client: &RpcClient, tx: &packed::Transaction) {
+    let out_points = tx
+        .raw()
+        .inputs()
+        .into_iter()
+        .map(|input| input.previous_output().into())
+        .collect::<Vec<_>>();
+    rpc_client.consume_cells(&out_points);
+    add_multi_client_cells(rpc_client, tx);
+}
+
+// The mock doesn't maintain live cells, so commit the multi-client cells of a sent transaction.
+fn add_multi_client_cells_from_tx(rpc_client: &RpcClient, tx_index: usize) {
+    let tx: packed::Transaction = rpc_client
+        .get_transaction_by_index(tx_index)
+        .unwrap()
+        .into();
+    add_multi_client_cells(rpc_client, &tx);
+}
+
+fn add_multi_client_cells(rpc_client: &RpcClient, tx: &packed::Transaction) {
+    let tx_hash = tx.calc_tx_hash();
+    let raw_tx = tx.raw();
+    for (index, (output, data)) in raw_tx
+        .outputs()
+        .into_iter()
+        .zip(raw_tx.outputs_data())
+        .enumerate()
+    {
+        let Some(type_script) = output.type_().to_opt() else {
+            continue;
+        };
+        let key: SearchKey = CellQueryOptions::new(type_script, PrimaryScriptType::Type).into();
+        let mut cell = random_cell(1, output, data.raw_data().to_vec());
+        cell.out_point = packed::OutPoint::new(tx_hash.clone(), index as u32).into();
+        rpc_client.add_cell(&key, cell);
+    }
+}
+
+#[test]
+fn test_fetch_client_info_only() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let contract_typeargs = chain.config.lightclient_contract_typeargs.clone();
+    let client_type_args = utils::pack_client_type_args(&h256!("0x1"), 3);
+    let fetch_client_info = || {
+        chain
+            .rt
+            .block_on(rpc_client.fetch_client_info(&contract_typeargs, &client_type_args))
+            .unwrap()
+    };
+    assert!(fetch_client_info().is_none());
+
+    // only the info cell is queried, by its data size
+    let type_script = multi_client_type_script(&contract_typeargs, &client_type_args);
+    let info = PackedClientInfo::new_builder()
+        .last_id(1.into())
+        .minimal_updates_count(4.into())
+        .build();
+    let mut query = CellQueryOptions::new(type_script.clone(), PrimaryScriptType::Type);
+    query.data_len_range = Some(ValueRangeOption::new_exact(info.as_slice().len() as u64));
+    let output = packed::CellOutput::new_builder()
+        .type_(Some(type_script).pack())
+        .build();
+    rpc_client.add_cell(
+        &query.into(),
+        random_cell(1, output, info.as_slice().to_vec()),
+    );
+    assert_eq!(
+        fetch_client_info().map(|info| info.as_slice().to_vec()),
+        Some(info.as_slice().to_vec())
+    );
+}
+
+#[test]
+fn test_storage_is_durable_after_shutdown() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let ckb_config = new_ckb_config(tmp_dir.path());
+
+    let mut chain = bootstrap_chain(ckb_config.clone());
+    prepare_key_and_cells(&mut chain);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let minimal_slot = updates.first().unwrap().finalized_header.slot;
+    let maximal_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    chain.shutdown().expect("shutdown");
+
+    let chain = bootstrap_chain(ckb_config);
+    assert_eq!(
+        chain.native_slot_range().unwrap(),
+        Some((minimal_slot, maximal_slot))
+    );
+    assert_eq!(
+        chain.storage.get_verified_beacon_header_slot().unwrap(),
+        Some(maximal_slot)
+    );
+}
+
+#[test]
+fn test_update_with_mismatched_cells_count() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    // one more client cell than the 2 ones of the configured `cells_count` is deployed
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(0).unwrap().into();
+    let output = tx.raw().outputs().get(0).unwrap();
+    let data = tx.raw().outputs_data().get(0).unwrap();
+    let type_script = output.type_().to_opt().unwrap();
+    let key: SearchKey = CellQueryOptions::new(type_script, PrimaryScriptType::Type).into();
+    rpc_client.add_cell(&key, random_cell(2, output, data.raw_data().to_vec()));
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let err = chain
+        .update_eth_multi_client(updates, None)
+        .expect_err("mismatched cells count");
+    assert!(
+        matches!(
+            err.detail(),
+            ErrorDetail::CellsCountMismatch(e) if e.config == 2 && e.onchain == 3
+        ),
+        "{err}"
+    );
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_create_eth_multi_client_case_1() {
+    test_create_eth_multi_client(1);
+}
+
+#[test]
+fn test_create_eth_multi_client_case_2() {
+    test_create_eth_multi_client(2);
+}
+
+fn test_create_eth_multi_client(case_id: usize) {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-{}", TESTDATA_DIR, case_id);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
 
     let updates_part_1 = load_updates_from_file(&testdata_dir, "headers_part_1.json");
 
-    let result = chain.create_eth_multi_client(updates_part_1);
+    let result = chain.create_eth_multi_client(updates_part_1, None);
     assert!(result.is_ok());
 
     let txs_len = rpc_client.get_transactions_len();
     assert_eq!(txs_len, 1);
 }
 
+#[test]
+fn test_create_eth_multi_client_with_client_keys() {
+    let tmp_dir = TempDir::new().unwrap();
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        // the client ids are not in the order of the keys
+        ckb_config.keyed_client_type_args.insert(
+            "goerli".to_owned(),
+            ClientTypeArgs {
+                type_id: None,
+                cells_count: 3,
+                client_id: Some(ClientId::from_str("eth-2").unwrap()),
+            },
+        );
+        ckb_config.keyed_client_type_args.insert(
+            "sepolia".to_owned(),
+            ClientTypeArgs {
+                type_id: None,
+                cells_count: 4,
+                client_id: Some(ClientId::from_str("eth-1").unwrap()),
+            },
+        );
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    for (case_id, client_key) in [(1, "goerli"), (2, "sepolia")] {
+        let testdata_dir = format!("{}/case-{}", TESTDATA_DIR, case_id);
+        let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+        chain
+            .create_eth_multi_client(updates, Some(client_key))
+            .expect("create keyed client");
+    }
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+
+    // only the keyed clients are created, the default one is untouched
+    assert!(chain.config.client_type_args.type_id.is_none());
+    let goerli_type_id = chain.config.keyed_client_type_args["goerli"]
+        .type_id
+        .clone()
+        .expect("goerli type id");
+    let sepolia_type_id = chain.config.keyed_client_type_args["sepolia"]
+        .type_id
+        .clone()
+        .expect("sepolia type id");
+    assert_ne!(goerli_type_id, sepolia_type_id);
+
+    // each client key has its own native storage
+    let goerli_base_slot = chain.keyed_clients["goerli"]
+        .storage
+        .get_base_beacon_header_slot()
+        .unwrap();
+    let sepolia_base_slot = chain.keyed_clients["sepolia"]
+        .storage
+        .get_base_beacon_header_slot()
+        .unwrap();
+    assert!(goerli_base_slot.is_some());
+    assert!(sepolia_base_slot.is_some());
+    let default_base_slot = chain.storage.get_base_beacon_header_slot().unwrap();
+    assert!(default_base_slot.is_none());
+
+    // the clients are reported by their configured ids
+    let client_ids = chain
+        .query_clients(QueryClientStatesRequest { pagination: None })
+        .expect("query clients")
+        .into_iter()
+        .map(|client| client.client_id.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(client_ids, ["eth-2", "eth-1"]);
+
+    let result = chain.create_eth_multi_client(vec![], Some("mainnet"));
+    assert!(result.is_err());
+}
+
+fn minimal_updates_count_in_tx(rpc_client: &RpcClient, tx_index: usize) -> u8 {
+    let tx: packed::Transaction = rpc_client
+        .get_transaction_by_index(tx_index)
+        .unwrap()
+        .into();
+    let info = tx
+        .raw()
+        .outputs_data()
+        .into_iter()
+        .find_map(|data| PackedClientInfo::from_slice(&data.raw_data()).ok())
+        .expect("client info");
+    u8::from(info.minimal_updates_count().as_reader())
+}
+
+#[test]
+fn test_create_eth_multi_client_inherit_minimal_updates_count() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.minimal_updates_count = 2;
+        ckb_config.inherit_minimal_updates_count = true;
+        for (key, client_id) in [("goerli", "eth-1"), ("sepolia", "eth-2")] {
+            ckb_config.keyed_client_type_args.insert(
+                key.to_owned(),
+                ClientTypeArgs {
+                    type_id: None,
+                    cells_count: 3,
+                    client_id: Some(ClientId::from_str(client_id).unwrap()),
+                },
+            );
+        }
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+
+    // no existing deployment, the config is used
+    chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect("create client");
+    assert_eq!(minimal_updates_count_in_tx(&rpc_client, 0), 2);
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    // the existing deployment takes precedence over the config
+    chain.config.minimal_updates_count = 1;
+    chain
+        .create_eth_multi_client(updates.clone(), Some("goerli"))
+        .expect("create goerli client");
+    assert_eq!(minimal_updates_count_in_tx(&rpc_client, 1), 2);
+
+    // the config is used if inheriting is disabled
+    chain.config.inherit_minimal_updates_count = false;
+    chain
+        .create_eth_multi_client(updates, Some("sepolia"))
+        .expect("create sepolia client");
+    assert_eq!(minimal_updates_count_in_tx(&rpc_client, 2), 1);
+}
+
+#[test]
+fn test_native_slot_range() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+
+    assert_eq!(chain.native_slot_range().unwrap(), None);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+
+    let (base_slot, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    let stored_base_slot = chain.storage.get_base_beacon_header_slot().unwrap();
+    let stored_tip_slot = chain.storage.get_tip_beacon_header_slot().unwrap();
+    assert_eq!(Some(base_slot), stored_base_slot);
+    assert_eq!(Some(tip_slot), stored_tip_slot);
+    assert!(base_slot <= tip_slot);
+}
+
+#[test]
+fn test_can_prove_slot() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+
+    assert!(!chain.can_prove_slot(0).unwrap());
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+
+    let (base_slot, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert!(chain.can_prove_slot(base_slot).unwrap());
+    assert!(chain.can_prove_slot(tip_slot).unwrap());
+    assert!(!chain.can_prove_slot(tip_slot + 1).unwrap());
+    if base_slot > 0 {
+        assert!(!chain.can_prove_slot(base_slot - 1).unwrap());
+    }
+}
+
+#[test]
+fn test_verify_onchain_against_checkpoint() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let checkpoint = &updates[updates.len() / 2].finalized_header;
+    let checkpoint_root = H256(checkpoint.tree_hash_root().0);
+    assert!(chain
+        .verify_onchain_against_checkpoint(checkpoint.slot, &checkpoint_root)
+        .unwrap());
+
+    let mut wrong_root = checkpoint_root.clone();
+    wrong_root.0[0] ^= 1;
+    assert!(!chain
+        .verify_onchain_against_checkpoint(checkpoint.slot, &wrong_root)
+        .unwrap());
+
+    // out of the range of the on-chain client
+    let tip_slot = updates.last().unwrap().finalized_header.slot;
+    assert!(!chain
+        .verify_onchain_against_checkpoint(tip_slot + 1, &checkpoint_root)
+        .unwrap());
+}
+
+#[test]
+fn test_compact_storage_keeps_onchain_slots_provable() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let slot_range = chain.native_slot_range().unwrap().expect("slot range");
+
+    // a failed update leaves its digests beyond the tip after the rollback
+    rpc_client.fail_next_sends(1);
+    chain
+        .update_eth_multi_client(
+            load_updates_from_file(&testdata_dir, "headers_part_2.json"),
+            None,
+        )
+        .expect_err("failed to send");
+    assert_eq!(chain.native_slot_range().unwrap(), Some(slot_range));
+
+    let stats = chain.compact_storage().expect("compact storage");
+    assert!(stats.pruned_digests > 0);
+    assert_eq!(chain.native_slot_range().unwrap(), Some(slot_range));
+    let (minimal_slot, maximal_slot) = chain.onchain_client_slot_range().unwrap();
+    for slot in minimal_slot..=maximal_slot {
+        assert!(chain.can_prove_slot(slot).unwrap(), "slot {slot}");
+    }
+    let checkpoint = &updates[updates.len() / 2].finalized_header;
+    let checkpoint_root = H256(checkpoint.tree_hash_root().0);
+    assert!(chain
+        .verify_onchain_against_checkpoint(checkpoint.slot, &checkpoint_root)
+        .unwrap());
+
+    // nothing is left to prune
+    let stats = chain.compact_storage().expect("compact storage");
+    assert_eq!(stats.pruned_digests, 0);
+}
+
+#[test]
+fn test_storage_stats() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let stats = chain.storage_stats().expect("storage stats");
+    assert_eq!(stats.headers_count, 0);
+    assert_eq!(stats.slot_range, None);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let minimal_slot = updates.first().unwrap().finalized_header.slot;
+    let maximal_slot = updates.last().unwrap().finalized_header.slot;
+    let headers_count = updates.len() as u64;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    let stats = chain.storage_stats().expect("storage stats");
+    assert_eq!(stats.headers_count, headers_count);
+    assert_eq!(stats.slot_range, Some((minimal_slot, maximal_slot)));
+    assert!(stats.bytes > 0);
+}
+
+#[test]
+fn test_export_signed_tx_and_broadcast() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let export_dir = tmp_dir.path().join("signed-txs");
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.export_signed_tx_dir = Some(export_dir.clone());
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the creation is always sent
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let prev_maximal_slot = chain.cached_maximal_slot();
+    let prev_native_slot_range = chain.native_slot_range().unwrap();
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let events = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("export update");
+    assert!(events.is_empty());
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+    assert_eq!(chain.cached_maximal_slot(), prev_maximal_slot);
+    // nothing is committed, so the native storage isn't advanced
+    assert_eq!(chain.native_slot_range().unwrap(), prev_native_slot_range);
+
+    // no other update is assembled until the exported one is broadcast
+    let err = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect_err("exported tx pending");
+    assert!(matches!(err.detail(), ErrorDetail::CkbExportedTxPending(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+
+    let exported = fs::read_dir(&export_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(exported.len(), 1);
+    chain
+        .broadcast_signed_tx(&exported[0])
+        .expect("broadcast exported tx");
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+    let sent: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    let tx_hash = hex::encode(sent.calc_tx_hash().as_slice());
+    assert_eq!(
+        exported[0].file_name().unwrap().to_str().unwrap(),
+        format!("{tx_hash}.json")
+    );
+    // the signatures are kept in the exported file
+    assert!(!sent.witnesses().is_empty());
+    assert!(chain.exported_tx.is_none());
+
+    // an exported update is discarded by removing its file
+    chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("export update again");
+    let (_, path) = chain.exported_tx.clone().expect("exported tx");
+    fs::remove_file(path).unwrap();
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("export update after discarding");
+    assert!(chain.exported_tx.is_some());
+}
+
+#[test]
+fn test_create_eth_multi_client_with_stale_type_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    // the type id is kept in the config, but its cells are gone after a contract redeploy
+    chain.config.client_type_args.type_id = Some(h256!("0x1"));
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .create_eth_multi_client(updates, None)
+        .expect_err("cells gone");
+    let ErrorDetail::CkbClientCellsGone(detail) = err.detail() else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(detail.type_id, format!("{:#x}", h256!("0x1")));
+    assert!(err.to_string().contains("clear `type_id`"));
+}
+
+#[test]
+fn test_create_eth_multi_client_across_sync_committee_period() {
+    let tmp_dir = TempDir::new().unwrap();
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the last slots of the period 0 and the first slots of the period 1
+    let mut updates = utils::synthetic_updates(8192 - 4, 8);
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("no next sync committee");
+    let ErrorDetail::MissingSyncCommittee(detail) = err.detail() else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(detail.period, 1);
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+
+    updates[3].next_sync_committee.aggregate_pubkey[0] = 1;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client with the next sync committee");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_update_across_sync_committee_period_announced_before() {
+    let tmp_dir = TempDir::new().unwrap();
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the next sync committee is announced by the first batch, the second one crosses the
+    // boundary of the period 1 without another announcement
+    let mut updates = utils::synthetic_updates(8192 - 8, 12);
+    updates[1].next_sync_committee.aggregate_pubkey[0] = 1;
+    let updates_part_2 = updates.split_off(4);
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    assert_eq!(
+        chain.storage.get_announced_sync_committee_slot().unwrap(),
+        Some(8192 - 7)
+    );
+
+    chain
+        .update_eth_multi_client(updates_part_2.clone(), None)
+        .expect("update across the period");
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+
+    // no committee is announced without the first batch
+    chain
+        .storage
+        .delete_announced_sync_committee_slot()
+        .unwrap();
+    let err = chain
+        .update_eth_multi_client(updates_part_2, None)
+        .expect_err("no next sync committee");
+    assert!(matches!(err.detail(), ErrorDetail::MissingSyncCommittee(_)));
+}
+
+#[test]
+fn test_create_eth_multi_client_returns_create_event() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let events = chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    assert_eq!(events.len(), 1);
+    let IbcEvent::CreateClient(event) = &events[0].event else {
+        panic!("unexpected event: {:?}", events[0].event);
+    };
+    assert_eq!(event.client_id(), &ClientId::default());
+    let base_slot = chain.cached_minimal_slot().unwrap();
+    assert_eq!(event.0.consensus_height.revision_height(), base_slot);
+}
+
+#[test]
+fn test_create_eth_multi_client_in_chunks() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.client_type_args.cells_count = 9;
+    ckb_config.create_chunk_size = 3;
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    // the created cells are indexed once sent, and the third transaction fails to be sent
+    let hook_rpc_client = Arc::clone(&rpc_client);
+    rpc_client.set_send_transaction_hook(move |tx| {
+        add_multi_client_cells(&hook_rpc_client, &tx.clone().into());
+        if hook_rpc_client.get_transactions_len() == 2 {
+            hook_rpc_client.fail_next_sends(1);
+        }
+    });
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("interrupted");
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+    // the partial state is kept to be resumed
+    let type_id = chain
+        .config
+        .client_type_args
+        .type_id
+        .clone()
+        .expect("type id");
+    assert!(chain.native_slot_range().unwrap().is_some());
+
+    let events = chain
+        .create_eth_multi_client(updates, None)
+        .expect("resume creating");
+    assert_eq!(events.len(), 1);
+    assert_eq!(chain.config.client_type_args.type_id, Some(type_id.clone()));
+
+    // 3 + 3 + 2 clients, then the info cell
+    let created_cells_counts = (0..rpc_client.get_transactions_len())
+        .map(|index| {
+            let tx: packed::Transaction =
+                rpc_client.get_transaction_by_index(index).unwrap().into();
+            tx.raw()
+                .outputs()
+                .into_iter()
+                .filter(|output| output.type_().to_opt().is_some())
+                .count()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(created_cells_counts, vec![3, 3, 2, 1]);
+
+    let client_type_args = utils::pack_client_type_args(&type_id, 9);
+    let (clients, client_info) = chain
+        .rt
+        .block_on(rpc_client.fetch_clients_and_info(
+            &chain.config.lightclient_contract_typeargs,
+            &client_type_args,
+        ))
+        .unwrap()
+        .expect("multi-client");
+    let mut client_ids = clients
+        .iter()
+        .map(|client| u8::from(client.id().as_reader()))
+        .collect::<Vec<_>>();
+    client_ids.sort_unstable();
+    assert_eq!(client_ids, (0..8).collect::<Vec<_>>());
+    assert_eq!(u8::from(client_info.last_id().as_reader()), 0);
+}
+
+#[test]
+fn test_create_eth_multi_client_from_genesis_client() {
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let updates_part_1 = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let updates_part_2 = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+
+    // the genesis client is built from the first part of the updates
+    let (genesis_client, genesis_minimal_slot) = {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+        prepare_key_and_cells(&mut chain);
+        chain
+            .create_eth_multi_client(updates_part_1.clone(), None)
+            .expect("create genesis client");
+        let client = chain.cached_onchain_client(None).unwrap().clone();
+        (client, chain.cached_minimal_slot().unwrap())
+    };
+    let encoded =
+        String::from_utf8(subtle_encoding::base64::encode(genesis_client.as_slice())).unwrap();
+
+    let tmp_dir = TempDir::new().unwrap();
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.genesis_client = Some(encoded);
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+
+    // the updates don't start from the genesis client
+    let err = chain
+        .create_eth_multi_client(updates_part_2.clone(), None)
+        .expect_err("not extending the genesis client");
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::GenesisClientMismatch(_)
+    ));
+    assert_eq!(chain.storage.get_tip_beacon_header_slot().unwrap(), None);
+
+    let updates = updates_part_1
+        .into_iter()
+        .chain(updates_part_2)
+        .collect::<Vec<_>>();
+    let tip_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client from genesis client");
+    assert_eq!(chain.cached_minimal_slot(), Some(genesis_minimal_slot));
+    assert_eq!(chain.cached_maximal_slot(), Some(tip_slot));
+}
+
+#[test]
+fn test_update_worker_processes_batches_in_order() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let new_tracked_msgs = |file: &str, tracking_id: &'static str| TrackedMsgs {
+        msgs: load_updates_from_file(&testdata_dir, file)
+            .into_iter()
+            .map(|update| {
+                EthClientState {
+                    chain_id: ChainId::new("eth".to_owned(), 0),
+                    lightclient_update: update,
+                }
+                .into()
+            })
+            .collect(),
+        tracking_id: TrackingId::Static(tracking_id),
+    };
+
+    let (batches, rx) = crossbeam_channel::unbounded();
+    let worker = chain.spawn_update_worker(rx);
+    batches
+        .send(new_tracked_msgs(
+            "headers_part_1.json",
+            NonCosmosTrackingId::ETH_CREATE_CLIENT,
+        ))
+        .unwrap();
+    batches
+        .send(new_tracked_msgs("headers_part_2.json", "unknown"))
+        .unwrap();
+    let created = worker.results().recv().unwrap();
+    assert_eq!(
+        created.tracking_id.to_string(),
+        NonCosmosTrackingId::ETH_CREATE_CLIENT
+    );
+    // the event of the created client is followed by the one of its correlation id
+    assert_eq!(created.result.expect("create client").len(), 2);
+    let unknown = worker.results().recv().unwrap();
+    assert_eq!(unknown.tracking_id.to_string(), "unknown");
+    let err = unknown.result.expect_err("unknown batch");
+    assert!(matches!(err.detail(), ErrorDetail::SendTx(_)));
+
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let maximal_slot = updates.last().unwrap().finalized_header.slot;
+    batches
+        .send(new_tracked_msgs(
+            "headers_part_2.json",
+            NonCosmosTrackingId::ETH_UPDATE_CLIENT,
+        ))
+        .unwrap();
+    let updated = worker.results().recv().unwrap();
+    assert_eq!(updated.result.expect("update client").len(), 1);
+
+    // the worker stops after the queue is closed, then the chain is returned
+    drop(batches);
+    let chain = worker.join().expect("join worker");
+    assert_eq!(chain.cached_maximal_slot(), Some(maximal_slot));
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+}
+
+#[test]
+fn test_skip_sending_while_paused() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let new_tracked_msgs = || TrackedMsgs {
+        msgs: updates
+            .iter()
+            .map(|update| {
+                EthClientState {
+                    chain_id: ChainId::new("eth".to_owned(), 0),
+                    lightclient_update: update.clone(),
+                }
+                .into()
+            })
+            .collect(),
+        tracking_id: TrackingId::Static(NonCosmosTrackingId::ETH_CREATE_CLIENT),
+    };
+
+    chain.pause();
+    assert!(chain.is_paused());
+    let events = chain
+        .send_messages_and_wait_commit(new_tracked_msgs())
+        .expect("skip sending");
+    assert!(events.is_empty());
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+    // queries still work
+    chain
+        .query_clients(QueryClientStatesRequest { pagination: None })
+        .expect("query clients");
+
+    chain.resume();
+    assert!(!chain.is_paused());
+    chain
+        .send_messages_and_wait_commit(new_tracked_msgs())
+        .expect("create client");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_cancel_cycle_rolls_back_storage() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // cancel after the transaction is sent, while waiting for it to be committed
+    let token = chain.cancellation_token();
+    rpc_client.set_send_transaction_hook(move |_| token.store(true, Ordering::SeqCst));
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("cancelled");
+    assert!(matches!(err.detail(), ErrorDetail::CycleCancelled(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+    assert!(chain.native_slot_range().unwrap().is_none());
+
+    // the following cycles are aborted before building the proof
+    let err = chain
+        .create_eth_multi_client(updates, None)
+        .expect_err("cancelled");
+    assert!(matches!(err.detail(), ErrorDetail::CycleCancelled(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_second_instance_refuses_to_start() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let bootstrap = || {
+        let config = ChainConfig::Ckb(new_ckb_config(tmp_dir.path()));
+        CkbChain::bootstrap(config, Arc::new(TokioRuntime::new().unwrap()))
+    };
+
+    let chain = bootstrap().expect("first instance");
+    let lock_path = chain.instance_lock_path.clone();
+    let err = bootstrap().err().expect("already running");
+    assert!(matches!(err.detail(), ErrorDetail::AlreadyRunning(_)));
+
+    // the lock is released by the OS, even though the file is left like after a crash
+    drop(chain);
+    assert!(lock_path.exists());
+    let mut chain = bootstrap().expect("restarted instance");
+    prepare_key_and_cells(&mut chain);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+}
+
+#[test]
+fn test_update_returns_advanced_slots() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let (_, prev_tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let events = chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert!(tip_slot > prev_tip_slot);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        utils::advanced_slots_of(&events[0]),
+        Some(tip_slot - prev_tip_slot)
+    );
+}
+
+#[test]
+fn test_cached_slots_after_update() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    assert_eq!(chain.cached_minimal_slot(), None);
+    assert_eq!(chain.cached_maximal_slot(), None);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let minimal_slot = updates.first().unwrap().finalized_header.slot;
+    let prev_maximal_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    assert_eq!(chain.cached_minimal_slot(), Some(minimal_slot));
+    assert_eq!(chain.cached_maximal_slot(), Some(prev_maximal_slot));
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let maximal_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    assert_eq!(chain.cached_minimal_slot(), Some(minimal_slot));
+    assert_eq!(chain.cached_maximal_slot(), Some(maximal_slot));
+}
+
+#[test]
+fn test_restart_skips_verified_headers() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let (first_half, second_half) = updates.split_at(updates.len() / 2);
+    chain
+        .update_eth_multi_client(first_half.to_vec(), None)
+        .expect("update client");
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    apply_multi_client_update(&rpc_client, &tx);
+    let verified_slot = first_half.last().unwrap().finalized_header.slot;
+    assert_eq!(
+        chain.storage.get_verified_beacon_header_slot().unwrap(),
+        Some(verified_slot)
+    );
+
+    // restart with the same storage, while the updates are fetched from an earlier slot
+    let ckb_config = chain.effective_config();
+    drop(chain);
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    chain.rpc_client = Arc::clone(&rpc_client);
+
+    let events = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("update client after restart");
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        utils::advanced_slots_of(&events[0]),
+        Some(second_half.len() as u64)
+    );
+    assert_eq!(
+        chain.storage.get_verified_beacon_header_slot().unwrap(),
+        Some(updates.last().unwrap().finalized_header.slot)
+    );
+}
+
+#[test]
+fn test_update_budget_limits_update_cycle() {
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let max_bytes = updates[..5]
+        .iter()
+        .map(|update| serde_json::to_vec(update).unwrap().len() as u64)
+        .sum();
+    let cases = [
+        (
+            UpdateBudget {
+                max_headers: 8,
+                ..Default::default()
+            },
+            8,
+        ),
+        (
+            UpdateBudget {
+                max_bytes,
+                ..Default::default()
+            },
+            5,
+        ),
+        (
+            // 1 slot per second in the recent cycles
+            UpdateBudget {
+                max_duration: Duration::from_secs(3),
+                ..Default::default()
+            },
+            3,
+        ),
+    ];
+    for (budget, expected_slots) in cases {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.update_budget = budget;
+        let mut chain = bootstrap_chain(ckb_config);
+        prepare_key_and_cells(&mut chain);
+        let rpc_client = Arc::clone(&chain.rpc_client);
+        let part_1 = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+        chain
+            .create_eth_multi_client(part_1, None)
+            .expect("create client");
+        add_multi_client_cells_from_tx(&rpc_client, 0);
+        chain.recent_update_cycles.push_back(utils::UpdateCycle {
+            advanced_slots: 10,
+            duration: Duration::from_secs(10),
+        });
+
+        let events = chain
+            .update_eth_multi_client(updates.clone(), None)
+            .expect("update client");
+        assert_eq!(events.len(), 1);
+        assert_eq!(utils::advanced_slots_of(&events[0]), Some(expected_slots));
+        assert!(utils::is_partial_update(&events[0]), "{budget:?}");
+        let maximal_slot = updates[expected_slots as usize - 1].finalized_header.slot;
+        assert_eq!(chain.cached_maximal_slot(), Some(maximal_slot));
+    }
+
+    // all the headers fit in an unlimited budget
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let part_1 = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(part_1, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let events = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("update client");
+    assert_eq!(
+        utils::advanced_slots_of(&events[0]),
+        Some(updates.len() as u64)
+    );
+    assert!(!utils::is_partial_update(&events[0]));
+}
+
+#[test]
+fn test_next_update_client_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    // 3 clients plus the info cell
+    ckb_config.client_type_args.cells_count = 4;
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    // the last id is 0 after the creation
+    assert_eq!(chain.next_update_client_id().unwrap(), 1);
+
+    // the ids are rotated as 1, 2 and then back to 0
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let (first_half, second_half) = updates.split_at(updates.len() / 2);
+    for (tx_index, updates, next_id) in [(1, first_half, 2), (2, second_half, 0)] {
+        let expected_id = chain.next_update_client_id().unwrap();
+        chain
+            .update_eth_multi_client(updates.to_vec(), None)
+            .expect("update client");
+        let updated_id = u8::from(chain.cached_onchain_client(None).unwrap().id().as_reader());
+        assert_eq!(updated_id, expected_id);
+        let tx: packed::Transaction = rpc_client
+            .get_transaction_by_index(tx_index)
+            .unwrap()
+            .into();
+        apply_multi_client_update(&rpc_client, &tx);
+        assert_eq!(chain.next_update_client_id().unwrap(), next_id);
+    }
+}
+
+#[test]
+fn test_raw_client_bytes() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    // the last id is 0 after the creation
+    let latest_client = chain.cached_onchain_client(None).unwrap().clone();
+    assert_eq!(
+        chain.raw_client_bytes(0).expect("client 0"),
+        latest_client.as_slice()
+    );
+    let raw_client = chain.raw_client_bytes(1).expect("client 1");
+    let client = PackedClient::from_slice(&raw_client).expect("packed client");
+    assert_eq!(u8::from(client.id().as_reader()), 1);
+    assert_eq!(
+        client.maximal_slot().as_slice(),
+        latest_client.maximal_slot().as_slice()
+    );
+    // 3 cells are 2 clients plus the info cell
+    assert!(chain.raw_client_bytes(2).is_err());
+}
+
+#[test]
+fn test_refresh_cached_client() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    // a read-only monitor which shares the CKB node with the relayer
+    let mut monitor_config = chain.effective_config();
+    monitor_config.data_dir = tmp_dir.path().join("monitor");
+    let mut monitor = bootstrap_chain(monitor_config);
+    monitor.rpc_client = Arc::clone(&rpc_client);
+    assert_eq!(monitor.onchain_client_slot_range(), None);
+    monitor.refresh_cached_client().expect("refresh");
+    assert_eq!(
+        monitor.onchain_client_slot_range(),
+        chain.onchain_client_slot_range()
+    );
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    apply_multi_client_update(&rpc_client, &tx);
+    let updated_range = chain.onchain_client_slot_range();
+    assert_ne!(monitor.onchain_client_slot_range(), updated_range);
+
+    monitor.refresh_cached_client().expect("refresh");
+    assert_eq!(monitor.onchain_client_slot_range(), updated_range);
+}
+
+#[test]
+fn test_pushed_new_tip_triggers_client_check() {
+    use futures::{SinkExt as _, StreamExt as _};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let created_maximal_slot = chain.cached_maximal_slot().unwrap();
+
+    // a node which pushes a new tip once it's subscribed to, and another one on request
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+    let pushed_tips: [u64; 2] = [1_000, 1_001];
+    let (push_tx, mut push_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    chain.rt.spawn(async move {
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let _subscribe = ws.next().await;
+        for (index, number) in pushed_tips.into_iter().enumerate() {
+            if index > 0 {
+                push_rx.recv().await.unwrap();
+            }
+            let header = HeaderBuilder::default().number(number.pack()).build();
+            let header =
+                serde_json::to_string(&ckb_jsonrpc_types::HeaderView::from(header)).unwrap();
+            let notification = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "subscribe",
+                "params": { "result": header, "subscription": "0x0" },
+            });
+            ws.send(Message::Text(notification.to_string()))
+                .await
+                .unwrap();
+        }
+        // keep the subscription alive until the watcher is dropped
+        while ws.next().await.is_some() {}
+    });
+
+    // a read-only monitor which shares the CKB node with the relayer
+    let mut monitor_config = chain.effective_config();
+    monitor_config.data_dir = tmp_dir.path().join("monitor");
+    monitor_config.ckb_ws_rpc = Some(Url::from_str(&ws_url).unwrap());
+    let mut monitor = bootstrap_chain(monitor_config);
+    monitor.rpc_client = Arc::clone(&rpc_client);
+    assert!(monitor.tip_watcher.is_none());
+    let subscription = monitor.subscribe().expect("subscribe");
+    let started = Instant::now();
+    while !monitor.tip_watcher.as_ref().unwrap().is_connected() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "not subscribed"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // the client found at the first tip is announced
+    let batch = subscription
+        .recv_timeout(Duration::from_secs(10))
+        .expect("client checked at the first tip");
+    let batch = batch.as_ref().as_ref().unwrap();
+    assert_eq!(
+        batch.height,
+        utils::ckb_block_to_height(pushed_tips[0]).unwrap()
+    );
+    assert_eq!(utils::advanced_slots_of(&batch.events[0]), Some(0));
+
+    // the update by the relayer is announced at the next tip
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    apply_multi_client_update(&rpc_client, &tx);
+    push_tx.send(()).unwrap();
+    let batch = subscription
+        .recv_timeout(Duration::from_secs(10))
+        .expect("client checked at the next tip");
+    let batch = batch.as_ref().as_ref().unwrap();
+    assert_eq!(
+        batch.height,
+        utils::ckb_block_to_height(pushed_tips[1]).unwrap()
+    );
+    assert_eq!(
+        utils::advanced_slots_of(&batch.events[0]),
+        Some(chain.cached_maximal_slot().unwrap() - created_maximal_slot)
+    );
+}
+
+#[test]
+fn test_estimate_sync_eta() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    assert!(chain.estimate_sync_eta(1).is_err());
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    assert_eq!(chain.recent_update_cycles.len(), 1);
+    assert_eq!(chain.recent_update_cycles[0].advanced_slots, 32);
+
+    let maximal_slot = chain.cached_maximal_slot().unwrap();
+    assert_eq!(
+        chain.estimate_sync_eta(maximal_slot).unwrap(),
+        Duration::ZERO
+    );
+
+    // recorded cycles of 32 slots in 5 minutes
+    chain.recent_update_cycles = (0..4)
+        .map(|_| utils::UpdateCycle {
+            advanced_slots: 32,
+            duration: Duration::from_secs(300),
+        })
+        .collect();
+    let eta = chain.estimate_sync_eta(maximal_slot + 100).unwrap();
+    assert_eq!(eta, Duration::from_secs(300 * 4));
+}
+
+#[test]
+fn test_query_consensus_state_heights_by_pages() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let query = |offset, limit| {
+        let request = QueryConsensusStateHeightsRequest {
+            client_id: ClientId::default(),
+            pagination: Some(PageRequest {
+                offset,
+                limit,
+                ..Default::default()
+            }),
+        };
+        chain.query_consensus_state_heights(request).unwrap()
+    };
+    // a zero limit returns all the heights
+    let all_heights = query(0, 0);
+    let all_slots = all_heights
+        .iter()
+        .map(|height| utils::from_height(*height))
+        .collect::<Vec<_>>();
+    assert!(all_slots.len() > 1);
+    assert!(all_slots.windows(2).all(|pair| pair[0] + 1 == pair[1]));
+
+    let first_page_len = all_heights.len() as u64 / 2;
+    let mut heights = query(0, first_page_len);
+    assert_eq!(heights.len() as u64, first_page_len);
+    heights.extend(query(first_page_len, u64::MAX));
+    assert_eq!(heights, all_heights);
+    assert!(query(all_heights.len() as u64, 10).is_empty());
+}
+
+#[test]
+fn test_query_consensus_state_by_height() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let query = |consensus_height, include_proof| {
+        let request = QueryConsensusStateRequest {
+            client_id: ClientId::default(),
+            consensus_height,
+            query_height: QueryHeight::Latest,
+        };
+        chain.query_consensus_state(request, include_proof)
+    };
+    let heights = chain
+        .query_consensus_state_heights(QueryConsensusStateHeightsRequest {
+            client_id: ClientId::default(),
+            pagination: None,
+        })
+        .unwrap();
+    let first_height = heights[0];
+    let last_height = *heights.last().unwrap();
+    for height in [first_height, last_height] {
+        let (consensus_state, proof) = query(height, IncludeProof::No).unwrap();
+        assert!(matches!(consensus_state, AnyConsensusState::Eth(_)));
+        assert!(proof.is_none());
+    }
+
+    // the slots out of the range of the client have no consensus states
+    let before_first = utils::slot_to_height(utils::from_height(first_height) - 1).unwrap();
+    let after_last = utils::slot_to_height(utils::from_height(last_height) + 1).unwrap();
+    for height in [before_first, after_last] {
+        let err = query(height, IncludeProof::No).expect_err("out of range");
+        assert!(matches!(
+            err.detail(),
+            ErrorDetail::CkbConsensusStateNotFound(_)
+        ));
+    }
+
+    let err = query(first_height, IncludeProof::Yes).expect_err("no proof");
+    assert!(matches!(err.detail(), ErrorDetail::Unsupported(_)));
+}
+
+#[test]
+fn test_self_test_on_dev_network() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let hook_rpc_client = Arc::clone(&rpc_client);
+    rpc_client.set_send_transaction_hook(move |tx| {
+        add_multi_client_cells(&hook_rpc_client, &tx.clone().into());
+    });
+    // the synthetic updates aren't checked against the accepted beacon chains
+    chain.config.accepted_genesis_validators_root = vec![h256!("0x1")];
+
+    let report = chain.self_test().expect("self-test");
+    assert_eq!(report.minimal_slot, 1);
+    assert_eq!(report.maximal_slot, 4);
+    assert_eq!(report.block_number, Some(1));
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+
+    // the synthetic client is dropped, and the config and the default client are untouched
+    assert!(chain.config.keyed_client_type_args.is_empty());
+    assert!(chain.keyed_clients.is_empty());
+    assert!(!tmp_dir
+        .path()
+        .join(chain.config.id.as_str())
+        .join("self-test")
+        .exists());
+    assert!(chain.config.client_type_args.type_id.is_none());
+    assert_eq!(chain.native_slot_range().unwrap(), None);
+
+    // refuse to run on a public network
+    let chain_info = r#"{
+        "alerts": [],
+        "chain": "ckb_testnet",
+        "difficulty": "0x10000",
+        "epoch": "0x100",
+        "is_initial_block_download": false,
+        "median_time": "0x5cd2b105"
+    }"#;
+    rpc_client.set_blockchain_info(Some(chain_info));
+    *chain.cached_network.write().unwrap() = None;
+    let err = chain.self_test().expect_err("self-test on testnet");
+    assert!(matches!(err.detail(), ErrorDetail::Unsupported(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_create_and_update_with_shuffled_updates() {
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    // returns the native slot range and MMR root after both updates are processed
+    let process_updates = |shuffle: bool| {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+        prepare_key_and_cells(&mut chain);
+        let rpc_client = Arc::clone(&chain.rpc_client);
+        let load_updates = |file| {
+            let mut updates = load_updates_from_file(&testdata_dir, file);
+            if shuffle {
+                updates.push(updates[updates.len() / 2].clone());
+                updates.shuffle(&mut thread_rng());
+            }
+            updates
+        };
+
+        chain
+            .create_eth_multi_client(load_updates("headers_part_1.json"), None)
+            .expect("create client");
+        add_multi_client_cells_from_tx(&rpc_client, 0);
+        chain
+            .update_eth_multi_client(load_updates("headers_part_2.json"), None)
+            .expect("update client");
+
+        let (base_slot, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+        let root = chain
+            .storage
+            .chain_root_mmr(tip_slot)
+            .unwrap()
+            .get_root()
+            .unwrap();
+        (base_slot, tip_slot, root.as_slice().to_vec())
+    };
+    assert_eq!(process_updates(true), process_updates(false));
+}
+
+#[test]
+fn test_onchain_client_root() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    assert_eq!(chain.onchain_client_root().unwrap(), None);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    let native_root = chain
+        .storage
+        .chain_root_mmr(tip_slot)
+        .unwrap()
+        .get_root()
+        .unwrap();
+    let root = chain.onchain_client_root().unwrap().expect("on-chain root");
+    assert_eq!(root.as_bytes(), native_root.as_slice());
+}
+
+#[test]
+fn test_call_from_async_context() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // an embedder drives the chain from the tasks of its own runtime
+    let embedder_rt = TokioRuntime::new().unwrap();
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    embedder_rt
+        .block_on(async { chain.create_eth_multi_client(updates, None) })
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let root = embedder_rt
+        .block_on(async { chain.onchain_client_root() })
+        .expect("query root");
+    assert!(root.is_some());
+}
+
+#[test]
+fn test_call_from_current_thread_runtime() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    // the thread of a current-thread runtime can't be handed over, so it's an error, not a panic
+    let embedder_rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let err = embedder_rt
+        .block_on(async { chain.onchain_client_root() })
+        .expect_err("current-thread runtime");
+    assert!(matches!(err.detail(), ErrorDetail::CurrentThreadRuntime(_)));
+
+    // it still works once the embedder has left the runtime
+    let root = chain.onchain_client_root().expect("query root");
+    assert!(root.is_some());
+}
+
+#[test]
+fn test_retry_update_cycle_on_transient_failure() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.max_cycle_retries = 1;
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let (_, prev_tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+
+    // the first attempt fails to send, and the second one succeeds
+    rpc_client.fail_next_sends(1);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let events = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("update client");
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        utils::advanced_slots_of(&events[0]),
+        Some(tip_slot - prev_tip_slot)
+    );
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+
+    // give up with the last error if all attempts fail, the mock keeps the cells of the
+    // creation, so the same updates are sent again
+    rpc_client.fail_next_sends(2);
+    let err = chain
+        .update_eth_multi_client(updates, None)
+        .expect_err("all attempts fail");
+    assert!(matches!(err.detail(), ErrorDetail::CkbSendTx(_)));
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(tip_slot, prev_tip_slot);
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+}
+
+#[test]
+fn test_cycle_retry_budget_is_shared_by_calls() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.cycle_retry_budget = 2;
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    chain
+        .create_eth_multi_client(
+            load_updates_from_file(&testdata_dir, "headers_part_1.json"),
+            None,
+        )
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let (_, prev_tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+
+    // the budget is exhausted by fetching the cells
+    rpc_client.fail_next_fetches(3);
+    let err = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect_err("budget exhausted");
+    assert!(matches!(err.detail(), ErrorDetail::RpcResponse(_)), "{err}");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+
+    // the budget is exhausted by sending, after a retry of fetching the cells
+    rpc_client.fail_next_fetches(1);
+    rpc_client.fail_next_sends(2);
+    let err = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect_err("budget exhausted");
+    assert!(matches!(err.detail(), ErrorDetail::CkbSendTx(_)), "{err}");
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(tip_slot, prev_tip_slot);
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+
+    // the failures of the calls are within the budget
+    rpc_client.fail_next_fetches(1);
+    rpc_client.fail_next_sends(1);
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+}
+
+// Serves the genesis and the headers of a beacon chain like the beacon API nodes, returns the
+// URL of it.
+fn serve_beacon_api(genesis_validators_root: EthH256, headers: Vec<EthHeader>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+            }
+            let path = request_line.split(' ').nth(1).unwrap_or_default();
+            let header_opt = path
+                .strip_prefix("/eth/v1/beacon/headers/")
+                .and_then(|slot| slot.parse::<u64>().ok())
+                .and_then(|slot| headers.iter().find(|header| header.slot == slot));
+            let (status, body) = if path == "/eth/v1/beacon/genesis" {
+                let data =
+                    serde_json::json!({ "genesis_validators_root": genesis_validators_root });
+                ("200 OK", serde_json::json!({ "data": data }))
+            } else if let Some(header) = header_opt {
+                let data = serde_json::json!({
+                    "root": EthH256(header.tree_hash_root().0),
+                    "canonical": true,
+                    "header": { "message": header, "signature": "0x" },
+                });
+                ("200 OK", serde_json::json!({ "data": data }))
+            } else {
+                let body = serde_json::json!({ "code": 404, "message": "not found" });
+                ("404 Not Found", body)
+            };
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\
+                 connection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = reader.get_mut().write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+// Accepts one HTTP request and responds with 200, returns the body of the request.
+fn serve_one_http_request(listener: TcpListener) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    })
+}
+
+#[cfg(feature = "ckb-status-server")]
+fn http_get(addr: std::net::SocketAddr, path: &str) -> (String, String) {
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nhost: {addr}\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status_line = head.lines().next().unwrap().to_owned();
+    (status_line, body.to_owned())
+}
+
+#[cfg(feature = "ckb-status-server")]
+#[test]
+fn test_serve_status() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.status_server_addr = Some("127.0.0.1:0".parse().unwrap());
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let addr = chain.status_server.as_ref().unwrap().local_addr();
+
+    let (status_line, body) = http_get(addr, "/status");
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+    let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(status["native_slot_range"], serde_json::Value::Null);
+    assert_eq!(status["onchain_client_slot_range"], serde_json::Value::Null);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let minimal_slot = updates.first().unwrap().finalized_header.slot;
+    let maximal_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+
+    let (_, body) = http_get(addr, "/status");
+    let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(status, chain.status_json().unwrap());
+    assert_eq!(
+        status["onchain_client_slot_range"],
+        serde_json::json!([minimal_slot, maximal_slot])
+    );
+    assert_eq!(status["chain_id"], chain.id().to_string());
+
+    let (status_line, _) = http_get(addr, "/unknown");
+    assert_eq!(status_line, "HTTP/1.1 404 Not Found");
+
+    // a client which sends nothing doesn't stall the server, nor an endless request line
+    let idle_client = std::net::TcpStream::connect(addr).unwrap();
+    let mut flooding_client = std::net::TcpStream::connect(addr).unwrap();
+    let _ = flooding_client.write_all(&[b'a'; 64 * 1024]);
+    let (status_line, _) = http_get(addr, "/status");
+    assert_eq!(status_line, "HTTP/1.1 200 OK");
+    drop((idle_client, flooding_client));
+
+    // the server is stopped with the chain
+    chain.shutdown().unwrap();
+    assert!(std::net::TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn test_notify_committed_update() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let webhook_url = format!("http://{}/progress", listener.local_addr().unwrap());
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.commit_webhook_url = Some(Url::from_str(&webhook_url).unwrap());
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let server = serve_one_http_request(listener);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let events = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect("update client");
+    let payload: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    let tx_hash: H256 = tx.calc_tx_hash().unpack();
+    let (_, maximal_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(events.len(), 1);
+    assert_eq!(payload["tx_hash"], format!("{tx_hash:#x}"));
+    assert_eq!(payload["maximal_slot"], maximal_slot);
+    assert!(payload["block_number"].is_u64());
+
+    // nobody listens to the webhook now, but the update still succeeds
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client without notification");
+    assert_eq!(rpc_client.get_transactions_len(), 3);
+}
+
+#[test]
+fn test_contract_celldep_missing_at_assembly() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the contract cell is consumed after bootstrap
+    let contract = packed::Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.0.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(
+            chain
+                .config
+                .lightclient_contract_typeargs
+                .as_bytes()
+                .to_vec()
+                .pack(),
+        )
+        .build();
+    let key: SearchKey = CellQueryOptions::new(contract, PrimaryScriptType::Type).into();
+    rpc_client.remove_cells(&key);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .create_eth_multi_client(updates, None)
+        .expect_err("missing contract cell dep");
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::ContractCelldepMissing(_)
+    ));
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+    assert_eq!(chain.native_slot_range().unwrap(), None);
+}
+
+// Adds a type-id contract cell, returns its out point for checking the cell deps.
+fn add_typeid_contract_cell(rpc_client: &RpcClient, type_args: &H256) -> packed::OutPoint {
+    let contract = packed::Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.0.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(type_args.as_bytes().to_vec().pack())
+        .build();
+    let output = packed::CellOutput::new_builder()
+        .type_(Some(contract.clone()).pack())
+        .build_exact_capacity(Capacity::bytes(100_000).unwrap())
+        .unwrap();
+    let cell = random_cell(1003, output, Default::default());
+    let out_point = cell.out_point.clone().into();
+    let key: SearchKey = CellQueryOptions::new(contract, PrimaryScriptType::Type).into();
+    rpc_client.add_cell(&key, cell);
+    out_point
+}
+
+#[test]
+fn test_spend_cells_under_retired_lock() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let old_lock_typeargs = h256!("0x789");
+    let new_lock_typeargs = h256!("0xabc");
+
+    let mut config = new_ckb_config(tmp_dir.path());
+    config.lightclient_lock_typeargs = old_lock_typeargs.clone();
+    let mut chain = bootstrap_chain(config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let old_lock_out_point = add_typeid_contract_cell(&rpc_client, &old_lock_typeargs);
+    let new_lock_out_point = add_typeid_contract_cell(&rpc_client, &new_lock_typeargs);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let prev_slot_range = chain.native_slot_range().unwrap();
+
+    // rotate the lock, the cells under the old lock can't be spent before it's accepted
+    chain.config.lightclient_lock_typeargs = new_lock_typeargs.clone();
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect_err("unaccepted lock");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+    assert_eq!(chain.native_slot_range().unwrap(), prev_slot_range);
+
+    chain.config.retired_lock_typeargs = vec![old_lock_typeargs];
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    let cell_deps = tx
+        .raw()
+        .cell_deps()
+        .into_iter()
+        .map(|dep| dep.out_point())
+        .collect::<Vec<_>>();
+    assert!(cell_deps.contains(&old_lock_out_point));
+    assert!(cell_deps.contains(&new_lock_out_point));
+
+    let new_lock_code_hash = packed::Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.0.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(new_lock_typeargs.as_bytes().to_vec().pack())
+        .build()
+        .calc_script_hash();
+    // the info cell and the updated client cell
+    for output in tx.raw().outputs().into_iter().take(2) {
+        assert_eq!(output.lock().code_hash(), new_lock_code_hash);
+    }
+}
+
+#[test]
+fn test_chain_id_in_untyped_errors() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let chain_id = chain.id().to_string();
+
+    // no multi-client is created to update
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .update_eth_multi_client(updates.clone(), None)
+        .expect_err("no type id");
+    assert!(matches!(err.detail(), ErrorDetail::OtherError(_)));
+    assert!(err.to_string().contains(&format!("[{chain_id}]")));
+
+    // the typed errors are untouched
+    let beacon_api_url = serve_beacon_api(EthH256::repeat_byte(2), vec![]);
+    chain.beacon_client = Some(BeaconClient::new(&[beacon_api_url]));
+    chain.config.accepted_genesis_validators_root = vec![h256!("0x1")];
+    let err = chain
+        .create_eth_multi_client(updates, None)
+        .expect_err("wrong genesis");
+    assert!(matches!(err.detail(), ErrorDetail::GenesisMismatch(_)));
+    assert!(!err.to_string().contains(&format!("[{chain_id}]")));
+}
+
+#[test]
+fn test_create_eth_multi_client_with_wrong_genesis() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let accepted_root = h256!("0x1234");
+    let wrong_root = h256!("0x5678");
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let headers = updates
+        .iter()
+        .map(|update| update.finalized_header.clone())
+        .collect::<Vec<_>>();
+
+    // the roots are accepted only with the beacon API nodes to check them
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.accepted_genesis_validators_root = vec![accepted_root.clone()];
+    assert!(ckb_config.validate().is_err());
+
+    // the beacon API nodes of a wrong beacon chain
+    ckb_config.beacon_api_urls = vec![serve_beacon_api(
+        EthH256::from_slice(wrong_root.as_bytes()),
+        headers.clone(),
+    )];
+    let mut chain = bootstrap_chain(ckb_config.clone());
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("wrong genesis should be rejected");
+    assert!(matches!(err.detail(), ErrorDetail::GenesisMismatch(_)));
+    assert_eq!(chain.native_slot_range().unwrap(), None);
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+
+    // the beacon API nodes of an accepted beacon chain, which doesn't have the updates
+    chain.beacon_client = Some(BeaconClient::new(&[serve_beacon_api(
+        EthH256::from_slice(accepted_root.as_bytes()),
+        vec![],
+    )]));
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("updates from another beacon chain should be rejected");
+    assert!(matches!(err.detail(), ErrorDetail::BeaconHeaderMismatch(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+
+    chain.beacon_client = Some(BeaconClient::new(&[serve_beacon_api(
+        EthH256::from_slice(accepted_root.as_bytes()),
+        headers,
+    )]));
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_create_eth_multi_client_with_tampered_header() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let mut updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let tampered = updates
+        .iter_mut()
+        .skip(1)
+        .find(|update| !update.finalized_header.is_empty())
+        .expect("a non-empty header");
+    tampered.finalized_header.parent_root = EthH256::repeat_byte(0xff);
+    let tampered_slot = tampered.finalized_header.slot;
+
+    let err = chain
+        .create_eth_multi_client(updates, None)
+        .expect_err("tampered header should be rejected");
+    match err.detail() {
+        ErrorDetail::ProofUpdateVerification(e) => assert_eq!(e.slot, Some(tampered_slot)),
+        _ => panic!("unexpected error: {err}"),
+    }
+    assert!(err.to_string().contains(&format!("slot {tampered_slot}")));
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+}
+
+#[test]
+fn test_query_upgraded_states_unsupported() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let upgrade_height = Height::new(0, 1).unwrap();
+
+    let err = chain
+        .query_upgraded_client_state(QueryUpgradedClientStateRequest { upgrade_height })
+        .expect_err("upgraded client state is unsupported");
+    assert!(matches!(err.detail(), ErrorDetail::Unsupported(_)));
+
+    let err = chain
+        .query_upgraded_consensus_state(QueryUpgradedConsensusStateRequest { upgrade_height })
+        .expect_err("upgraded consensus state is unsupported");
+    assert!(matches!(err.detail(), ErrorDetail::Unsupported(_)));
+}
+
+#[test]
+fn test_capabilities_match_implemented_methods() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let capabilities = chain.capabilities();
+
+    // an operation is implemented if it neither panics nor reports that it's unsupported
+    fn is_implemented<T>(operation: impl FnOnce() -> Result<T, Error>) -> bool {
+        match panic::catch_unwind(AssertUnwindSafe(operation)) {
+            Ok(Ok(_)) => true,
+            Ok(Err(err)) => !matches!(err.detail(), ErrorDetail::Unsupported(_)),
+            Err(_) => false,
+        }
+    }
+    let client_id = ClientId::default();
+    let height = Height::new(0, 1).unwrap();
+    let new_tracked_msgs = || TrackedMsgs {
+        msgs: vec![],
+        tracking_id: TrackingId::Static("probe"),
+    };
+
+    let actual = ChainCapabilities {
+        health_check: is_implemented(|| chain.health_check()),
+        send_messages_and_wait_commit: is_implemented(|| {
+            chain.send_messages_and_wait_commit(new_tracked_msgs())
+        }),
+        send_messages_and_wait_check_tx: is_implemented(|| {
+            chain.send_messages_and_wait_check_tx(new_tracked_msgs())
+        }),
+        query_application_status: is_implemented(|| chain.query_application_status()),
+        query_clients: is_implemented(|| {
+            chain.query_clients(QueryClientStatesRequest { pagination: None })
+        }),
+        query_client_state: is_implemented(|| {
+            chain.query_client_state(
+                QueryClientStateRequest {
+                    client_id: client_id.clone(),
+                    height: QueryHeight::Latest,
+                },
+                IncludeProof::No,
+            )
+        }),
+        query_consensus_state: is_implemented(|| {
+            chain.query_consensus_state(
+                QueryConsensusStateRequest {
+                    client_id: client_id.clone(),
+                    consensus_height: height,
+                    query_height: QueryHeight::Latest,
+                },
+                IncludeProof::No,
+            )
+        }),
+        query_consensus_state_heights: is_implemented(|| {
+            chain.query_consensus_state_heights(QueryConsensusStateHeightsRequest {
+                client_id: client_id.clone(),
+                pagination: None,
+            })
+        }),
+        query_upgraded_states: is_implemented(|| {
+            chain.query_upgraded_client_state(QueryUpgradedClientStateRequest {
+                upgrade_height: height,
+            })
+        }) && is_implemented(|| {
+            chain.query_upgraded_consensus_state(QueryUpgradedConsensusStateRequest {
+                upgrade_height: height,
+            })
+        }),
+        query_connections: is_implemented(|| {
+            chain.query_connections(QueryConnectionsRequest { pagination: None })
+        }),
+        query_channels: is_implemented(|| {
+            chain.query_channels(QueryChannelsRequest { pagination: None })
+        }),
+        query_packet_commitments: is_implemented(|| {
+            chain.query_packet_commitments(QueryPacketCommitmentsRequest {
+                port_id: PortId::transfer(),
+                channel_id: ChannelId::default(),
+                pagination: None,
+            })
+        }),
+        query_txs: is_implemented(|| {
+            chain.query_txs(QueryTxRequest::Transaction(QueryTxHash(TxHash::None)))
+        }),
+        build_header: is_implemented(|| {
+            let client_state = AnyClientState::Ckb(CkbClientState {
+                chain_id: chain.id(),
+            });
+            chain.build_header(height, height.increment(), &client_state)
+        }),
+        subscribe: is_implemented(|| chain.subscribe()),
+    };
+    assert_eq!(capabilities, actual);
+}
+
+#[test]
+fn test_subscribe_overflow_policy() {
+    let chain_id = ChainId::new("chainA".to_string(), 10);
+    let new_batch = |height: u64| EventBatch {
+        chain_id: chain_id.clone(),
+        tracking_id: TrackingId::Static(NonCosmosTrackingId::ETH_UPDATE_CLIENT),
+        height: Height::new(0, height).unwrap(),
+        events: vec![],
+    };
+    let received_height = |subscription: &crate::chain::handle::Subscription| {
+        let batch = subscription
+            .recv_timeout(Duration::from_secs(1))
+            .expect("receive a batch");
+        batch.as_ref().as_ref().unwrap().height.revision_height()
+    };
+
+    // the oldest batches are dropped
+    let mut stream = EventStream::new(chain_id.clone(), 2, SubscribeOverflow::DropOldest);
+    let subscription = stream.subscribe();
+    for height in 1..=3 {
+        stream.broadcast(new_batch(height));
+    }
+    // wait for the relay to buffer the batches
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(received_height(&subscription), 2);
+    assert_eq!(received_height(&subscription), 3);
+    assert!(subscription
+        .recv_timeout(Duration::from_millis(200))
+        .is_err());
+
+    // a dropped subscriber is pruned
+    drop(subscription);
+    let mut height = 4;
+    while stream.subscribers_count() > 0 {
+        assert!(height < 100, "the dropped subscriber isn't pruned");
+        stream.broadcast(new_batch(height));
+        thread::sleep(Duration::from_millis(10));
+        height += 1;
+    }
+
+    // the broadcast is blocked until the subscriber has room
+    let mut stream = EventStream::new(chain_id.clone(), 1, SubscribeOverflow::Block);
+    let subscription = stream.subscribe();
+    let batches = vec![new_batch(1), new_batch(2)];
+    let handle = thread::spawn(move || {
+        for batch in batches {
+            stream.broadcast(batch);
+        }
+    });
+    thread::sleep(Duration::from_millis(200));
+    assert!(!handle.is_finished());
+    assert_eq!(subscription.len(), 1);
+    assert_eq!(received_height(&subscription), 1);
+    handle.join().unwrap();
+    assert_eq!(received_height(&subscription), 2);
+
+    // the chain subscribes with the configured capacity
+    let tmp_dir = TempDir::new().unwrap();
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.subscribe_capacity = 8;
+    ckb_config.subscribe_overflow = SubscribeOverflow::Block;
+    let mut chain = bootstrap_chain(ckb_config);
+    assert_eq!(chain.subscribe().unwrap().capacity(), Some(8));
+}
+
+#[test]
+fn test_request_limiter_caps_in_flight_requests() {
+    let max_concurrent_requests = 2;
+    let limiter = RequestLimiter::new(max_concurrent_requests);
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+    let requests = (0..8)
+        .map(|_| {
+            let in_flight = Arc::clone(&in_flight);
+            let max_in_flight = Arc::clone(&max_in_flight);
+            limiter.limit(async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let rt = TokioRuntime::new().unwrap();
+    let results = rt.block_on(futures::future::join_all(requests));
+    // all requests are queued and finished, none of them fails
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(
+        max_in_flight.load(Ordering::SeqCst),
+        max_concurrent_requests
+    );
+}
+
+#[test]
+fn test_request_timeout_of_method_overrides_default() {
+    let method_timeouts = [("send_transaction".to_owned(), Duration::from_millis(50))];
+    let timeouts = RequestTimeouts::new(Duration::from_secs(5), BTreeMap::from(method_timeouts));
+    assert_eq!(
+        timeouts.timeout_of("send_transaction"),
+        Duration::from_millis(50)
+    );
+    assert_eq!(timeouts.timeout_of("get_cells"), Duration::from_secs(5));
+
+    let request = |method: &str| {
+        timeouts.limit(method, async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(())
+        })
+    };
+    let rt = TokioRuntime::new().unwrap();
+
+    // the method-specific timeout is shorter than the request
+    let start = Instant::now();
+    let err = rt
+        .block_on(request("send_transaction"))
+        .expect_err("timed out");
+    assert!(matches!(err.detail(), ErrorDetail::RpcResponse(_)));
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    // the other methods fall back to the default one
+    rt.block_on(request("get_cells"))
+        .expect("within default timeout");
+}
+
+#[test]
+fn test_next_cycle_waits_for_indexer() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the indexer hasn't reached the block of the first transaction (block 1 in mock)
+    rpc_client.set_indexer_tip(Some(0));
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+
+    let indexer_delay = Duration::from_secs(1);
+    let indexer = {
+        let rpc_client = Arc::clone(&rpc_client);
+        thread::spawn(move || {
+            thread::sleep(indexer_delay);
+            rpc_client.set_indexer_tip(Some(1));
+        })
+    };
+    let start = Instant::now();
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    // the result doesn't matter, only when the cells are read
+    let _ = chain.update_eth_multi_client(updates, None);
+    assert!(start.elapsed() >= indexer_delay);
+    indexer.join().unwrap();
+}
+
+#[test]
+fn test_handle_stuck_tx_after_grace_period() {
+    let url = Url::from_str("http://ckb_rpc").unwrap();
+    let rpc_client = Arc::new(RpcClient::new(&url, &url));
+    let rt = TokioRuntime::new().unwrap();
+    let interval = Duration::from_millis(100);
+    let stuck_after = Duration::from_millis(250);
+
+    let wait = |time_limit: Duration, stuck_times: &mut Vec<Duration>| {
+        let mut on_stuck = |time_used: Duration| stuck_times.push(time_used);
+        let stuck_handler = utils::StuckHandler {
+            stuck_after,
+            on_stuck: &mut on_stuck,
+        };
+        rt.block_on(utils::wait_ckb_transaction_committed(
+            &rpc_client,
+            h256!("0x1"),
+            interval,
+            0,
+            time_limit,
+            None,
+            Some(stuck_handler),
+        ))
+    };
+
+    // pending at the first 5 polls, so it's stuck at the 3rd to 5th polls
+    rpc_client.set_pending_polls(5);
+    let mut stuck_times = vec![];
+    wait(Duration::from_secs(60), &mut stuck_times).expect("committed");
+    assert_eq!(
+        stuck_times,
+        (3..=5).map(|polls| interval * polls).collect::<Vec<_>>()
+    );
+
+    // the hard timeout still aborts a stuck transaction
+    rpc_client.set_pending_polls(usize::MAX);
+    let mut stuck_times = vec![];
+    let result = wait(Duration::from_millis(500), &mut stuck_times);
+    assert!(matches!(
+        result.unwrap_err().detail(),
+        ErrorDetail::SendTx(_)
+    ));
+    assert_eq!(stuck_times.len(), 4);
+    assert!(stuck_times
+        .iter()
+        .all(|time_used| *time_used >= stuck_after));
+}
+
+#[test]
+fn test_no_cycle_retry_after_tx_sent() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.max_cycle_retries = 1;
+        ckb_config.tx_confirmations = 3;
+        bootstrap_chain(ckb_config)
+    };
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let (_, prev_tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+
+    // the sent transaction is reorged out, another one over the same cells isn't assembled
+    rpc_client.set_tip_number(Some(1));
+    rpc_client.set_reorg_after_polls(Some(2));
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let err = chain
+        .update_eth_multi_client(updates, None)
+        .expect_err("reorged out");
+    assert!(matches!(err.detail(), ErrorDetail::CkbTxReorged(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 2);
+    let (_, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(tip_slot, prev_tip_slot);
+}
+
+#[test]
+fn test_rollback_storage_on_reorged_tx() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.tx_confirmations = 3;
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // committed in block 1 (in mock) while the tip stays there, then reorged out
+    rpc_client.set_tip_number(Some(1));
+    rpc_client.set_reorg_after_polls(Some(2));
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect_err("reorged out");
+    let ErrorDetail::CkbTxReorged(detail) = err.detail() else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(detail.block_number, 1);
+    assert_eq!(chain.storage.get_tip_beacon_header_slot().unwrap(), None);
+
+    // the storage is kept after enough confirmations
+    rpc_client.set_reorg_after_polls(None);
+    rpc_client.set_tip_number(Some(4));
+    chain
+        .create_eth_multi_client(updates.clone(), None)
+        .expect("create client");
+    let tip_slot = updates.last().unwrap().finalized_header.slot;
+    assert_eq!(
+        chain.storage.get_tip_beacon_header_slot().unwrap(),
+        Some(tip_slot)
+    );
+}
+
+#[test]
+fn test_wait_tx_committed_with_fake_clock() {
+    // advances instantly, and records the delays which the waiter sleeps
+    #[derive(Default)]
+    struct FakeClock {
+        slept: Mutex<Vec<Duration>>,
+        // Milliseconds since the UNIX epoch
+        now: u64,
+    }
+
+    impl utils::Clock for FakeClock {
+        fn sleep(
+            &self,
+            duration: Duration,
+        ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 'static>> {
+            self.slept.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+
+        fn now(&self) -> SystemTime {
+            UNIX_EPOCH + Duration::from_millis(self.now)
+        }
+    }
+
+    let url = Url::from_str("http://ckb_rpc").unwrap();
+    let rpc_client = Arc::new(RpcClient::new(&url, &url));
+    let rt = TokioRuntime::new().unwrap();
+    let interval = Duration::from_secs(10);
+    let wait = |time_limit: Duration, clock: &FakeClock| {
+        rt.block_on(utils::wait_ckb_transaction_committed_with_clock(
+            &rpc_client,
+            h256!("0x1"),
+            interval,
+            0,
+            time_limit,
+            None,
+            None,
+            clock,
+        ))
+    };
+    let start = Instant::now();
+
+    // committed at the 4th poll, and confirmed at the next one
+    rpc_client.set_pending_polls(3);
+    let clock = FakeClock::default();
+    wait(Duration::from_secs(600), &clock).expect("committed");
+    assert_eq!(*clock.slept.lock().unwrap(), vec![interval; 5]);
+
+    // timeout after the time limit is used up
+    rpc_client.set_pending_polls(usize::MAX);
+    let clock = FakeClock::default();
+    let result = wait(Duration::from_secs(60), &clock);
+    assert!(matches!(
+        result.unwrap_err().detail(),
+        ErrorDetail::SendTx(_)
+    ));
+    assert_eq!(*clock.slept.lock().unwrap(), vec![interval; 7]);
+
+    // the blocks are produced every 8 seconds and the tip is produced 3 seconds ago, so the
+    // first poll is just after the next block, i.e. 5 seconds later with a margin of 500 ms
+    rpc_client.set_pending_polls(0);
+    rpc_client.set_tip_number(Some(100));
+    rpc_client.set_block_timestamp(90, 1_000_000);
+    rpc_client.set_tip_timestamp(1_080_000);
+    let clock = FakeClock {
+        now: 1_083_000,
+        ..Default::default()
+    };
+    wait(Duration::from_secs(600), &clock).expect("committed");
+    assert_eq!(
+        *clock.slept.lock().unwrap(),
+        vec![Duration::from_millis(5_500), interval]
+    );
+
+    // no real delay is taken
+    assert!(start.elapsed() < interval);
+}
+
+#[test]
+fn test_verify_signatures_with_corrupted_witness() {
+    let key = new_test_key(false);
+    let lock_script: packed::Script = (&AddressPayload::from_pubkey(&key.public_key)).into();
+    let lock_args = lock_script.args().raw_data();
+    let input = packed::CellOutput::new_builder()
+        .lock(lock_script)
+        .build_exact_capacity(Capacity::zero())
+        .unwrap();
+    let inputs = vec![input.clone(), input];
+    let tx = TransactionBuilder::default()
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .output(Default::default())
+        .output_data(Default::default())
+        .build();
+
+    let tx = signer::sign(tx, &inputs, vec![], key).unwrap();
+    signer::verify(&tx, &inputs, &lock_args).expect("valid signatures");
+
+    // wrong signer
+    let err = signer::verify(&tx, &inputs, &[0u8; 20]).expect_err("wrong signer");
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::CkbSignatureVerification(_)
+    ));
+
+    // corrupt the signature in the first witness
+    let corrupted_tx = {
+        let witnesses = tx.witnesses();
+        let witness_args =
+            packed::WitnessArgs::from_slice(&witnesses.get(0).unwrap().raw_data()).unwrap();
+        let mut signature = witness_args.lock().to_opt().unwrap().raw_data().to_vec();
+        signature[0] ^= 0xff;
+        let witness = witness_args
+            .as_builder()
+            .lock(Some(Bytes::from(signature)).pack())
+            .build();
+        tx.as_advanced_builder()
+            .set_witnesses(vec![witness.as_bytes().pack(), witnesses.get(1).unwrap()])
+            .build()
+    };
+    let err = signer::verify(&corrupted_tx, &inputs, &lock_args).expect_err("corrupted witness");
+    assert!(matches!(
+        err.detail(),
+        ErrorDetail::CkbSignatureVerification(_)
+    ));
+}
+
+fn new_fee_payer_key() -> Secp256k1KeyPair {
+    let secret_key = SecretKey::from_slice(&[0x11; 32]).unwrap();
+    Secp256k1KeyPair::from_ckb_private_key(secret_key, NetworkType::Testnet)
+}
+
+#[test]
+fn test_address_for_pubkey() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    // the key of the genesis issued cells on a dev chain
+    let secret_key =
+        SecretKey::from_str("d00c06bfd800d27397002dca6fb0993d5ba6399b4238b2f29ee9deb97593d2bc")
+            .unwrap();
+    let key = Secp256k1KeyPair::from_ckb_private_key(secret_key, NetworkType::Dev);
+    assert_eq!(
+        hex::encode(key.public_key.serialize()),
+        "03fe6c6d09d1a0f70255cddf25c5ed57d41b5c08822ae710dc10f8c88290e0acdf"
+    );
+
+    let address = chain.address_for_pubkey(&key.public_key).unwrap();
+    assert_eq!(
+        address.to_string(),
+        "ckt1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqwgx292hnvmn68xf779vmzrshpmm6epn4c0cgwga"
+    );
+
+    // the network is followed after it's reported by the node
+    let chain_info = r#"{
+        "alerts": [],
+        "chain": "ckb",
+        "difficulty": "0x10000",
+        "epoch": "0x100",
+        "is_initial_block_download": false,
+        "median_time": "0x5cd2b105"
+    }"#;
+    chain.rpc_client.set_blockchain_info(Some(chain_info));
+    *chain.cached_network.write().unwrap() = None;
+    let address = chain.address_for_pubkey(&key.public_key).unwrap();
+    assert_eq!(
+        address.to_string(),
+        "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqwgx292hnvmn68xf779vmzrshpmm6epn4cp2rpz9"
+    );
+}
+
+#[test]
+fn test_address_for_pubkey_with_lock_args_suffix() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.lock_args_suffix = JsonBytes::from_vec(vec![0xde, 0xad, 0xbe, 0xef]);
+    // the sighash lock rejects the args other than the pubkey hash
+    let err = ckb_config
+        .validate()
+        .expect_err("suffix of the sighash lock");
+    let ConfigErrorDetail::SuffixedLockRequired(_) = err.detail() else {
+        panic!("unexpected config error: {err}");
+    };
+
+    let lock_code_hash = h256!("0xabcd");
+    let lock_cell_dep = packed::CellDep::new_builder()
+        .out_point(random_out_point())
+        .build();
+    ckb_config.suffixed_lock = Some(SuffixedLock {
+        code_hash: lock_code_hash.clone(),
+        hash_type: ScriptHashType::Type.into(),
+        cell_dep: lock_cell_dep.clone().into(),
+    });
+    ckb_config.validate().expect("suffixed lock");
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let key: Secp256k1KeyPair = chain.keybase().get_key(&chain.config.key_name).unwrap();
+    let lock_script: packed::Script = chain.tx_assembler_address().unwrap().payload().into();
+    let pubkey_hash = AddressPayload::from_pubkey(&key.public_key).args();
+    let expected_args = [pubkey_hash.as_ref(), &[0xde, 0xad, 0xbe, 0xef]].concat();
+    assert_eq!(
+        lock_script.args().raw_data().as_ref(),
+        expected_args.as_slice()
+    );
+    assert_eq!(lock_script.code_hash(), lock_code_hash.pack());
+    assert_eq!(lock_script.hash_type(), ScriptHashType::Type.into());
+
+    // the inputs under the suffixed lock are still signed and verified by the key
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    let tx: packed::Transaction = rpc_client.get_transaction_by_index(0).unwrap().into();
+    let change = tx.raw().outputs().into_iter().last().unwrap();
+    assert_eq!(change.lock(), lock_script);
+    assert!(tx
+        .raw()
+        .cell_deps()
+        .into_iter()
+        .any(|cell_dep| cell_dep == lock_cell_dep));
+}
+
+#[test]
+fn test_query_sudt_balance_by_type_hash() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let ckb_balance = chain.query_balance(None, None).expect("ckb balance");
+    assert_eq!(ckb_balance.denom, "ckb");
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let sudt_type_script = |owner: &[u8]| {
+        packed::Script::new_builder()
+            .code_hash(h256!("0x5e7a").pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(owner.pack())
+            .build()
+    };
+    let sudt = sudt_type_script(&[1]);
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    for (type_script, amount) in [(&sudt, 100u128), (&sudt, 23), (&sudt_type_script(&[2]), 7)] {
+        let output = packed::CellOutput::new_builder()
+            .lock(lock_script.clone())
+            .type_(Some(type_script.clone()).pack())
+            .build_exact_capacity(Capacity::bytes(16).unwrap())
+            .unwrap();
+        let cell = random_cell(1003, output, amount.to_le_bytes().to_vec());
+        chain.rpc_client.add_cell(&key, cell);
+    }
+
+    let type_hash: H256 = sudt.calc_script_hash().unpack();
+    let balance = chain
+        .query_balance(None, Some(&format!("{type_hash:#x}")))
+        .expect("sudt balance");
+    assert_eq!(balance.amount, "123");
+    assert_eq!(balance.denom, format!("{type_hash:#x}"));
+
+    // the sUDT cells are excluded from the capacity
+    let balance = chain.query_balance(None, None).expect("ckb balance");
+    assert_eq!(balance.amount, ckb_balance.amount);
+
+    assert!(chain.query_balance(None, Some("usdt")).is_err());
+}
+
+#[test]
+fn test_sign_inputs_with_distinct_keys() {
+    let key = new_test_key(false);
+    let fee_payer = new_fee_payer_key();
+    let lock_script: packed::Script = (&AddressPayload::from_pubkey(&key.public_key)).into();
+    let fee_payer_lock_script: packed::Script =
+        (&AddressPayload::from_pubkey(&fee_payer.public_key)).into();
+    let lock_args = lock_script.args().raw_data();
+    let fee_payer_lock_args = fee_payer_lock_script.args().raw_data();
+    let new_input = |lock_script: &packed::Script| {
+        packed::CellOutput::new_builder()
+            .lock(lock_script.clone())
+            .build_exact_capacity(Capacity::zero())
+            .unwrap()
+    };
+    let inputs = vec![new_input(&lock_script), new_input(&fee_payer_lock_script)];
+    let tx = TransactionBuilder::default()
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .output(Default::default())
+        .output_data(Default::default())
+        .build();
+
+    let fee_payer_lock_hash = fee_payer_lock_script.calc_script_hash();
+    let tx = signer::sign_with_keys(
+        tx,
+        &inputs,
+        vec![],
+        key,
+        &[(fee_payer_lock_hash.clone(), fee_payer)],
+    )
+    .unwrap();
+    signer::verify_with_keys(
+        &tx,
+        &inputs,
+        &lock_args,
+        &[(fee_payer_lock_hash, fee_payer_lock_args)],
+    )
+    .expect("valid signatures");
+
+    // the second input isn't signed by the main key
+    let err = signer::verify(&tx, &inputs, &lock_args).expect_err("signed by the fee payer");
+    match err.detail() {
+        ErrorDetail::CkbSignatureVerification(e) => assert_eq!(e.input_index, 1),
+        _ => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test]
+fn test_send_tx_with_fee_payer_key() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.fee_payer_key = Some("fee-payer".to_owned());
+        bootstrap_chain(ckb_config)
+    };
+    let fee_payer = new_fee_payer_key();
+    let fee_payer_lock_script: packed::Script =
+        (&AddressPayload::from_pubkey(&fee_payer.public_key)).into();
+    chain.keybase_mut().add_key("fee-payer", fee_payer).unwrap();
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // the fees are paid by the fee payer
+    let address_lock_script: packed::Script =
+        chain.tx_assembler_address().unwrap().payload().into();
+    assert_eq!(address_lock_script, fee_payer_lock_script);
+
+    // the light-client cell is authorized by the main key
+    let client_lock_script = packed::Script::new_builder()
+        .code_hash(random_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .build();
+    let inputs = vec![
+        packed::CellOutput::new_builder()
+            .lock(client_lock_script.clone())
+            .capacity(Capacity::bytes(100).unwrap().pack())
+            .build(),
+        packed::CellOutput::new_builder()
+            .lock(fee_payer_lock_script.clone())
+            .capacity(Capacity::bytes(200).unwrap().pack())
+            .build(),
+    ];
+    let tx = TransactionBuilder::default()
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .output(
+            packed::CellOutput::new_builder()
+                .lock(client_lock_script)
+                .capacity(Capacity::bytes(100).unwrap().pack())
+                .build(),
+        )
+        .output_data(Default::default())
+        .output(
+            packed::CellOutput::new_builder()
+                .lock(fee_payer_lock_script)
+                .capacity(Capacity::bytes(199).unwrap().pack())
+                .build(),
+        )
+        .output_data(Default::default())
+        .build();
+    chain
+        .sign_and_send_transaction(tx, inputs)
+        .expect("send tx");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_refuse_to_send_underfunded_tx() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let lock_script: packed::Script = chain.tx_assembler_address().unwrap().payload().into();
+    let input = packed::CellOutput::new_builder()
+        .lock(lock_script.clone())
+        .capacity(Capacity::bytes(100).unwrap().pack())
+        .build();
+    let output = packed::CellOutput::new_builder()
+        .lock(lock_script)
+        .capacity(Capacity::bytes(200).unwrap().pack())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(packed::CellInput::new(random_out_point(), 0))
+        .output(output)
+        .output_data(Default::default())
+        .build();
+
+    let err = chain
+        .sign_and_send_transaction(tx, vec![input])
+        .expect_err("underfunded tx");
+    match err.detail() {
+        ErrorDetail::InsufficientCapacity(e) => {
+            assert_eq!(e.have, Capacity::bytes(100).unwrap().as_u64());
+            assert_eq!(e.need, Capacity::bytes(200).unwrap().as_u64());
+        }
+        _ => panic!("unexpected error: {err}"),
+    }
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+}
+
+#[test]
+fn test_effective_config_has_created_type_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    assert!(chain.effective_config().client_type_args.type_id.is_none());
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+
+    let config = chain.effective_config();
+    assert!(config.client_type_args.type_id.is_some());
+    assert_eq!(
+        config.client_type_args.type_id,
+        chain.config.client_type_args.type_id
+    );
+}
+
+#[test]
+fn test_storage_isolated_by_chain_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let mut chain_a = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let chain_b = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.id = ChainId::new("chainB".to_string(), 10);
+        bootstrap_chain(ckb_config)
+    };
+    let storage_dir_a = tmp_dir.path().join(chain_a.config.id.as_str());
+    let storage_dir_b = tmp_dir.path().join(chain_b.config.id.as_str());
+    assert_ne!(storage_dir_a, storage_dir_b);
+    assert!(storage_dir_a.join("CURRENT").is_file());
+    assert!(storage_dir_b.join("CURRENT").is_file());
+
+    prepare_key_and_cells(&mut chain_a);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    chain_a
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    assert!(chain_a.native_slot_range().unwrap().is_some());
+    assert_eq!(chain_b.native_slot_range().unwrap(), None);
+}
+
+#[test]
+fn test_open_storage_at_unwritable_path() {
+    let tmp_dir = TempDir::new().unwrap();
+    // a regular file in place of the data directory
+    let data_dir = tmp_dir.path().join("data");
+    fs::write(&data_dir, b"not a directory").unwrap();
+
+    let ckb_config = new_ckb_config(&data_dir);
+    let storage_dir = data_dir.join(ckb_config.id.as_str());
+    let rt = Arc::new(TokioRuntime::new().unwrap());
+    let Err(err) = CkbChain::bootstrap(ChainConfig::Ckb(ckb_config), rt) else {
+        panic!("the storage is opened at an unwritable path");
+    };
+    let ErrorDetail::StorageOpen(e) = err.detail() else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(e.path, storage_dir.display().to_string());
+    assert!(err.to_string().contains(&e.path));
+}
+
+#[test]
+fn test_reject_cells_count_over_limit() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.max_cells_count = 8;
+    ckb_config.keyed_client_type_args.insert(
+        "large".to_owned(),
+        ClientTypeArgs {
+            type_id: None,
+            cells_count: 9,
+            client_id: Some(ClientId::from_str("eth-1").unwrap()),
+        },
+    );
+
+    let rt = Arc::new(TokioRuntime::new().unwrap());
+    let Err(err) = CkbChain::bootstrap(ChainConfig::Ckb(ckb_config.clone()), rt.clone()) else {
+        panic!("an over-limit cells_count is accepted");
+    };
+    let ErrorDetail::Config(e) = err.detail() else {
+        panic!("unexpected error: {err}");
+    };
+    let ConfigErrorDetail::CellsCountExceeded(e) = &e.source else {
+        panic!("unexpected config error: {err}");
+    };
+    assert_eq!((e.cells_count, e.max_cells_count), (9, 8));
+    assert_eq!(e.client, "`large`");
+
+    // the limit is inclusive
+    ckb_config.max_cells_count = 9;
+    CkbChain::bootstrap(ChainConfig::Ckb(ckb_config), rt).expect("bootstrap");
+}
+
+#[test]
+fn test_reject_invalid_keyed_clients() {
+    let tmp_dir = TempDir::new().unwrap();
+    let keyed_config = |key: &str, client_id: Option<&str>| {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.keyed_client_type_args.insert(
+            "other".to_owned(),
+            ClientTypeArgs {
+                type_id: None,
+                cells_count: 3,
+                client_id: Some(ClientId::from_str("eth-1").unwrap()),
+            },
+        );
+        ckb_config.keyed_client_type_args.insert(
+            key.to_owned(),
+            ClientTypeArgs {
+                type_id: None,
+                cells_count: 3,
+                client_id: client_id.map(|id| ClientId::from_str(id).unwrap()),
+            },
+        );
+        ckb_config
+    };
+    keyed_config("goerli_2-a", Some("eth-2"))
+        .validate()
+        .expect("valid keyed clients");
+
+    let default_id = ClientId::default();
+    let cases = [
+        ("", Some("eth-2")),
+        ("..", Some("eth-2")),
+        ("a/b", Some("eth-2")),
+        ("goerli.1", Some("eth-2")),
+        ("goerli", None),
+        ("goerli", Some("eth-1")),
+        ("goerli", Some(default_id.as_str())),
+    ];
+    for (key, client_id) in cases {
+        let err = keyed_config(key, client_id)
+            .validate()
+            .expect_err("invalid keyed client");
+        let ConfigErrorDetail::InvalidKeyedClient(e) = err.detail() else {
+            panic!("unexpected config error: {err}");
+        };
+        // the taken client id is reported on the later key
+        assert!(e.key == key || e.key == "other", "{err}");
+    }
+}
+
+#[test]
+fn test_migrate_flat_storage() {
+    let tmp_dir = TempDir::new().unwrap();
+    {
+        let storage = Storage::<MainnetEthSpec>::new(tmp_dir.path()).unwrap();
+        storage.put_base_beacon_header_slot(100).unwrap();
+        storage.put_tip_beacon_header_slot(200).unwrap();
+    }
+    // the files which don't belong to the database are left in the data dir
+    fs::write(tmp_dir.path().join("relayer.toml"), "").unwrap();
+    fs::create_dir(tmp_dir.path().join("backups")).unwrap();
+
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let storage_dir = tmp_dir.path().join(chain.config.id.as_str());
+    assert!(!tmp_dir.path().join("CURRENT").exists());
+    assert!(storage_dir.join("CURRENT").is_file());
+    assert_eq!(chain.native_slot_range().unwrap(), Some((100, 200)));
+    assert!(tmp_dir.path().join("relayer.toml").is_file());
+    assert!(tmp_dir.path().join("backups").is_dir());
+    assert!(!storage_dir.join("relayer.toml").exists());
+}
+
+#[test]
+fn test_fetch_update_cells_with_missing_client_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let contract_type_args = chain.config.lightclient_contract_typeargs.clone();
+    let client_type_args = utils::pack_client_type_args(&h256!("0x1"), 4);
+    let type_script = multi_client_type_script(&contract_type_args, &client_type_args);
+    let key: SearchKey = CellQueryOptions::new(type_script.clone(), PrimaryScriptType::Type).into();
+    let output = packed::CellOutput::new_builder()
+        .type_(Some(type_script).pack())
+        .build();
+    // the 3 clients should have ids 0, 1 and 2, but 1 is missing
+    for id in [0u8, 2, 3] {
+        let client = PackedClient::new_builder().id(id.into()).build();
+        let cell = random_cell(1003, output.clone(), client.as_slice().to_vec());
+        rpc_client.add_cell(&key, cell);
+    }
+    let info = PackedClientInfo::new_builder().last_id(0.into()).build();
+    rpc_client.add_cell(&key, random_cell(1003, output, info.as_slice().to_vec()));
+
+    let err = chain
+        .rt
+        .block_on(rpc_client.fetch_update_cells(&contract_type_args, &client_type_args))
+        .expect_err("missing client id");
+    assert!(matches!(err.detail(), ErrorDetail::ClientIdSetCorrupted(_)));
+}
+
+#[test]
+fn test_audit_last_id() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+
+    let type_id = h256!("0x1");
+    let mut chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.client_type_args.type_id = Some(type_id.clone());
+        bootstrap_chain(ckb_config)
+    };
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let cells_count = chain.config.client_type_args.cells_count;
+    let client_type_args = utils::pack_client_type_args(&type_id, cells_count);
+    let type_script = multi_client_type_script(
+        &chain.config.lightclient_contract_typeargs,
+        &client_type_args,
+    );
+    let key: SearchKey = CellQueryOptions::new(type_script.clone(), PrimaryScriptType::Type).into();
+    let output = packed::CellOutput::new_builder()
+        .type_(Some(type_script).pack())
+        .build();
+    // the client of the highest id is the newest one
+    let add_cells = |last_id: u8| {
+        rpc_client.remove_cells(&key);
+        for id in 0..cells_count - 1 {
+            let maximal_slot = 100 + id as u64;
+            let client = PackedClient::new_builder()
+                .id(id.into())
+                .maximal_slot(
+                    eth_light_client_in_ckb_verification::types::prelude::Pack::pack(&maximal_slot),
+                )
+                .build();
+            let cell = random_cell(1003, output.clone(), client.as_slice().to_vec());
+            rpc_client.add_cell(&key, cell);
+        }
+        let info = PackedClientInfo::new_builder()
+            .last_id(last_id.into())
+            .build();
+        rpc_client.add_cell(
+            &key,
+            random_cell(1003, output.clone(), info.as_slice().to_vec()),
+        );
+    };
+    let newest_id = cells_count - 2;
+
+    add_cells(newest_id);
+    chain.audit_last_id().expect("consistent last id");
+
+    // the last id points at a client which isn't the newest one
+    add_cells(0);
+    let err = chain.audit_last_id().expect_err("inconsistent last id");
+    let ErrorDetail::LastIdInconsistent(e) = err.detail() else {
+        panic!("unexpected error {err}");
+    };
+    assert_eq!((e.last_id, e.newest_id), (0, newest_id));
+    assert_eq!(e.newest_slot, 100 + newest_id as u64);
+
+    // the update refuses to extend the corrupted history
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let err = chain
+        .update_eth_multi_client(updates, None)
+        .expect_err("update on inconsistent last id");
+    assert!(matches!(err.detail(), ErrorDetail::LastIdInconsistent(_)));
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+}
+
+#[test]
+fn test_update_tx_fee() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let type_id = h256!("0x1");
+    chain.config.client_type_args.type_id = Some(type_id.clone());
+    let client_type_args = utils::pack_client_type_args(&type_id, 3);
+    let type_script = multi_client_type_script(
+        &chain.config.lightclient_contract_typeargs,
+        &client_type_args,
+    );
+    let output_with_capacity = |capacity: u64, type_script: Option<packed::Script>| {
+        packed::CellOutput::new_builder()
+            .type_(type_script.pack())
+            .capacity(Capacity::bytes(capacity as usize).unwrap().pack())
+            .build()
+    };
+
+    // the previous transaction of the input
+    let funding_tx = TransactionBuilder::default()
+        .output(output_with_capacity(10_000, None))
+        .output_data(Default::default())
+        .build();
+    let funding_tx_hash = rpc_client.add_committed_transaction(funding_tx.data().into());
+
+    // 2 clients which cover slots [100, 200] and [100, 300], and the change pays 1000 shannons
+    let fee = Capacity::shannons(1_000);
+    let mut update_tx = TransactionBuilder::default().input(packed::CellInput::new(
+        packed::OutPoint::new(funding_tx_hash.pack(), 0),
+        0,
+    ));
+    for (id, maximal_slot) in [(0u8, 200u64), (1, 300)] {
+        let client = PackedClient::new_builder()
+            .id(id.into())
+            .minimal_slot(eth_light_client_in_ckb_verification::types::prelude::Pack::pack(&100u64))
+            .maximal_slot(
+                eth_light_client_in_ckb_verification::types::prelude::Pack::pack(&maximal_slot),
+            )
+            .build();
+        update_tx = update_tx
+            .output(output_with_capacity(3_000, Some(type_script.clone())))
+            .output_data(client.as_slice().pack());
+    }
+    let info = PackedClientInfo::new_builder().last_id(1.into()).build();
+    let change = Capacity::bytes(3_000).unwrap().safe_sub(fee).unwrap();
+    let update_tx = update_tx
+        .output(output_with_capacity(1_000, Some(type_script)))
+        .output_data(info.as_slice().pack())
+        .output(
+            packed::CellOutput::new_builder()
+                .capacity(change.pack())
+                .build(),
+        )
+        .output_data(Default::default())
+        .build();
+    rpc_client.add_committed_transaction(update_tx.data().into());
+    add_multi_client_cells(&rpc_client, &update_tx.data());
+
+    assert_eq!(chain.update_tx_fee(150).unwrap(), fee);
+    assert_eq!(chain.update_tx_fee(250).unwrap(), fee);
+    chain.update_tx_fee(301).expect_err("no client covers it");
+}
+
+#[test]
+fn test_client_slot_for_tx() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let tx_hash_of = |index: usize| -> H256 {
+        let tx: packed::Transaction = rpc_client.get_transaction_by_index(index).unwrap().into();
+        tx.calc_tx_hash().unpack()
+    };
+
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    let created_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+    let updates = load_updates_from_file(&testdata_dir, "headers_part_2.json");
+    let updated_slot = updates.last().unwrap().finalized_header.slot;
+    chain
+        .update_eth_multi_client(updates, None)
+        .expect("update client");
+
+    assert_eq!(
+        chain.client_slot_for_tx(&tx_hash_of(0)).unwrap(),
+        Some(created_slot)
+    );
+    assert_eq!(
+        chain.client_slot_for_tx(&tx_hash_of(1)).unwrap(),
+        Some(updated_slot)
+    );
+    // the mock commits any unknown transaction with an empty body
+    assert_eq!(chain.client_slot_for_tx(&h256!("0x1")).unwrap(), None);
+}
+
+#[test]
+fn test_last_fee_rate() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    assert_eq!(chain.last_fee_rate(), None);
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    let input = packed::CellOutput::new_builder()
+        .lock(lock_script.clone())
+        .capacity(Capacity::bytes(1000).unwrap().pack())
+        .build();
+    rpc_client.add_cell(&key, random_cell(1002, input, Default::default()));
+    let tx = TransactionBuilder::default()
+        .output(
+            packed::CellOutput::new_builder()
+                .capacity(Capacity::bytes(100).unwrap().pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let fee_rate = chain.config.fee_rate;
+    let (tx, inputs) = chain
+        .rt
+        .block_on(rpc_client.complete_tx_with_secp256k1_change(tx, &address, 0, fee_rate, 0))
+        .unwrap();
+    chain
+        .sign_and_send_transaction(tx, inputs.clone())
+        .expect("send tx");
+
+    // the fee is paid by the configured rate of the signed transaction
+    let sent: packed::Transaction = rpc_client.get_transaction_by_index(0).unwrap().into();
+    let summary = utils::TxSummary::new(&sent.into_view(), &inputs);
+    assert_eq!(chain.last_fee_rate(), Some(summary.fee_rate()));
+    assert!(summary.fee_rate() >= fee_rate);
+}
+
+#[test]
+fn test_send_tx_paid_at_min_fee_rate() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.min_fee_rate = ckb_config.fee_rate;
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    for _ in 0..2 {
+        let input = packed::CellOutput::new_builder()
+            .lock(lock_script.clone())
+            .capacity(Capacity::bytes(100).unwrap().pack())
+            .build();
+        rpc_client.add_cell(&key, random_cell(1002, input, Default::default()));
+    }
+    // a single cell covers the output and the fee, but not the change cell
+    let tx = TransactionBuilder::default()
+        .output(
+            packed::CellOutput::new_builder()
+                .capacity(Capacity::bytes(99).unwrap().pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let min_fee_rate = chain.config.min_fee_rate;
+    let (tx, inputs) = chain
+        .rt
+        .block_on(rpc_client.complete_tx_with_secp256k1_change(tx, &address, 0, min_fee_rate, 0))
+        .unwrap();
+    chain
+        .sign_and_send_transaction(tx, inputs.clone())
+        .expect("send tx paid at the floor");
+
+    let sent: packed::Transaction = rpc_client.get_transaction_by_index(0).unwrap().into();
+    let summary = utils::TxSummary::new(&sent.into_view(), &inputs);
+    assert_eq!(summary.inputs_count, 2);
+    assert!(summary.fee() >= summary.size * min_fee_rate);
+}
+
+#[test]
+fn test_verify_updates_locally() {
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let mut updates = load_updates_from_file(&testdata_dir, "headers_part_1.json");
+    // a signed update whose signature isn't a point of BLS12-381
+    let bad_slot = updates[1].finalized_header.slot;
+    let aggregate = &mut updates[1].sync_aggregate;
+    aggregate.sync_committee_bits.set(0, true).unwrap();
+    aggregate.sync_committee_signature = FixedVector::from(vec![0xff; 96]);
+
+    let tmp_dir = TempDir::new().unwrap();
+    let mut ckb_config = new_ckb_config(tmp_dir.path());
+    ckb_config.verify_updates_locally = true;
+    let mut chain = bootstrap_chain(ckb_config);
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let err = chain
+        .create_eth_multi_client(updates.clone(), None)
+        .unwrap_err();
+    assert!(
+        matches!(
+            err.detail(),
+            ErrorDetail::UpdateRejectedLocally(e) if e.slot == bad_slot
+        ),
+        "{err}"
+    );
+    assert_eq!(rpc_client.get_transactions_len(), 0);
+
+    // the same updates are sent without the local verification
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    chain
+        .create_eth_multi_client(updates, None)
+        .expect("create client");
+    assert_eq!(rpc_client.get_transactions_len(), 1);
+}
+
+#[test]
+fn test_correlation_id_in_events() {
+    let tmp_dir = TempDir::new().unwrap();
+    let testdata_dir = format!("{}/case-1", TESTDATA_DIR);
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let new_tracked_msgs = |file: &str, tracking_id: &'static str| TrackedMsgs {
+        msgs: load_updates_from_file(&testdata_dir, file)
+            .into_iter()
+            .map(|update| {
+                EthClientState {
+                    chain_id: ChainId::new("eth".to_owned(), 0),
+                    lightclient_update: update,
+                }
+                .into()
+            })
+            .collect(),
+        tracking_id: TrackingId::Static(tracking_id),
+    };
+
+    // the event of the created client has no attributes, so the id follows it in another event
+    let create_msgs = new_tracked_msgs(
+        "headers_part_1.json",
+        NonCosmosTrackingId::ETH_CREATE_CLIENT,
+    );
+    let correlation_id = create_msgs.tracking_id.to_string();
+    let events = chain
+        .send_messages_and_wait_commit(create_msgs)
+        .expect("create client");
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0].event, IbcEvent::CreateClient(_)));
+    assert_eq!(events[1].height, events[0].height);
+    assert_eq!(
+        utils::correlation_id_of(&events[1]),
+        Some(correlation_id.as_str())
+    );
+    assert_eq!(
+        chain.storage.get_verified_correlation_id().unwrap(),
+        Some(correlation_id)
+    );
+    add_multi_client_cells_from_tx(&rpc_client, 0);
+
+    let update_msgs = new_tracked_msgs(
+        "headers_part_2.json",
+        NonCosmosTrackingId::ETH_UPDATE_CLIENT,
+    );
+    let correlation_id = update_msgs.tracking_id.to_string();
+    let events = chain
+        .send_messages_and_wait_commit(update_msgs)
+        .expect("update client");
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        utils::correlation_id_of(&events[0]),
+        Some(correlation_id.as_str())
+    );
+    // the id isn't kept for the updates out of the tracked messages
+    assert_eq!(chain.correlation_id, None);
+    // but it's kept with the verified slot in the native storage, until it's rolled back
+    let (base_slot, tip_slot) = chain.native_slot_range().unwrap().expect("slot range");
+    assert_eq!(
+        chain.storage.get_verified_beacon_header_slot().unwrap(),
+        Some(tip_slot)
+    );
+    assert_eq!(
+        chain.storage.get_verified_correlation_id().unwrap(),
+        Some(correlation_id.clone())
+    );
+
+    // the committed event is queried by the hash of the update transaction
+    let sent: packed::Transaction = rpc_client.get_transaction_by_index(1).unwrap().into();
+    let tx_hash: H256 = sent.calc_tx_hash().unpack();
+    let queried = chain
+        .query_txs(QueryTxRequest::Transaction(QueryTxHash(TxHash::Sha256(
+            tx_hash.0,
+        ))))
+        .unwrap();
+    assert_eq!(queried.len(), 1);
+    assert_eq!(
+        utils::correlation_id_of(&queried[0]),
+        Some(correlation_id.as_str())
+    );
+    assert_eq!(
+        utils::advanced_slots_of(&queried[0]),
+        utils::advanced_slots_of(&events[0])
+    );
+    let unknown = chain
+        .query_txs(QueryTxRequest::Transaction(QueryTxHash(TxHash::None)))
+        .unwrap();
+    assert!(unknown.is_empty());
+
+    chain.storage.rollback_to(Some(base_slot)).unwrap();
+    assert_eq!(chain.storage.get_verified_correlation_id().unwrap(), None);
+}
+
+#[test]
+fn test_complete_tx_with_tiny_change() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    let tx = TransactionBuilder::default()
+        .output(
+            packed::CellOutput::new_builder()
+                .capacity(Capacity::bytes(100).unwrap().pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+
+    let complete = |input_capacity: Capacity, min_change_capacity: Capacity| {
+        rpc_client.clear_cells();
+        let output = packed::CellOutput::new_builder()
+            .lock(lock_script.clone())
+            .capacity(input_capacity.pack())
+            .build();
+        rpc_client.add_cell(&key, random_cell(1002, output, Default::default()));
+        let (tx, inputs) = chain
+            .rt
+            .block_on(rpc_client.complete_tx_with_secp256k1_change(
+                tx.clone(),
+                &address,
+                0,
+                0,
+                min_change_capacity.as_u64(),
+            ))
+            .unwrap();
+        assert_eq!(inputs.len(), 1);
+        tx
+    };
+
+    // 1 shannon left, which is merged into the fee
+    let input_capacity = Capacity::bytes(100)
+        .unwrap()
+        .safe_add(Capacity::shannons(1))
+        .unwrap();
+    let tx = complete(input_capacity, Capacity::zero());
+    assert_eq!(tx.outputs().len(), 1);
+
+    // enough for a change cell
+    let tx = complete(Capacity::bytes(1000).unwrap(), Capacity::zero());
+    assert_eq!(tx.outputs().len(), 2);
+    let change: u64 = tx.outputs().get(1).unwrap().capacity().unpack();
+    assert_eq!(change, Capacity::bytes(900).unwrap().as_u64());
+
+    // the change is less than the configured minimum
+    let tx = complete(
+        Capacity::bytes(1000).unwrap(),
+        Capacity::bytes(1000).unwrap(),
+    );
+    assert_eq!(tx.outputs().len(), 1);
+}
+
+#[test]
+fn test_search_unsorted_cells_by_capacity() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    let cells = [100, 300, 200]
+        .into_iter()
+        .map(|capacity| {
+            let output = packed::CellOutput::new_builder()
+                .lock(lock_script.clone())
+                .capacity(Capacity::bytes(capacity).unwrap().pack())
+                .build();
+            random_cell(1002, output, Default::default())
+        })
+        .collect::<Vec<_>>();
+
+    let mut selections = vec![];
+    for order in [[0, 1, 2], [2, 0, 1], [1, 2, 0]] {
+        rpc_client.clear_cells();
+        for i in order {
+            rpc_client.add_cell(&key, cells[i].clone());
+        }
+        let mut excessive_capacity = 0;
+        let selected = chain
+            .rt
+            .block_on(rpc_client.search_cells_by_address_and_capacity(
+                &address,
+                Capacity::bytes(250).unwrap().as_u64(),
+                &mut excessive_capacity,
+            ))
+            .unwrap();
+        assert_eq!(excessive_capacity, Capacity::bytes(50).unwrap().as_u64());
+        let out_points = selected
+            .into_iter()
+            .map(|cell| cell.out_point)
+            .collect::<Vec<_>>();
+        selections.push(out_points);
+    }
+    // only the largest cell is selected, whatever the order of the indexer is
+    let largest: packed::OutPoint = cells[1].out_point.clone().into();
+    assert!(selections
+        .iter()
+        .all(|out_points| out_points == &[largest.clone()]));
+}
+
+#[test]
+fn test_search_cells_skips_dead_on_node() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    prepare_key_and_cells(&mut chain);
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let address = chain.tx_assembler_address().unwrap();
+    let lock_script: packed::Script = address.payload().into();
+    let key: SearchKey = CellQueryOptions::new(lock_script.clone(), PrimaryScriptType::Lock).into();
+    let cells = [300, 200]
+        .into_iter()
+        .map(|capacity| {
+            let output = packed::CellOutput::new_builder()
+                .lock(lock_script.clone())
+                .capacity(Capacity::bytes(capacity).unwrap().pack())
+                .build();
+            random_cell(1002, output, Default::default())
+        })
+        .collect::<Vec<_>>();
+    rpc_client.clear_cells();
+    for cell in &cells {
+        rpc_client.add_cell(&key, cell.clone());
+    }
+    // the largest cell is still live in the indexer, but it's spent on the node
+    rpc_client.mark_dead_on_node(cells[0].out_point.clone());
+
+    let mut excessive_capacity = 0;
+    let selected = chain
+        .rt
+        .block_on(rpc_client.search_cells_by_address_and_capacity(
+            &address,
+            Capacity::bytes(150).unwrap().as_u64(),
+            &mut excessive_capacity,
+        ))
+        .unwrap();
+    let out_points = selected
+        .into_iter()
+        .map(|cell| cell.out_point)
+        .collect::<Vec<_>>();
+    let live: packed::OutPoint = cells[1].out_point.clone().into();
+    assert_eq!(out_points, vec![live]);
+    assert_eq!(excessive_capacity, Capacity::bytes(50).unwrap().as_u64());
+}
+
+#[test]
+fn test_fee_rate_floor_over_suggestion() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let rpc_client = Arc::clone(&chain.rpc_client);
+    let fee_options = FeeOptions {
+        fee_rate: 1,
+        min_fee_rate: 5,
+        min_change_capacity: 0,
+        lock_cell_dep: None,
+    };
+
+    // 2 shannons per byte suggested, which is below the floor
+    rpc_client.set_fee_rate_statistics(Some(2_000));
+    let fee_rate = chain.rt.block_on(rpc_client.select_fee_rate(&fee_options));
+    assert_eq!(fee_rate, 5);
+
+    // 8 shannons per byte suggested, which is above the floor
+    rpc_client.set_fee_rate_statistics(Some(7_001));
+    let fee_rate = chain.rt.block_on(rpc_client.select_fee_rate(&fee_options));
+    assert_eq!(fee_rate, 8);
+
+    // no suggestion
+    rpc_client.set_fee_rate_statistics(None);
+    let fee_rate = chain.rt.block_on(rpc_client.select_fee_rate(&fee_options));
+    assert_eq!(fee_rate, 5);
+}
+
+#[test]
+fn test_key_from_env() {
+    let tmp_dir = TempDir::new().unwrap();
+    let env_var = "CKB_CHAIN_TEST_KEY_FROM_ENV";
+    let expected = new_test_key(false);
+    std::env::set_var(env_var, hex::encode(expected.raw_private_key()));
+
+    let mut config = new_ckb_config(tmp_dir.path());
+    config.key_from_env = Some(env_var.to_owned());
+    let chain = bootstrap_chain(config);
+    std::env::remove_var(env_var);
+
+    let key: Secp256k1KeyPair = chain.keybase.get_key(&chain.config.key_name).unwrap();
+    assert_eq!(key.public_key, expected.public_key);
+    let address = chain.tx_assembler_address().unwrap();
+    let expected_payload = AddressPayload::from_pubkey(&expected.public_key);
+    assert_eq!(address.payload(), &expected_payload);
+}
+
+#[test]
+fn test_key_from_pkcs8_file() {
+    let testdata_dir = Path::new("src/testdata/ckb_key_file");
+    // the private key in the files, which is printed by `openssl pkey -text`
+    let private_key = SecretKey::from_slice(
+        &hex::decode("2c9fb62513723c0ddee6c445e622367f31252aa303289b6f749a86e6d57ee9d7").unwrap(),
+    )
+    .unwrap();
+    let expected = Secp256k1KeyPair::from_ckb_private_key(private_key, NetworkType::Testnet);
+
+    for file in ["secp256k1.pem", "secp256k1.der"] {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut config = new_ckb_config(tmp_dir.path());
+        config.key_file = Some(testdata_dir.join(file));
+        let chain = bootstrap_chain(config);
+
+        let key: Secp256k1KeyPair = chain.keybase.get_key(&chain.config.key_name).unwrap();
+        assert_eq!(key.public_key, expected.public_key, "{file}");
+        let address = chain.tx_assembler_address().unwrap();
+        let expected_payload = AddressPayload::from_pubkey(&expected.public_key);
+        assert_eq!(address.payload(), &expected_payload, "{file}");
+    }
+
+    // the other curves and formats are rejected
+    for file in ["prime256v1.pem", "secp256k1_sec1.pem"] {
+        let err = utils::key_from_pkcs8_file(&testdata_dir.join(file), NetworkType::Testnet)
+            .expect_err(file);
+        assert!(matches!(err.detail(), ErrorDetail::CkbKeyFile(_)), "{file}");
+    }
+}
+
+#[test]
+fn test_export_and_import_encrypted_key() {
+    let env_var = "CKB_CHAIN_TEST_KEY_PASSPHRASE";
+    std::env::set_var(env_var, "correct horse battery staple");
+
+    let tmp_dir = TempDir::new().unwrap();
+    let mut config = new_ckb_config(tmp_dir.path());
+    config.key_passphrase_env = Some(env_var.to_owned());
+    let mut chain = bootstrap_chain(config.clone());
+    prepare_key_and_cells(&mut chain);
+    let key_name = chain.config.key_name.clone();
+    let key: Secp256k1KeyPair = chain.keybase.get_key(&key_name).unwrap();
+
+    let encrypted_key = chain.export_key(&key_name).expect("export key");
+    assert_eq!(encrypted_key.key_name, key_name);
+    let exported = serde_json::to_string(&encrypted_key).unwrap();
+    assert!(!exported.contains(&hex::encode(key.raw_private_key())));
+
+    // restore the key on another relayer
+    let restored_dir = TempDir::new().unwrap();
+    config.data_dir = restored_dir.path().to_path_buf();
+    let mut restored = bootstrap_chain(config);
+    let encrypted_key = serde_json::from_str(&exported).unwrap();
+    restored
+        .import_encrypted_key(&encrypted_key)
+        .expect("import key");
+    assert_eq!(
+        restored.tx_assembler_address().unwrap(),
+        chain.tx_assembler_address().unwrap()
+    );
+
+    // a wrong passphrase fails to decrypt the key
+    std::env::set_var(env_var, "wrong passphrase");
+    let err = restored
+        .import_encrypted_key(&encrypted_key)
+        .expect_err("wrong passphrase");
+    assert!(matches!(err.detail(), ErrorDetail::CkbKeyBackup(_)));
+    std::env::remove_var(env_var);
+    let err = chain.export_key(&key_name).expect_err("no passphrase");
+    assert!(matches!(err.detail(), ErrorDetail::CkbKeyBackup(_)));
+}
+
+#[test]
+fn test_signing_key_errors() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let key_name = chain.config.key_name.clone();
+    let tx = TransactionBuilder::default().build();
+
+    // no key is added under the name
+    let err = chain
+        .sign_transaction(tx.clone(), vec![])
+        .expect_err("missing key");
+    assert!(
+        matches!(err.detail(), ErrorDetail::KeyNotFound(e) if e.key_name == key_name),
+        "{err}"
+    );
+    // the other users of the keybase report the same error
+    let err = chain
+        .query_balance(Some(&key_name), None)
+        .expect_err("missing key");
+    assert!(
+        matches!(err.detail(), ErrorDetail::KeyNotFound(e) if e.key_name == key_name),
+        "{err}"
+    );
+    let env_var = "CKB_CHAIN_TEST_KEY_ERRORS_PASSPHRASE";
+    std::env::set_var(env_var, "correct horse battery staple");
+    chain.config.key_passphrase_env = Some(env_var.to_owned());
+    let err = chain.export_key(&key_name).expect_err("missing key");
+    assert!(
+        matches!(err.detail(), ErrorDetail::KeyNotFound(e) if e.key_name == key_name),
+        "{err}"
+    );
+
+    // the key file of the name is corrupt
+    let keys_dir = tmp_dir.path().join("keys");
+    fs::create_dir_all(&keys_dir).unwrap();
+    fs::write(keys_dir.join(format!("{key_name}.json")), "not a key").unwrap();
+    chain.keybase = KeyRing::Test(KeyStoreTest::new("ckb".to_owned(), keys_dir));
+    let err = chain.sign_transaction(tx, vec![]).expect_err("corrupt key");
+    assert!(
+        matches!(err.detail(), ErrorDetail::KeyDecode(e) if e.key_name == key_name),
+        "{err}"
+    );
+    let err = chain
+        .query_balance(Some(&key_name), None)
+        .expect_err("corrupt key");
+    assert!(
+        matches!(err.detail(), ErrorDetail::KeyDecode(e) if e.key_name == key_name),
+        "{err}"
+    );
+}
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_print_status_log_with_structured_clients() {
+    let tmp_dir = TempDir::new().unwrap();
+    let type_id = h256!("0x1");
+    let chain = {
+        let mut ckb_config = new_ckb_config(tmp_dir.path());
+        ckb_config.client_type_args.type_id = Some(type_id.clone());
+        bootstrap_chain(ckb_config)
+    };
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    let cells_count = chain.config.client_type_args.cells_count;
+    let client_type_args = utils::pack_client_type_args(&type_id, cells_count);
+    let type_script = multi_client_type_script(
+        &chain.config.lightclient_contract_typeargs,
+        &client_type_args,
+    );
+    let key: SearchKey = CellQueryOptions::new(type_script.clone(), PrimaryScriptType::Type).into();
+    let output = packed::CellOutput::new_builder()
+        .type_(Some(type_script).pack())
+        .build();
+    for id in 0..cells_count - 1 {
+        let client = PackedClient::new_builder().id(id.into()).build();
+        let cell = random_cell(1003, output.clone(), client.as_slice().to_vec());
+        rpc_client.add_cell(&key, cell);
+    }
+    let info = PackedClientInfo::new_builder().last_id(0.into()).build();
+    rpc_client.add_cell(&key, random_cell(1003, output, info.as_slice().to_vec()));
+
+    let logs = CapturedLogs::default();
+    let subscriber = {
+        let logs = logs.clone();
+        tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || logs.clone())
+            .finish()
+    };
+    tracing::subscriber::with_default(subscriber, || chain.print_status_log(None)).unwrap();
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let clients = logs
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|event| event["fields"]["message"] == "[STATUS] on-chain client")
+        .collect::<Vec<_>>();
+    assert_eq!(clients.len(), cells_count as usize - 1);
+    for (id, client) in clients.iter().enumerate() {
+        assert_eq!(client["fields"]["id"], id);
+        assert_eq!(client["fields"]["minimal_slot"], 0);
+        assert_eq!(client["fields"]["maximal_slot"], 0);
+    }
+}
+
+#[test]
+fn test_warn_clock_skew() {
+    let tmp_dir = TempDir::new().unwrap();
+    let chain = bootstrap_chain(new_ckb_config(tmp_dir.path()));
+    let rpc_client = Arc::clone(&chain.rpc_client);
+
+    // returns the warnings about the clock skew
+    let check_clock_skew = || {
+        let logs = CapturedLogs::default();
+        let subscriber = {
+            let logs = logs.clone();
+            tracing_subscriber::fmt()
+                .json()
+                .with_writer(move || logs.clone())
+                .finish()
+        };
+        tracing::subscriber::with_default(subscriber, || chain.check_clock_skew());
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        logs.lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|event| {
+                event["level"] == "WARN"
+                    && event["fields"]["message"]
+                        .as_str()
+                        .map_or(false, |message| message.contains("clock"))
+            })
+            .count()
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    // an hour behind
+    rpc_client.set_tip_timestamp(now - 3_600_000);
+    assert_eq!(check_clock_skew(), 1);
+    // an hour ahead
+    rpc_client.set_tip_timestamp(now + 3_600_000);
+    assert_eq!(check_clock_skew(), 1);
+    // a block ago
+    rpc_client.set_tip_timestamp(now - 8_000);
+    assert_eq!(check_clock_skew(), 0);
+}
+
 // TODO: add update_eth_multi_client test
 
 // fn test_update_eth_client(case_id: usize) {
@@ -0,0 +1,253 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ckb_jsonrpc_types::HeaderView;
+use ckb_types::H256;
+use eth_light_client_in_ckb_verification::types::{
+    packed::{Client as PackedClient, ClientTypeArgs as PackedClientTypeArgs},
+    prelude::*,
+};
+use futures::{SinkExt as _, StreamExt as _};
+use ibc_relayer_storage::Slot;
+use ibc_relayer_types::core::ics24_host::identifier::ChainId;
+use tendermint_rpc::Url;
+use tokio::runtime::Runtime as TokioRuntime;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tracing::{debug, info, warn};
+
+use crate::chain::tracking::{NonCosmosTrackingId as NonCosmos, TrackingId};
+use crate::error::Error;
+use crate::event::monitor::EventBatch;
+
+use super::assembler::TxAssembler as _;
+use super::communication::CkbReader as _;
+use super::rpc_client::RpcClient;
+use super::subscription::EventStream;
+use super::utils;
+
+// The topic of the new-tip notifications of a CKB node.
+const NEW_TIP_HEADER_TOPIC: &str = "new_tip_header";
+
+// Delay before reconnecting to a dropped subscription, the tips are polled meanwhile.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Interval of polling the tip when no tip is pushed, about one block of CKB.
+const POLL_INTERVAL: Duration = Duration::from_secs(8);
+
+/// The default multi-client which is shared by the chain with its tip watcher, so a client
+/// changed by the chain itself isn't announced again by the watcher.
+#[derive(Clone, Default)]
+pub struct WatchedClient(Arc<Mutex<WatchedClientInner>>);
+
+#[derive(Default)]
+struct WatchedClientInner {
+    // Set once the client is found on chain.
+    type_args: Option<PackedClientTypeArgs>,
+    // The maximal slot of the client which is announced to the subscribers at last.
+    maximal_slot: Option<Slot>,
+}
+
+impl WatchedClient {
+    /// Records the latest on-chain client, returns the previous maximal slot if it's changed,
+    /// i.e. the change should be announced.
+    pub fn observe(
+        &self,
+        type_args: PackedClientTypeArgs,
+        maximal_slot: Slot,
+    ) -> Option<Option<Slot>> {
+        let mut inner = self.0.lock().expect("poisoned watched client");
+        inner.type_args = Some(type_args);
+        let previous = inner.maximal_slot.replace(maximal_slot);
+        (previous != Some(maximal_slot)).then_some(previous)
+    }
+
+    /// Records the type args of a client which is created already, so it's checked before the
+    /// chain has found it on chain.
+    pub fn watch(&self, type_args: PackedClientTypeArgs) {
+        let mut inner = self.0.lock().expect("poisoned watched client");
+        inner.type_args.get_or_insert(type_args);
+    }
+
+    fn type_args(&self) -> Option<PackedClientTypeArgs> {
+        let inner = self.0.lock().expect("poisoned watched client");
+        inner.type_args.clone()
+    }
+}
+
+/// What the tip watcher needs to check the default multi-client at each new tip.
+pub struct ClientCheck {
+    pub chain_id: ChainId,
+    pub rpc_client: Arc<RpcClient>,
+    pub contract_typeargs: H256,
+    pub client: WatchedClient,
+    pub event_stream: Arc<Mutex<EventStream>>,
+}
+
+/// Checks the default multi-client at each new tip of a CKB node, and broadcasts an update event
+/// to the subscribers once the client is changed on chain. The new tips are pushed by the
+/// WebSocket subscription of the node if it's configured, which is reconnected after it drops,
+/// and polled otherwise. The watcher is stopped when it's dropped.
+pub struct TipWatcher {
+    connected: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl TipWatcher {
+    pub fn spawn(rt: &TokioRuntime, url: Option<Url>, check: ClientCheck) -> Self {
+        // only the latest tip is kept, the checks which fall behind skip the older ones
+        let (tx, tips) = watch::channel(0);
+        let connected = Arc::new(AtomicBool::new(false));
+        let mut handles = Vec::with_capacity(2);
+        if let Some(url) = url {
+            handles.push(rt.spawn(watch_tips(url, tx, Arc::clone(&connected))));
+        }
+        handles.push(rt.spawn(check_client_at_new_tips(tips, check)));
+        Self { connected, handles }
+    }
+
+    /// Returns whether the subscription is alive, the tips are polled if it isn't.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for TipWatcher {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
+
+async fn check_client_at_new_tips(mut tips: watch::Receiver<u64>, check: ClientCheck) {
+    let mut last_checked_tip = None;
+    loop {
+        let pushed_tip = match tokio::time::timeout(POLL_INTERVAL, tips.changed()).await {
+            Ok(Ok(())) => Some(*tips.borrow_and_update()),
+            // no subscription is configured
+            Ok(Err(_)) => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                None
+            }
+            Err(_) => None,
+        };
+        let tip_number = match pushed_tip {
+            Some(number) => number,
+            None => match check.rpc_client.get_tip_header().await {
+                Ok(tip) => tip.inner.number.value(),
+                Err(err) => {
+                    warn!(
+                        "failed to poll the tip of ckb chain {}: {err}",
+                        check.chain_id
+                    );
+                    continue;
+                }
+            },
+        };
+        if matches!(last_checked_tip, Some(last) if tip_number <= last) {
+            continue;
+        }
+        last_checked_tip = Some(tip_number);
+        if let Err(err) = check_client(&check, tip_number).await {
+            warn!("failed to check the client at tip {tip_number}: {err}");
+        }
+    }
+}
+
+async fn check_client(check: &ClientCheck, tip_number: u64) -> Result<(), Error> {
+    // nothing to check before the creation
+    let Some(type_args) = check.client.type_args() else {
+        return Ok(());
+    };
+    let Some(update_cells) = check
+        .rpc_client
+        .fetch_update_cells(&check.contract_typeargs, &type_args)
+        .await?
+    else {
+        return Ok(());
+    };
+    let latest_client = PackedClient::new_unchecked(update_cells.latest.output_data);
+    let maximal_slot: Slot = latest_client.maximal_slot().unpack();
+    let Some(previous) = check.client.observe(type_args, maximal_slot) else {
+        return Ok(());
+    };
+    info!("the client is changed to the maximal slot {maximal_slot} at tip {tip_number}");
+    let advanced_slots = previous.map_or(0, |slot| maximal_slot.saturating_sub(slot));
+    let event = utils::new_update_event(advanced_slots, maximal_slot, false, tip_number)?;
+    let batch = EventBatch {
+        chain_id: check.chain_id.clone(),
+        tracking_id: TrackingId::Static(NonCosmos::ETH_UPDATE_CLIENT),
+        height: event.height,
+        events: vec![event],
+    };
+    // the broadcast blocks on a full subscriber with `SubscribeOverflow::Block`
+    let event_stream = Arc::clone(&check.event_stream);
+    tokio::task::spawn_blocking(move || {
+        event_stream
+            .lock()
+            .map(|mut event_stream| event_stream.broadcast(batch))
+            .map_err(Error::other)
+    })
+    .await
+    .map_err(Error::other)??;
+    Ok(())
+}
+
+async fn watch_tips(url: Url, tips: watch::Sender<u64>, connected: Arc<AtomicBool>) {
+    loop {
+        match subscribe(&url, &tips, &connected).await {
+            Ok(()) => warn!("new-tip subscription to {url} is closed, fallback to polling"),
+            Err(err) => warn!("new-tip subscription to {url} failed: {err}, fallback to polling"),
+        }
+        connected.store(false, Ordering::SeqCst);
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+}
+
+async fn subscribe(
+    url: &Url,
+    tips: &watch::Sender<u64>,
+    connected: &AtomicBool,
+) -> Result<(), WsError> {
+    let (mut stream, _) = tokio_tungstenite::connect_async(url.to_string()).await?;
+    let request = serde_json::json!({
+        "id": 0,
+        "jsonrpc": "2.0",
+        "method": "subscribe",
+        "params": [NEW_TIP_HEADER_TOPIC],
+    });
+    stream.send(Message::Text(request.to_string())).await?;
+    connected.store(true, Ordering::SeqCst);
+    info!("subscribed to the new tips of {url}");
+    while let Some(message) = stream.next().await {
+        let Message::Text(text) = message? else {
+            continue;
+        };
+        match parse_new_tip(&text) {
+            // the tips may be pushed out of order after a reorg, only the highest one is kept
+            Some(number) => {
+                tips.send_if_modified(|tip| {
+                    let higher = number > *tip;
+                    if higher {
+                        *tip = number;
+                    }
+                    higher
+                });
+            }
+            None => debug!("skip the message of the new-tip subscription: {text}"),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the block number of a new-tip notification, whose result is the JSON string of the
+/// tip header, or `None` for the other messages, e.g. the response of the subscription.
+pub fn parse_new_tip(text: &str) -> Option<u64> {
+    let message: serde_json::Value = serde_json::from_str(text).ok()?;
+    let result = message.get("params")?.get("result")?.as_str()?;
+    let header: HeaderView = serde_json::from_str(result).ok()?;
+    Some(header.inner.number.value())
+}
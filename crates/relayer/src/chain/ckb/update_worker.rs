@@ -0,0 +1,60 @@
+use std::thread;
+
+use crossbeam_channel as channel;
+use tracing::{info, warn};
+
+use super::CkbChain;
+use crate::chain::endpoint::ChainEndpoint as _;
+use crate::chain::tracking::{TrackedMsgs, TrackingId};
+use crate::error::Error;
+use crate::event::IbcEventWithHeight;
+
+/// The result of an update batch, which is tagged by the tracking id of the batch.
+pub struct UpdateResult {
+    pub tracking_id: TrackingId,
+    pub result: Result<Vec<IbcEventWithHeight>, Error>,
+}
+
+/// Sends the queued update batches on a dedicated thread one by one, so the caller isn't
+/// blocked while the transactions are being committed. The results are delivered in the order
+/// of the batches, and the worker stops after the queue is closed and drained.
+pub struct UpdateWorker {
+    results: channel::Receiver<UpdateResult>,
+    handle: thread::JoinHandle<CkbChain>,
+}
+
+impl UpdateWorker {
+    pub(super) fn spawn(mut chain: CkbChain, batches: channel::Receiver<TrackedMsgs>) -> Self {
+        let (tx, results) = channel::unbounded();
+        let handle = thread::spawn(move || {
+            let chain_id = chain.id();
+            info!("update worker of ckb chain {chain_id} is started");
+            for batch in batches {
+                let tracking_id = batch.tracking_id;
+                let result = chain.send_messages_and_wait_commit(batch);
+                if let Err(err) = &result {
+                    warn!("update batch {tracking_id} of ckb chain {chain_id} failed: {err}");
+                }
+                // a dropped receiver only means nobody waits for the results
+                let _ = tx.send(UpdateResult {
+                    tracking_id,
+                    result,
+                });
+            }
+            info!("update worker of ckb chain {chain_id} is stopped");
+            chain
+        });
+        Self { results, handle }
+    }
+
+    pub fn results(&self) -> &channel::Receiver<UpdateResult> {
+        &self.results
+    }
+
+    /// Waits until the queue is closed and drained, then returns the chain.
+    pub fn join(self) -> Result<CkbChain, Error> {
+        self.handle
+            .join()
+            .map_err(|_| Error::other_error("update worker of ckb chain panicked".to_owned()))
+    }
+}
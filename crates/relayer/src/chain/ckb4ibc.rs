@@ -25,6 +25,7 @@ use ckb_ics_axon::message::Envelope;
 use ckb_ics_axon::{ChannelArgs, PacketArgs};
 use ckb_jsonrpc_types::{JsonBytes, Status, TransactionView};
 use ckb_sdk::constants::TYPE_ID_CODE_HASH;
+use ckb_sdk::rpc::ckb_indexer::{CellType, Tx, TxWithCell};
 use ckb_sdk::rpc::ckb_light_client::{ScriptType, SearchKey};
 use ckb_sdk::traits::SecpCkbRawKeySigner;
 use ckb_sdk::unlock::{ScriptSigner, SecpSighashScriptSigner};
@@ -34,6 +35,7 @@ use ckb_types::core::TransactionView as CoreTransactionView;
 use ckb_types::molecule::prelude::Entity;
 use ckb_types::packed::{CellInput, OutPoint, Script, WitnessArgs};
 use ckb_types::prelude::{Builder, Pack, Unpack};
+use ckb_types::H256;
 use futures::TryFutureExt;
 use ibc_proto::ibc::apps::fee::v1::{
     QueryIncentivizedPacketRequest, QueryIncentivizedPacketResponse,
@@ -55,6 +57,7 @@ use ibc_relayer_types::core::ics23_commitment::merkle::MerkleProof;
 use ibc_relayer_types::core::ics24_host::identifier::{
     ChainId, ChannelId, ClientId, ConnectionId, PortId,
 };
+use ibc_relayer_types::events::WithBlockDataType;
 use ibc_relayer_types::proofs::Proofs;
 use ibc_relayer_types::signer::Signer;
 use ibc_relayer_types::timestamp::Timestamp;
@@ -67,7 +70,7 @@ use tokio::runtime::Runtime;
 
 use self::extractor::{extract_connections_from_tx, extract_ibc_packet_from_tx};
 use self::message::{convert_msg_to_ckb_tx, CkbTxInfo, Converter, MsgToTxConverter};
-use self::monitor::Ckb4IbcEventMonitor;
+use self::monitor::{convert_packet_to_event, Ckb4IbcEventMonitor};
 use self::utils::{
     convert_port_id_to_array, get_channel_idx, get_dummy_merkle_proof, get_encoded_object,
     get_search_key,
@@ -80,16 +83,16 @@ use super::cosmos::encode::key_pair_to_signer;
 use super::endpoint::{ChainStatus, HealthCheck};
 use super::handle::Subscription;
 use super::requests::{
-    CrossChainQueryRequest, IncludeProof, QueryChannelClientStateRequest, QueryChannelRequest,
-    QueryChannelsRequest, QueryClientConnectionsRequest, QueryClientStateRequest,
-    QueryClientStatesRequest, QueryConnectionChannelsRequest, QueryConnectionRequest,
-    QueryConnectionsRequest, QueryConsensusStateHeightsRequest, QueryConsensusStateRequest,
-    QueryHostConsensusStateRequest, QueryNextSequenceReceiveRequest,
-    QueryPacketAcknowledgementRequest, QueryPacketAcknowledgementsRequest,
-    QueryPacketCommitmentRequest, QueryPacketCommitmentsRequest, QueryPacketEventDataRequest,
-    QueryPacketReceiptRequest, QueryTxRequest, QueryUnreceivedAcksRequest,
-    QueryUnreceivedPacketsRequest, QueryUpgradedClientStateRequest,
-    QueryUpgradedConsensusStateRequest,
+    CrossChainQueryRequest, IncludeProof, Qualified, QueryChannelClientStateRequest,
+    QueryChannelRequest, QueryChannelsRequest, QueryClientConnectionsRequest,
+    QueryClientStateRequest, QueryClientStatesRequest, QueryConnectionChannelsRequest,
+    QueryConnectionRequest, QueryConnectionsRequest, QueryConsensusStateHeightsRequest,
+    QueryConsensusStateRequest, QueryHeight, QueryHostConsensusStateRequest,
+    QueryNextSequenceReceiveRequest, QueryPacketAcknowledgementRequest,
+    QueryPacketAcknowledgementsRequest, QueryPacketCommitmentRequest,
+    QueryPacketCommitmentsRequest, QueryPacketEventDataRequest, QueryPacketReceiptRequest,
+    QueryTxRequest, QueryUnreceivedAcksRequest, QueryUnreceivedPacketsRequest,
+    QueryUpgradedClientStateRequest, QueryUpgradedConsensusStateRequest,
 };
 use super::tracking::TrackedMsgs;
 use tokio::runtime::Runtime as TokioRuntime;
@@ -100,8 +103,14 @@ pub mod message;
 mod monitor;
 pub mod utils;
 
+#[cfg(test)]
+mod tests;
+
 pub use utils::keccak256;
 
+// The number of the transactions of a packet which are searched in each request.
+const PACKET_TXS_PAGE_SIZE: u32 = 100;
+
 pub struct Ckb4IbcChain {
     rt: Arc<TokioRuntime>,
     rpc_client: Arc<RpcClient>,
@@ -209,21 +218,7 @@ impl Ckb4IbcChain {
         port_id: &PortId,
         sequence: Sequence,
     ) -> Result<(IbcPacket, CellInput), Error> {
-        let script = Script::new_builder()
-            .code_hash(self.get_converter().get_packet_code_hash())
-            .hash_type(ScriptHashType::Type.into())
-            .args(
-                PacketArgs {
-                    channel_id: get_channel_idx(channel_id)?,
-                    port_id: port_id.as_str().as_bytes().try_into().unwrap(),
-                    sequence: u64::from(sequence) as u16,
-                    owner: Default::default(),
-                }
-                .get_search_args()
-                .pack(),
-            )
-            .build();
-        let search_key = get_search_key(script);
+        let search_key = self.get_packet_search_key(channel_id, port_id, sequence)?;
         let resp = self
             .rpc_client
             .fetch_live_cells(search_key, 1, None)
@@ -260,6 +255,90 @@ impl Ckb4IbcChain {
         Ok(result)
     }
 
+    fn get_packet_search_key(
+        &self,
+        channel_id: &ChannelId,
+        port_id: &PortId,
+        sequence: Sequence,
+    ) -> Result<SearchKey, Error> {
+        let script = Script::new_builder()
+            .code_hash(self.get_converter().get_packet_code_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .args(
+                PacketArgs {
+                    channel_id: get_channel_idx(channel_id)?,
+                    port_id: port_id.as_str().as_bytes().try_into().unwrap(),
+                    sequence: u64::from(sequence) as u16,
+                    owner: Default::default(),
+                }
+                .get_search_args()
+                .pack(),
+            )
+            .build();
+        Ok(get_search_key(script))
+    }
+
+    /// Searches the transactions which create the cells of the packet `sequence`, in the order
+    /// they are committed, each with the packet in the created cell and the block number.
+    ///
+    /// The cells which are consumed are searched as well, so the packet is found in each status
+    /// it has ever been in, e.g. a sent packet is still found after it's acknowledged.
+    fn fetch_packet_txs_and_extract(
+        &self,
+        channel_id: &ChannelId,
+        port_id: &PortId,
+        sequence: Sequence,
+    ) -> Result<Vec<(IbcPacket, H256, u64)>, Error> {
+        let search_key = self.get_packet_search_key(channel_id, port_id, sequence)?;
+        let search = async {
+            let mut indexed_txs = vec![];
+            let mut cursor = None;
+            loop {
+                let page = self
+                    .rpc_client
+                    .get_transactions(search_key.clone(), PACKET_TXS_PAGE_SIZE, cursor)
+                    .await?;
+                let is_last_page = page.objects.len() < PACKET_TXS_PAGE_SIZE as usize;
+                indexed_txs.extend(page.objects);
+                if is_last_page {
+                    break;
+                }
+                cursor = Some(page.last_cursor);
+            }
+
+            let mut packets = vec![];
+            for indexed_tx in indexed_txs {
+                // the transactions which consume the cells are found as well
+                let Tx::Ungrouped(TxWithCell {
+                    tx_hash,
+                    block_number,
+                    io_type: CellType::Output,
+                    ..
+                }) = indexed_tx
+                else {
+                    continue;
+                };
+                let tx = self
+                    .rpc_client
+                    .get_transaction(&tx_hash)
+                    .await?
+                    .and_then(|resp| resp.transaction)
+                    .ok_or_else(|| Error::query(format!("transaction {tx_hash:#x} of packet")))?;
+                let tx = match tx.inner {
+                    ckb_jsonrpc_types::Either::Left(tx) => tx,
+                    ckb_jsonrpc_types::Either::Right(json_bytes) => {
+                        serde_json::from_slice(json_bytes.as_bytes())
+                            .map_err(|e| Error::query(e.to_string()))?
+                    }
+                };
+                let ibc_packet = extract_ibc_packet_from_tx(tx)?;
+                packets.push((ibc_packet, tx_hash, block_number.value()));
+            }
+            Ok(packets)
+        };
+        self.rt.block_on(search)
+    }
+
     fn fetch_channel_cell_and_extract(
         &self,
         channel_id: ChannelId,
@@ -408,6 +487,7 @@ impl Ckb4IbcChain {
             &address,
             input_capacity,
             fee_rate,
+            0,
         );
         let (result, _) = self.rt.block_on(tx)?;
         let witness = WitnessArgs::new_builder()
@@ -627,6 +707,8 @@ impl ChainEndpoint for Ckb4IbcChain {
                         Duration::from_secs(10),
                         4,
                         Duration::from_secs(600),
+                        None,
+                        None,
                     )
                 })
         });
@@ -1041,9 +1123,49 @@ impl ChainEndpoint for Ckb4IbcChain {
 
     fn query_packet_events(
         &self,
-        _request: QueryPacketEventDataRequest,
+        request: QueryPacketEventDataRequest,
     ) -> Result<Vec<IbcEventWithHeight>, Error> {
-        todo!()
+        // the packets sent from CKB are searched by the source channel, while the received
+        // ones, whose acknowledgements are written on CKB, by the destination channel
+        let (channel_id, port_id) = match request.event_id {
+            WithBlockDataType::SendPacket => (&request.source_channel_id, &request.source_port_id),
+            WithBlockDataType::WriteAck => (
+                &request.destination_channel_id,
+                &request.destination_port_id,
+            ),
+            _ => return Ok(vec![]),
+        };
+        // the events of the blocks beyond the requested height are excluded
+        let target_block_number = match request.height.get() {
+            QueryHeight::Latest => self
+                .rt
+                .block_on(self.rpc_client.get_tip_header())?
+                .inner
+                .number
+                .value(),
+            QueryHeight::Specific(height) => height.revision_height(),
+        };
+        let is_requested_block = |block_number: u64| match request.height {
+            Qualified::SmallerEqual(_) => block_number <= target_block_number,
+            Qualified::Equal(_) => block_number == target_block_number,
+        };
+        let mut events = vec![];
+        for sequence in &request.sequences {
+            let packets = self.fetch_packet_txs_and_extract(channel_id, port_id, *sequence)?;
+            for (packet, tx_hash, block_number) in packets {
+                if !is_requested_block(block_number) {
+                    continue;
+                }
+                let height = Height::new(1, block_number).map_err(Error::ics02)?;
+                events.extend(convert_packet_to_event(
+                    &request.event_id,
+                    packet,
+                    tx_hash,
+                    height,
+                ));
+            }
+        }
+        Ok(events)
     }
 
     fn query_host_consensus_state(
@@ -19,12 +19,12 @@ use ibc_relayer_types::core::ics03_connection::events::{
 use ibc_relayer_types::core::ics04_channel::channel::State;
 use ibc_relayer_types::core::ics04_channel::events::{
     AcknowledgePacket, OpenInit as ChannelOpenInit, OpenTry as ChannelOpenTry, ReceivePacket,
-    SendPacket,
+    SendPacket, WriteAcknowledgement,
 };
 use ibc_relayer_types::core::ics04_channel::packet::{Packet, Sequence};
 use ibc_relayer_types::core::ics04_channel::timeout::TimeoutHeight;
 use ibc_relayer_types::core::ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId};
-use ibc_relayer_types::events::IbcEvent;
+use ibc_relayer_types::events::{IbcEvent, WithBlockDataType};
 use ibc_relayer_types::timestamp::Timestamp;
 use tokio::runtime::Runtime as TokioRuntime;
 
@@ -382,6 +382,42 @@ impl Ckb4IbcEventMonitor {
     }
 }
 
+/// Reconstructs the `event_id` event of a packet cell which is created by `tx_hash` in the block
+/// at `height`, `None` if the status of the packet doesn't match the event.
+///
+/// A sent packet is still in the `Send` status, and a received packet, whose acknowledgement
+/// is written on CKB, is in the `InboxAck` status.
+pub fn convert_packet_to_event(
+    event_id: &WithBlockDataType,
+    ibc_packet: IbcPacket,
+    tx_hash: H256,
+    height: Height,
+) -> Option<IbcEventWithHeight> {
+    let event = match (event_id, &ibc_packet.status) {
+        (WithBlockDataType::SendPacket, PacketStatus::Send) => IbcEvent::SendPacket(SendPacket {
+            packet: convert_packet(ibc_packet),
+        }),
+        (WithBlockDataType::WriteAck, PacketStatus::InboxAck) => {
+            // the acknowledgement on CKB is the hash of the transaction which writes it
+            let ack = ibc_packet
+                .tx_hash
+                .as_ref()
+                .map(|hash| hash.as_bytes().to_vec())
+                .unwrap_or_default();
+            IbcEvent::WriteAcknowledgement(WriteAcknowledgement {
+                packet: convert_packet(ibc_packet),
+                ack,
+            })
+        }
+        _ => return None,
+    };
+    Some(IbcEventWithHeight {
+        event,
+        height,
+        tx_hash: tx_hash.into(),
+    })
+}
+
 fn convert_packet(packet: IbcPacket) -> Packet {
     let sequence = Sequence::from(packet.packet.sequence as u64);
 
@@ -416,3 +452,81 @@ fn convert_packet(packet: IbcPacket) -> Packet {
         timeout_timestamp: Timestamp::none(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ckb_ics_axon::handler::{IbcPacket, PacketStatus};
+    use ckb_ics_axon::object::Packet as CkbPacket;
+    use ckb_types::{h256, H256};
+    use ibc_relayer_types::events::{IbcEvent, WithBlockDataType};
+    use ibc_relayer_types::Height;
+
+    use super::convert_packet_to_event;
+
+    fn new_ibc_packet(sequence: u16, status: PacketStatus) -> IbcPacket {
+        IbcPacket {
+            packet: CkbPacket {
+                sequence,
+                source_port_id: "transfer".to_owned(),
+                source_channel_id: "channel-0".to_owned(),
+                destination_port_id: "transfer".to_owned(),
+                destination_channel_id: "channel-1".to_owned(),
+                data: vec![sequence as u8; 4],
+            },
+            tx_hash: None,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_convert_committed_packets_to_events() {
+        let send_tx_hash = h256!("0x1");
+        let ack_tx_hash = h256!("0x2");
+        let height = Height::new(1, 5).unwrap();
+
+        let sent = new_ibc_packet(1, PacketStatus::Send);
+        let event =
+            convert_packet_to_event(&WithBlockDataType::SendPacket, sent, send_tx_hash, height)
+                .unwrap();
+        assert_eq!(event.tx_hash, <[u8; 32]>::from(send_tx_hash));
+        assert_eq!(event.height, height);
+        let IbcEvent::SendPacket(send_packet) = event.event else {
+            panic!("not a send packet event");
+        };
+        assert_eq!(u64::from(send_packet.packet.sequence), 1);
+        assert_eq!(send_packet.packet.source_channel.as_str(), "channel-0");
+        assert_eq!(send_packet.packet.destination_channel.as_str(), "channel-1");
+        assert_eq!(send_packet.packet.data, vec![1; 4]);
+
+        let mut acked = new_ibc_packet(2, PacketStatus::InboxAck);
+        acked.tx_hash = Some(Default::default());
+        let ack = acked.tx_hash.as_ref().unwrap().as_bytes().to_vec();
+        let event =
+            convert_packet_to_event(&WithBlockDataType::WriteAck, acked, ack_tx_hash, height)
+                .unwrap();
+        assert_eq!(event.tx_hash, <[u8; 32]>::from(ack_tx_hash));
+        let IbcEvent::WriteAcknowledgement(write_ack) = event.event else {
+            panic!("not a write acknowledgement event");
+        };
+        assert_eq!(u64::from(write_ack.packet.sequence), 2);
+        assert_eq!(write_ack.ack, ack);
+
+        // the status doesn't match the requested event
+        let received = new_ibc_packet(3, PacketStatus::Recv);
+        assert!(convert_packet_to_event(
+            &WithBlockDataType::SendPacket,
+            received,
+            H256::default(),
+            height
+        )
+        .is_none());
+        let sent = new_ibc_packet(4, PacketStatus::Send);
+        assert!(convert_packet_to_event(
+            &WithBlockDataType::WriteAck,
+            sent,
+            H256::default(),
+            height
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use ckb_ics_axon::handler::{IbcConnections, IbcPacket, PacketStatus};
+use ckb_ics_axon::message::{Envelope, MsgType};
+use ckb_ics_axon::object::Packet as CkbPacket;
+use ckb_sdk::rpc::ckb_indexer::{CellType, Tx, TxWithCell};
+use ckb_types::packed::{self, CellInput, OutPoint, WitnessArgs};
+use ckb_types::prelude::*;
+use ckb_types::{h256, H256};
+use ibc_relayer_types::core::ics04_channel::packet::Sequence;
+use ibc_relayer_types::core::ics24_host::identifier::{ChainId, ChannelId, PortId};
+use ibc_relayer_types::events::{IbcEvent, WithBlockDataType};
+use ibc_relayer_types::Height;
+use tokio::runtime::Runtime;
+
+use super::utils::get_encoded_object;
+use super::Ckb4IbcChain;
+use crate::chain::ckb::rpc_client::RpcClient;
+use crate::chain::endpoint::ChainEndpoint;
+use crate::chain::requests::{Qualified, QueryHeight, QueryPacketEventDataRequest};
+use crate::config::ckb4ibc::ChainConfig;
+use crate::error::ErrorDetail;
+use crate::keyring::{KeyRing, Store};
+
+fn new_chain() -> Ckb4IbcChain {
+    let config = ChainConfig {
+        id: ChainId::new("ckb4ibc".to_owned(), 0),
+        counter_chain: ChainId::new("axon".to_owned(), 0),
+        ckb_rpc: "http://127.0.0.1:8114".parse().unwrap(),
+        ckb_indexer_rpc: "http://127.0.0.1:8116".parse().unwrap(),
+        key_name: "relayer".to_owned(),
+        client_type_args: h256!("0x1"),
+        connection_type_args: h256!("0x2"),
+        channel_type_args: h256!("0x3"),
+        packet_type_args: h256!("0x4"),
+    };
+    let rpc_client = RpcClient::new(&config.ckb_rpc, &config.ckb_indexer_rpc);
+    let keybase = KeyRing::new(Store::Memory, "ckb", &config.id).unwrap();
+    Ckb4IbcChain {
+        rt: Arc::new(Runtime::new().unwrap()),
+        rpc_client: Arc::new(rpc_client),
+        config,
+        keybase,
+        cached_network: RwLock::new(None),
+        tx_monitor_cmd: None,
+        client_outpoint: OutPoint::default(),
+        connection_outpoint: OutPoint::default(),
+        channel_outpoint: OutPoint::default(),
+        packet_outpoint: OutPoint::default(),
+        channel_input_data: RefCell::new(HashMap::new()),
+        channel_cache: RefCell::new(HashMap::new()),
+        // the connections are cached, so they aren't searched by the packet queries
+        connection_cache: RefCell::new(Some((IbcConnections::default(), CellInput::default()))),
+        packet_input_data: RefCell::new(HashMap::new()),
+        cached_tx_assembler_address: RwLock::new(None),
+    }
+}
+
+fn new_ibc_packet(sequence: u16, status: PacketStatus) -> IbcPacket {
+    IbcPacket {
+        packet: CkbPacket {
+            sequence,
+            source_port_id: port_id().to_string(),
+            source_channel_id: "channel-0".to_owned(),
+            destination_port_id: port_id().to_string(),
+            destination_channel_id: "channel-1".to_owned(),
+            data: vec![sequence as u8; 4],
+        },
+        tx_hash: None,
+        status,
+    }
+}
+
+// The port id is put into the args of the packet cells as 32 bytes.
+fn port_id() -> PortId {
+    PortId::from_str(&"b".repeat(32)).unwrap()
+}
+
+// Commits a transaction in the block `block_number` which creates the cell of `packet` on
+// `channel_id`, the packet is put in the witness which the `msg_type` message navigates to. The
+// cell of `consumed_tx_hash` is consumed by the transaction.
+fn commit_packet_tx(
+    chain: &Ckb4IbcChain,
+    channel_id: &ChannelId,
+    msg_type: MsgType,
+    packet: IbcPacket,
+    block_number: u64,
+    consumed_tx_hash: Option<&H256>,
+) -> H256 {
+    let sequence = Sequence::from(packet.packet.sequence as u64);
+    let envelope = Envelope {
+        msg_type,
+        content: vec![],
+    };
+    let witness = |output_type: packed::BytesOpt| {
+        WitnessArgs::new_builder()
+            .output_type(output_type)
+            .build()
+            .as_bytes()
+            .pack()
+    };
+    let witnesses = packed::BytesVec::new_builder()
+        .push(witness(Default::default()))
+        .push(witness(get_encoded_object(packet).witness))
+        .push(witness(get_encoded_object(envelope).witness))
+        .build();
+    let inputs = consumed_tx_hash
+        .map(|tx_hash| CellInput::new(OutPoint::new(tx_hash.pack(), 0), 0))
+        .into_iter()
+        .collect::<Vec<_>>();
+    let tx = packed::Transaction::new_builder()
+        .raw(
+            packed::RawTransaction::new_builder()
+                .inputs(inputs.pack())
+                .build(),
+        )
+        .witnesses(witnesses)
+        .build();
+    let tx_hash = chain.rpc_client.add_committed_transaction(tx.into());
+
+    let search_key = chain
+        .get_packet_search_key(channel_id, &port_id(), sequence)
+        .unwrap();
+    let indexed_tx = |io_type| {
+        Tx::Ungrouped(TxWithCell {
+            tx_hash: tx_hash.clone(),
+            block_number: block_number.into(),
+            tx_index: 0u32.into(),
+            io_index: 0u32.into(),
+            io_type,
+        })
+    };
+    if consumed_tx_hash.is_some() {
+        chain
+            .rpc_client
+            .add_indexed_transaction(&search_key, indexed_tx(CellType::Input));
+    }
+    chain
+        .rpc_client
+        .add_indexed_transaction(&search_key, indexed_tx(CellType::Output));
+    tx_hash
+}
+
+fn new_request(
+    event_id: WithBlockDataType,
+    sequences: &[u64],
+    height: Qualified<QueryHeight>,
+) -> QueryPacketEventDataRequest {
+    QueryPacketEventDataRequest {
+        event_id,
+        source_channel_id: ChannelId::new(0),
+        source_port_id: port_id(),
+        destination_channel_id: ChannelId::new(1),
+        destination_port_id: port_id(),
+        sequences: sequences.iter().copied().map(Sequence::from).collect(),
+        height,
+    }
+}
+
+#[test]
+fn test_query_packet_events() {
+    let chain = new_chain();
+    let (source_channel_id, destination_channel_id) = (ChannelId::new(0), ChannelId::new(1));
+    let latest = Qualified::SmallerEqual(QueryHeight::Latest);
+
+    let sent_tx_hashes = [1, 2].map(|sequence| {
+        let packet = new_ibc_packet(sequence, PacketStatus::Send);
+        let block_number = sequence as u64;
+        commit_packet_tx(
+            &chain,
+            &source_channel_id,
+            MsgType::MsgSendPacket,
+            packet,
+            block_number,
+            None,
+        )
+    });
+    // the cell of the sent packet 1 is consumed when it's acknowledged
+    commit_packet_tx(
+        &chain,
+        &source_channel_id,
+        MsgType::MsgAckPacket,
+        new_ibc_packet(1, PacketStatus::OutboxAck),
+        3,
+        Some(&sent_tx_hashes[0]),
+    );
+    let mut acked = new_ibc_packet(4, PacketStatus::InboxAck);
+    acked.tx_hash = Some(Default::default());
+    let acked_tx_hash = commit_packet_tx(
+        &chain,
+        &destination_channel_id,
+        MsgType::MsgRecvPacket,
+        acked,
+        4,
+        None,
+    );
+
+    // the packet 3 has never been sent, so it has no event
+    let events = chain
+        .query_packet_events(new_request(
+            WithBlockDataType::SendPacket,
+            &[1, 2, 3],
+            latest,
+        ))
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    for (event, (sequence, tx_hash)) in events.iter().zip([1, 2].into_iter().zip(&sent_tx_hashes)) {
+        assert_eq!(event.tx_hash, <[u8; 32]>::from(tx_hash.clone()));
+        assert_eq!(event.height, Height::new(1, sequence).unwrap());
+        let IbcEvent::SendPacket(send_packet) = &event.event else {
+            panic!("not a send packet event");
+        };
+        assert_eq!(u64::from(send_packet.packet.sequence), sequence);
+    }
+
+    let events = chain
+        .query_packet_events(new_request(WithBlockDataType::WriteAck, &[4], latest))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].tx_hash, <[u8; 32]>::from(acked_tx_hash));
+    assert_eq!(events[0].height, Height::new(1, 4).unwrap());
+    assert!(matches!(events[0].event, IbcEvent::WriteAcknowledgement(_)));
+
+    // the events of the blocks beyond the requested height are excluded
+    let at_block = |block_number| QueryHeight::Specific(Height::new(1, block_number).unwrap());
+    let query_sequences = |height| {
+        chain
+            .query_packet_events(new_request(WithBlockDataType::SendPacket, &[1, 2], height))
+            .unwrap()
+            .iter()
+            .map(|event| event.event.packet().unwrap().sequence.into())
+            .collect::<Vec<u64>>()
+    };
+    assert_eq!(query_sequences(Qualified::SmallerEqual(at_block(1))), [1]);
+    assert_eq!(
+        query_sequences(Qualified::SmallerEqual(at_block(2))),
+        [1, 2]
+    );
+    assert_eq!(query_sequences(Qualified::Equal(at_block(2))), [2]);
+    assert!(query_sequences(Qualified::Equal(at_block(3))).is_empty());
+
+    // the failures of the search aren't taken as no events
+    chain.rpc_client.fail_next_fetches(1);
+    let err = chain
+        .query_packet_events(new_request(WithBlockDataType::SendPacket, &[1, 2], latest))
+        .expect_err("failed search");
+    assert!(matches!(err.detail(), ErrorDetail::RpcResponse(_)));
+}
@@ -134,7 +134,7 @@ impl AnyClientState {
     pub fn refresh_period(&self) -> Option<Duration> {
         match self {
             AnyClientState::Tendermint(tm_state) => tm_state.refresh_time(),
-            AnyClientState::Eth(_) => None,
+            AnyClientState::Eth(eth_state) => eth_state.refresh_time(),
             AnyClientState::Ckb(_) => None,
             AnyClientState::Axon(_) => None,
 
@@ -142,6 +142,31 @@ impl AnyClientState {
             AnyClientState::Mock(mock_state) => mock_state.refresh_time(),
         }
     }
+
+    /// Compares only the fields which are relevant to the consensus of the counterparty, so two
+    /// client states differing only in the proof material of an Eth light client update, e.g.
+    /// the sync aggregate or the merkle branches, aren't considered as a changed client.
+    pub fn consensus_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Tendermint(state), Self::Tendermint(other)) => state == other,
+            (Self::Eth(state), Self::Eth(other)) => {
+                let (update, other_update) = (&state.lightclient_update, &other.lightclient_update);
+                state.chain_id == other.chain_id
+                    && update.attested_header == other_update.attested_header
+                    && update.finalized_header == other_update.finalized_header
+                    && update.next_sync_committee == other_update.next_sync_committee
+            }
+            (Self::Ckb(state), Self::Ckb(other)) => state.chain_id == other.chain_id,
+            (Self::Axon(state), Self::Axon(other)) => {
+                state.chain_id == other.chain_id && state.latest_height == other.latest_height
+            }
+
+            #[cfg(test)]
+            (Self::Mock(state), Self::Mock(other)) => state == other,
+
+            _ => false,
+        }
+    }
 }
 
 impl Protobuf<Any> for AnyClientState {}
@@ -418,8 +443,11 @@ impl From<IdentifiedAnyClientState> for IdentifiedClientState {
 #[cfg(test)]
 mod tests {
     use ibc_proto::google::protobuf::Any;
+    use ibc_relayer_types::clients::ics07_eth::client_state::ClientState as EthClientState;
     use ibc_relayer_types::clients::ics07_tendermint::client_state::test_util::get_dummy_tendermint_client_state;
     use ibc_relayer_types::clients::ics07_tendermint::header::test_util::get_dummy_tendermint_header;
+    use ibc_relayer_types::core::ics24_host::identifier::ChainId;
+    use std::time::Duration;
     use test_log::test;
 
     use super::AnyClientState;
@@ -433,4 +461,47 @@ mod tests {
         let tm_client_state_back = AnyClientState::try_from(raw).unwrap();
         assert_eq!(tm_client_state, tm_client_state_back);
     }
+
+    #[test]
+    fn eth_client_state_refresh_period() {
+        let eth_client_state = EthClientState {
+            chain_id: ChainId::new("eth".to_owned(), 0),
+            lightclient_update: Default::default(),
+        };
+        let refresh_period = AnyClientState::Eth(eth_client_state)
+            .refresh_period()
+            .expect("default refresh period");
+        // at least once per sync committee period
+        assert!(refresh_period < Duration::from_secs(12 * 8192));
+    }
+
+    #[test]
+    fn eth_client_state_consensus_eq() {
+        let eth_client_state = EthClientState {
+            chain_id: ChainId::new("eth".to_owned(), 0),
+            lightclient_update: Default::default(),
+        };
+        let client_state = AnyClientState::Eth(eth_client_state.clone());
+
+        // only the proof material of the update is changed
+        let mut reproved = eth_client_state.clone();
+        reproved
+            .lightclient_update
+            .finality_branch
+            .push(Default::default());
+        reproved.lightclient_update.signature_slot += 1;
+        let reproved = AnyClientState::Eth(reproved);
+        assert_ne!(client_state, reproved);
+        assert!(client_state.consensus_eq(&reproved));
+
+        let mut advanced = eth_client_state;
+        advanced.lightclient_update.finalized_header.slot += 1;
+        let advanced = AnyClientState::Eth(advanced);
+        assert!(!client_state.consensus_eq(&advanced));
+
+        let tm_client_state: AnyClientState =
+            get_dummy_tendermint_client_state(get_dummy_tendermint_header()).into();
+        assert!(tm_client_state.consensus_eq(&tm_client_state));
+        assert!(!client_state.consensus_eq(&tm_client_state));
+    }
 }
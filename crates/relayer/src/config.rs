@@ -299,6 +299,18 @@ impl ChainConfig {
             ChainConfig::Ckb4Ibc(_) => Duration::from_secs(90),
         }
     }
+
+    /// Returns the configured maximal duration between two updates of the clients of this chain,
+    /// which overrides the refresh period of their client states.
+    pub fn client_refresh_period(&self) -> Option<Duration> {
+        match self {
+            ChainConfig::Cosmos(_) => None,
+            ChainConfig::Eth(c) => c.client_refresh_period,
+            ChainConfig::Ckb(_) => None,
+            ChainConfig::Axon(_) => None,
+            ChainConfig::Ckb4Ibc(_) => None,
+        }
+    }
 }
 
 impl<'a> TryFrom<&'a ChainConfig> for &'a CosmosChainConfig {
@@ -685,6 +697,14 @@ impl Display for AddressType {
 pub fn load(path: impl AsRef<Path>) -> Result<Config, Error> {
     let config_toml = std::fs::read_to_string(&path).map_err(Error::io)?;
 
+    // a bad field of a chain only fails the untagged chain config as a whole
+    let raw_config = toml::from_str::<toml::Value>(&config_toml[..]).map_err(Error::decode)?;
+    if let Some(chains) = raw_config.get("chains").and_then(toml::Value::as_array) {
+        for chain in chains {
+            ckb::validate_hash_fields(chain)?;
+        }
+    }
+
     let config = toml::from_str::<Config>(&config_toml[..]).map_err(Error::decode)?;
 
     Ok(config)
@@ -714,8 +734,10 @@ pub(crate) fn store_writer(config: &Config, mut writer: impl Write) -> Result<()
 #[cfg(test)]
 mod tests {
     use core::str::FromStr;
+    use core::time::Duration;
 
-    use super::{load, parse_gas_prices, store_writer};
+    use super::{ckb, load, parse_gas_prices, store_writer, ChainConfig, EthChainConfig};
+    use crate::config::error::ErrorDetail;
     use crate::config::GasPrice;
     use test_log::test;
 
@@ -756,6 +778,68 @@ mod tests {
         store_writer(&config, &mut buffer).unwrap();
     }
 
+    #[test]
+    fn eth_client_refresh_period() {
+        let mut eth_config = EthChainConfig::goerli();
+        // the refresh period of the client state is used
+        assert_eq!(
+            ChainConfig::Eth(eth_config.clone()).client_refresh_period(),
+            None
+        );
+
+        eth_config.client_refresh_period = Some(Duration::from_secs(60));
+        assert_eq!(
+            ChainConfig::Eth(eth_config).client_refresh_period(),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn reject_ckb_hash_fields_of_bad_length() {
+        let hash = format!("0x{}", "11".repeat(32));
+        let chain = format!(
+            r#"
+            id = "ckb-0"
+            lightclient_contract_typeargs = "{hash}"
+            lightclient_lock_typeargs = "{hash}"
+            retired_lock_typeargs = ["{hash}"]
+            client_type_args = {{ type_id = "{hash}", cells_count = 3 }}
+            keyed_client_type_args = {{ other = {{ type_id = "{hash}", cells_count = 3 }} }}
+            "#
+        );
+        let valid: toml::Value = toml::from_str(&chain).unwrap();
+        ckb::validate_hash_fields(&valid).expect("valid hashes");
+
+        let short_hash = toml::Value::String("0x1234".to_owned());
+        let fields: [(&str, fn(&mut toml::Value) -> &mut toml::Value); 5] = [
+            ("lightclient_contract_typeargs", |chain| {
+                &mut chain["lightclient_contract_typeargs"]
+            }),
+            ("lightclient_lock_typeargs", |chain| {
+                &mut chain["lightclient_lock_typeargs"]
+            }),
+            ("retired_lock_typeargs[0]", |chain| {
+                &mut chain["retired_lock_typeargs"][0]
+            }),
+            ("client_type_args.type_id", |chain| {
+                &mut chain["client_type_args"]["type_id"]
+            }),
+            ("keyed_client_type_args.other.type_id", |chain| {
+                &mut chain["keyed_client_type_args"]["other"]["type_id"]
+            }),
+        ];
+        for (field, value_of) in fields {
+            let mut chain = valid.clone();
+            *value_of(&mut chain) = short_hash.clone();
+            let err = ckb::validate_hash_fields(&chain).expect_err(field);
+            let ErrorDetail::InvalidHash(detail) = err.detail() else {
+                panic!("unexpected error: {err}");
+            };
+            assert_eq!(detail.field, field);
+            assert_eq!(detail.chain, "ckb-0");
+        }
+    }
+
     #[test]
     fn gas_price_from_str() {
         let gp_original = GasPrice::new(10.0, "atom".to_owned());
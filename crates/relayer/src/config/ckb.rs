@@ -1,21 +1,214 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use ckb_jsonrpc_types::{CellDep, JsonBytes, ScriptHashType};
 use ckb_types::H256;
-use ibc_relayer_types::core::ics24_host::identifier::ChainId;
+use ibc_relayer_types::core::ics24_host::identifier::{ChainId, ClientId};
 use serde_derive::{Deserialize, Serialize};
 use tendermint_rpc::Url;
 
+use super::Error as ConfigError;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ChainConfig {
     pub id: ChainId,
     pub ckb_rpc: Url,
     pub ckb_indexer_rpc: Url,
+    // WebSocket endpoint of the CKB node to subscribe to its new tips, the tips are polled from
+    // `ckb_rpc` if it's absent or its subscription drops
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ckb_ws_rpc: Option<Url>,
     pub lightclient_contract_typeargs: H256,
     pub lightclient_lock_typeargs: H256,
+    // Lock type args retired by a lock-script rotation, the cells under them are still spent
+    // during the migration, while the new outputs are always locked by the one above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retired_lock_typeargs: Vec<H256>,
     pub client_type_args: ClientTypeArgs,
+    // Base64 of a trusted packed client, the default multi-client is only created when the
+    // header updates start from it and the headers cover its slots with the same roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_client: Option<String>,
+    // Additional multi-client deployments on the same chain, selected by a client key, which
+    // is also the directory name of its native storage, so it's made of `[A-Za-z0-9_-]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keyed_client_type_args: BTreeMap<String, ClientTypeArgs>,
+    // Upper bound of the `cells_count` above, each client cell is built and put into a single
+    // transaction when creating a multi-client, unless `create_chunk_size` is set
+    #[serde(default = "default::max_cells_count")]
+    pub max_cells_count: u8,
+    // Number of the client cells created in each transaction, each one is committed before the
+    // next, and the info cell is created after all of them, 0 to create all cells at once
+    #[serde(default)]
+    pub create_chunk_size: u8,
     pub minimal_updates_count: u8,
+    // Create a multi-client with the `minimal_updates_count` of an existing deployment, the
+    // default one and then the keyed ones in order, the one above is used if none is found
+    #[serde(default)]
+    pub inherit_minimal_updates_count: bool,
+    // Genesis validators roots of the accepted beacon chains, which are checked against the
+    // `beacon_api_urls` nodes, empty to accept any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted_genesis_validators_root: Vec<H256>,
+    // Verify the sync aggregates and the proofs of the signed beacon updates before assembling
+    // a transaction, so an update which the contract rejects isn't sent
+    #[serde(default)]
+    pub verify_updates_locally: bool,
     pub key_name: String,
+    // Name of the environment variable which holds the hex encoded private key, the key is
+    // loaded into an in-memory keybase as `key_name` instead of reading the on-disk keystore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_from_env: Option<String>,
+    // Path of a PKCS#8 file in PEM or DER which holds the secp256k1 private key, it's loaded as
+    // `key_from_env` is, and they can't be both set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<PathBuf>,
+    // Name of the environment variable which holds the passphrase of the exported keys, the
+    // keys can't be exported or imported if it's not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase_env: Option<String>,
+    // Name of the key which funds the fees and receives the changes, while the light-client
+    // cells are still authorized by `key_name`, which is also the fee payer if it's not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_payer_key: Option<String>,
+    // Hex of the bytes appended to the pubkey hash in the args of the lock of the tx-assembler
+    // cells, it requires `suffixed_lock`, since the sighash lock rejects any args other than the
+    // 20-byte pubkey hash
+    #[serde(default, skip_serializing_if = "JsonBytes::is_empty")]
+    pub lock_args_suffix: JsonBytes,
+    // Lock of the tx-assembler cells instead of the sighash lock, see `SuffixedLock`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffixed_lock: Option<SuffixedLock>,
     pub data_dir: PathBuf,
+    // Compress the beacon headers in the native storage, it could be switched at any time
+    #[serde(default)]
+    pub compress_storage: bool,
+    // Maximum number of in-flight requests to the CKB node and indexer
+    #[serde(default = "default::max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    // Timeout of each request to the CKB node and indexer
+    #[serde(default = "default::rpc_timeout", with = "humantime_serde")]
+    pub rpc_timeout: Duration,
+    // Timeouts of the specific JSON-RPC methods, e.g. `send_transaction` or `get_cells`, which
+    // override the one above
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        with = "duration_map"
+    )]
+    pub rpc_method_timeouts: BTreeMap<String, Duration>,
+    // Verify the signatures of a signed transaction before sending it
+    #[serde(default = "default::verify_signatures")]
+    pub verify_signatures: bool,
+    // Minimal capacity of a change cell, the change is merged into the fee if it's less than
+    // this and no more capacity is found, 0 for the occupied capacity of the change cell
+    #[serde(default)]
+    pub min_change_capacity: u64,
+    // Fee rate in shannons per byte, it's used when the node suggests a lower one
+    #[serde(default = "default::fee_rate")]
+    pub fee_rate: u64,
+    // Floor of the fee rate in shannons per byte, it's applied to both the suggested and the
+    // configured fee rates
+    #[serde(default)]
+    pub min_fee_rate: u64,
+    // Maximal size in bytes of a transaction, which is checked before broadcasting
+    #[serde(default = "default::max_tx_size")]
+    pub max_tx_size: u64,
+    // Retries of a failed update cycle, each one fetches the cells again after the native
+    // storage is rolled back, 0 to give up at the first failure
+    #[serde(default)]
+    pub max_cycle_retries: u32,
+    // Retries shared by the calls in an update cycle, i.e. fetching the cells, assembling and
+    // sending the transaction, each retry of any call takes one, 0 to not retry the calls
+    #[serde(default)]
+    pub cycle_retry_budget: u32,
+    // Limits of the headers in an update cycle, the rest of them are left to the next cycles,
+    // see `UpdateBudget`
+    #[serde(default)]
+    pub update_budget: UpdateBudget,
+    // Hard limit of waiting a sent transaction to be committed, the update fails after it
+    #[serde(default = "default::tx_commit_timeout", with = "humantime_serde")]
+    pub tx_commit_timeout: Duration,
+    // Blocks on top of the one which a sent transaction is committed in, before the headers
+    // in the native storage are kept, the storage is rolled back if the transaction is reorged
+    // out before then, the wait is also limited by the timeout above
+    #[serde(default)]
+    pub tx_confirmations: u8,
+    // Grace period after sending, a transaction which isn't committed after it is treated as
+    // stuck, e.g. to bump its fee, it takes no effect if it's not less than the timeout above
+    #[serde(default = "default::stuck_after", with = "humantime_serde")]
+    pub stuck_after: Duration,
+    // Maximal difference between the relayer's clock and the timestamp of the CKB tip block at
+    // startup, a warning is logged if it's exceeded
+    #[serde(default = "default::max_clock_skew", with = "humantime_serde")]
+    pub max_clock_skew: Duration,
+    // URL which the committed `{tx_hash, maximal_slot, block_number}` of each update is posted
+    // to, a failed notification doesn't fail the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_webhook_url: Option<Url>,
+    // Beacon API nodes to fetch the updates from, empty to only receive the updates from the
+    // Ethereum chain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub beacon_api_urls: Vec<String>,
+    // Slots below the finalized slot reported by the beacon API nodes, the updates are only
+    // fetched up to it, in case a header around the finality is reorged
+    #[serde(default)]
+    pub finality_margin_slots: u64,
+    // Address to serve the relay status as JSON at `GET /status`, it requires the
+    // `ckb-status-server` feature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_server_addr: Option<SocketAddr>,
+    // Directory which the signed update transactions are written into instead of being sent,
+    // for an offline broadcast by `CkbChain::broadcast_signed_tx`, the headers are kept in the
+    // native storage as if they are sent, while the creation is always sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_signed_tx_dir: Option<PathBuf>,
+    // Capacity of the channel of each subscriber, in event batches
+    #[serde(default = "default::subscribe_capacity")]
+    pub subscribe_capacity: usize,
+    // What to do when the channel of a subscriber is full, see `SubscribeOverflow`
+    #[serde(default)]
+    pub subscribe_overflow: SubscribeOverflow,
+}
+
+/// Policy of a full subscriber channel, so a slow subscriber doesn't grow the memory unboundedly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubscribeOverflow {
+    /// Drop the oldest event batch in the channel to make room for the new one, with a warning.
+    #[default]
+    DropOldest,
+    /// Block the chain until the subscriber has received a batch, so no batch is lost.
+    Block,
+}
+
+/// A lock which verifies the secp256k1 signature of the pubkey hash in the first 20 bytes of its
+/// args as the sighash lock does, while the rest of its args are `lock_args_suffix`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SuffixedLock {
+    pub code_hash: H256,
+    pub hash_type: ScriptHashType,
+    // Cell dep of the lock, which is added to the transactions spending the cells under it
+    pub cell_dep: CellDep,
+}
+
+/// Limits of an update cycle, so a huge catch-up is split into the cycles which are bounded in
+/// memory and time instead of an unbounded one. A zero limit means no limit, and a cycle always
+/// takes the minimal updates count of headers at least.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UpdateBudget {
+    /// Maximal number of headers.
+    #[serde(default)]
+    pub max_headers: u64,
+    /// Maximal total size in bytes of the headers, as they're serialized in JSON.
+    #[serde(default)]
+    pub max_bytes: u64,
+    /// Maximal duration, which is estimated by the recent update cycles, so it takes no effect
+    /// until a cycle is done.
+    #[serde(default, with = "humantime_serde")]
+    pub max_duration: Duration,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -24,4 +217,219 @@ pub struct ClientTypeArgs {
     pub type_id: Option<H256>,
     // Number of client cells, plus one info cell
     pub cells_count: u8,
+    // Client id of a keyed multi-client which is reported by the queries, it's required by the
+    // keyed ones and kept across the changes of the keys, the default one is `ClientId::default`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<ClientId>,
+}
+
+impl ChainConfig {
+    /// Returns the client type args selected by `client_key`, `None` selects the default one.
+    pub fn select_client_type_args(&self, client_key: Option<&str>) -> Option<&ClientTypeArgs> {
+        match client_key {
+            Some(key) => self.keyed_client_type_args.get(key),
+            None => Some(&self.client_type_args),
+        }
+    }
+
+    /// Checks the options which can't be expressed by their types.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.key_from_env.is_some() && self.key_file.is_some() {
+            return Err(ConfigError::conflicting_key_sources(self.key_name.clone()));
+        }
+        if !self.lock_args_suffix.is_empty() && self.suffixed_lock.is_none() {
+            return Err(ConfigError::suffixed_lock_required(self.id.to_string()));
+        }
+        if !self.accepted_genesis_validators_root.is_empty() && self.beacon_api_urls.is_empty() {
+            return Err(ConfigError::beacon_api_required(
+                self.id.to_string(),
+                "accepted_genesis_validators_root".to_owned(),
+            ));
+        }
+        let default_client = (String::from("default"), &self.client_type_args);
+        let keyed_clients = self
+            .keyed_client_type_args
+            .iter()
+            .map(|(key, args)| (format!("`{key}`"), args));
+        let mut client_ids = vec![ClientId::default()];
+        for (key, args) in &self.keyed_client_type_args {
+            let invalid = |reason: &str| {
+                ConfigError::invalid_keyed_client(
+                    self.id.to_string(),
+                    key.clone(),
+                    reason.to_owned(),
+                )
+            };
+            let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+            if key.is_empty() || !key.chars().all(is_valid_char) {
+                return Err(invalid("the key isn't made of [A-Za-z0-9_-]"));
+            }
+            let Some(client_id) = args.client_id.as_ref() else {
+                return Err(invalid("no client_id"));
+            };
+            if client_ids.contains(client_id) {
+                return Err(invalid("the client_id is taken"));
+            }
+            client_ids.push(client_id.clone());
+        }
+        for (client, args) in std::iter::once(default_client).chain(keyed_clients) {
+            if args.cells_count > self.max_cells_count {
+                return Err(ConfigError::cells_count_exceeded(
+                    client,
+                    args.cells_count,
+                    self.max_cells_count,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn select_client_type_args_mut(
+        &mut self,
+        client_key: Option<&str>,
+    ) -> Option<&mut ClientTypeArgs> {
+        match client_key {
+            Some(key) => self.keyed_client_type_args.get_mut(key),
+            None => Some(&mut self.client_type_args),
+        }
+    }
+}
+
+/// Checks that the hash fields of a CKB chain in the raw config are hex of 32 bytes, since a
+/// bad one only fails the untagged chain config as a whole, which doesn't tell the field.
+///
+/// The chains of the other types, which have no `lightclient_contract_typeargs`, are skipped.
+pub fn validate_hash_fields(chain: &toml::Value) -> Result<(), ConfigError> {
+    let Some(table) = chain.as_table() else {
+        return Ok(());
+    };
+    if !table.contains_key("lightclient_contract_typeargs") {
+        return Ok(());
+    }
+    let chain_id = table
+        .get("id")
+        .and_then(toml::Value::as_str)
+        .unwrap_or("unknown");
+
+    let mut fields = vec![];
+    for field in ["lightclient_contract_typeargs", "lightclient_lock_typeargs"] {
+        fields.push((field.to_owned(), table.get(field)));
+    }
+    if let Some(values) = table
+        .get("retired_lock_typeargs")
+        .and_then(toml::Value::as_array)
+    {
+        for (index, value) in values.iter().enumerate() {
+            fields.push((format!("retired_lock_typeargs[{index}]"), Some(value)));
+        }
+    }
+    let type_id_of = |args: &toml::Value| args.get("type_id");
+    if let Some(args) = table.get("client_type_args") {
+        fields.push(("client_type_args.type_id".to_owned(), type_id_of(args)));
+    }
+    if let Some(keyed) = table
+        .get("keyed_client_type_args")
+        .and_then(toml::Value::as_table)
+    {
+        for (key, args) in keyed {
+            fields.push((
+                format!("keyed_client_type_args.{key}.type_id"),
+                type_id_of(args),
+            ));
+        }
+    }
+
+    for (field, value) in fields {
+        // the missing ones are reported by the deserialization, if they're required
+        let Some(value) = value else {
+            continue;
+        };
+        let invalid_hash =
+            |reason: String| ConfigError::invalid_hash(chain_id.to_owned(), field.clone(), reason);
+        let Some(hex_str) = value.as_str() else {
+            return Err(invalid_hash("not a string".to_owned()));
+        };
+        let Some(digits) = hex_str.strip_prefix("0x") else {
+            return Err(invalid_hash("no 0x prefix".to_owned()));
+        };
+        let bytes = hex::decode(digits).map_err(|err| invalid_hash(format!("{err}")))?;
+        if bytes.len() != 32 {
+            return Err(invalid_hash(format!("{} bytes", bytes.len())));
+        }
+    }
+    Ok(())
+}
+
+// (De)serializes the durations of a map in the human-readable format, like a single one.
+mod duration_map {
+    use humantime_serde::Serde;
+    use serde::{Deserialize as _, Deserializer, Serialize as _, Serializer};
+
+    use super::{BTreeMap, Duration};
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<String, Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        map.iter()
+            .map(|(key, duration)| (key, Serde::from(*duration)))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, Duration>, D::Error> {
+        let map = BTreeMap::<String, Serde<Duration>>::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(key, duration)| (key, duration.into_inner()))
+            .collect())
+    }
+}
+
+/// Defaults for various fields
+pub mod default {
+    use super::Duration;
+
+    pub fn max_cells_count() -> u8 {
+        64
+    }
+
+    pub fn max_concurrent_requests() -> usize {
+        16
+    }
+
+    pub fn rpc_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn verify_signatures() -> bool {
+        true
+    }
+
+    pub fn fee_rate() -> u64 {
+        3000
+    }
+
+    /// The maximal block bytes of the CKB consensus.
+    pub fn max_tx_size() -> u64 {
+        597_000
+    }
+
+    pub fn tx_commit_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    pub fn stuck_after() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    pub fn max_clock_skew() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    pub fn subscribe_capacity() -> usize {
+        256
+    }
 }
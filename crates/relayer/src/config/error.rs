@@ -17,5 +17,41 @@ define_error! {
         InvalidGasPrice
             { price: String }
             |e| { format!("invalid gas price: {}", e.price) },
+
+        ConflictingKeySources
+            { key_name: String }
+            |e| {
+                format!("both key_from_env and key_file are set for the signing key `{}`",
+                    e.key_name)
+            },
+
+        InvalidHash
+            { chain: String, field: String, reason: String }
+            |e| {
+                format!("invalid `{}` of chain {}: {}, expect a 0x-prefixed hex of 32 bytes",
+                    e.field, e.chain, e.reason)
+            },
+
+        SuffixedLockRequired
+            { chain: String }
+            |e| {
+                format!("`lock_args_suffix` of chain {} requires `suffixed_lock`, the sighash lock only accepts the pubkey hash as its args",
+                    e.chain)
+            },
+
+        BeaconApiRequired
+            { chain: String, field: String }
+            |e| { format!("`{}` of chain {} requires `beacon_api_urls`", e.field, e.chain) },
+
+        InvalidKeyedClient
+            { chain: String, key: String, reason: String }
+            |e| { format!("invalid keyed client `{}` of chain {}: {}", e.key, e.chain, e.reason) },
+
+        CellsCountExceeded
+            { client: String, cells_count: u8, max_cells_count: u8 }
+            |e| {
+                format!("cells_count {} of the {} client exceeds max_cells_count {}",
+                    e.cells_count, e.client, e.max_cells_count)
+            },
     }
 }
@@ -3,6 +3,7 @@ use ibc_relayer_types::{
     core::ics24_host::identifier::ChainId,
 };
 use serde_derive::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EthChainConfig {
@@ -15,6 +16,10 @@ pub struct EthChainConfig {
     pub rpc_addr_pool: Vec<String>,
     pub rpc_port: u16,
     pub forks: Forks,
+    // Maximal duration between two updates of the Ethereum client on the counterparty chain,
+    // it overrides the refresh period of the client state, i.e. 2/3 of a sync committee period
+    #[serde(default, with = "humantime_serde")]
+    pub client_refresh_period: Option<Duration>,
 }
 
 pub fn array_hex_deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
@@ -83,6 +88,7 @@ impl EthChainConfig {
             },
             initial_checkpoint: Default::default(),
             key_name: Default::default(),
+            client_refresh_period: None,
         }
     }
 }
@@ -39,6 +39,7 @@ use ibc_relayer_types::{
     relayer::ics18_relayer::error as relayer_error,
 };
 
+use crate::chain::ckb::utils::SendTxErrorKind;
 use crate::chain::cosmos::version;
 use crate::chain::cosmos::BLOCK_MAX_BYTES_MAX_FRACTION;
 use crate::config::Error as ConfigError;
@@ -284,6 +285,11 @@ define_error! {
             [ KeyringError ]
             |e| { format!("signature key not found: {}", e.key_name) },
 
+        KeyDecode
+            { key_name: String }
+            [ KeyringError ]
+            |e| { format!("signature key is corrupt: {}", e.key_name) },
+
         Ics02
             [ client_error::Error ]
             |e| { format!("ICS 02 error: {}", e.source) },
@@ -635,6 +641,213 @@ define_error! {
 
         EmptyConnectionHops
         |_| {"empty connection hops"},
+
+        CkbUnknownClientKey
+            { key: String }
+            |e| { format_args!("no client type args configured for client key `{}`", e.key) },
+
+        GenesisMismatch
+            { genesis_validators_root: String }
+            |e| {
+                format_args!("genesis validators root {} of the beacon API nodes is not accepted",
+                    e.genesis_validators_root)
+            },
+
+        BeaconHeaderMismatch
+            { slot: u64 }
+            |e| {
+                format_args!("the beacon header of the update at slot {} is not the one of the beacon API nodes",
+                    e.slot)
+            },
+
+        Unsupported
+            { feature: String }
+            |e| { format_args!("unsupported by the chain: {}", e.feature) },
+
+        CkbSignatureVerification
+            { input_index: usize, reason: String }
+            |e| {
+                format_args!("signature verification failed for input {}: {}",
+                    e.input_index, e.reason)
+            },
+
+        ClientIdSetCorrupted
+            { cells_count: u8, client_ids: Vec<u8> }
+            |e| {
+                format_args!("on-chain client ids {:?} are not exactly 0..{} of the multi-client",
+                    e.client_ids, e.cells_count.saturating_sub(1))
+            },
+
+        CkbKeyFromEnv
+            { env_var: String, reason: String }
+            |e| {
+                format_args!("failed to load the signing key from environment variable `{}`: {}",
+                    e.env_var, e.reason)
+            },
+
+        AlreadyRunning
+            { lock_file: String }
+            |e| {
+                format_args!("another relayer instance holds the lock of file {}",
+                    e.lock_file)
+            },
+
+        CurrentThreadRuntime
+            |_| { "cannot block on the ckb chain from a current-thread tokio runtime, call it from a multi-thread runtime or outside of any runtime" },
+
+        TxTooLarge
+            { size: u64, limit: u64 }
+            |e| {
+                format_args!("transaction size {} exceeds the limit {}, try to send fewer updates in one transaction",
+                    e.size, e.limit)
+            },
+
+        CkbSendTx
+            {
+                kind: SendTxErrorKind,
+                detail: String,
+            }
+            |e| { format_args!("ckb send_transaction failed ({}): {}", e.kind, e.detail) },
+
+        CycleCancelled
+            |_| { "the update cycle is cancelled, the native storage is rolled back" },
+
+        InsufficientCapacity
+            { have: u64, need: u64 }
+            |e| {
+                format_args!("inputs capacity {} shannons doesn't cover outputs and fee {} shannons",
+                    e.have, e.need)
+            },
+
+        ProofUpdateVerification
+            { slot: Option<u64>, reason: String }
+            |e| {
+                match e.slot {
+                    Some(slot) => format!("failed to verify the beacon header at slot {}: {}",
+                        slot, e.reason),
+                    None => format!("failed to verify the proof update: {}", e.reason),
+                }
+            },
+
+        ConflictingUpdates
+            { slot: u64 }
+            |e| { format_args!("conflicting beacon updates of the same slot {}", e.slot) },
+
+        ContractCelldepMissing
+            { typeid_args: String }
+            |e| {
+                format_args!("contract cell of type id args {} is missing, it may be consumed or moved",
+                    e.typeid_args)
+            },
+
+        StorageOpen
+            { path: String }
+            [ TraceError<StorageError> ]
+            |e| { format_args!("failed to open the native storage at {}", e.path) },
+
+        NativeBehindOnchain
+            { native_tip: u64, onchain_tip: u64 }
+            |e| {
+                format_args!("native storage tip slot {} is behind the on-chain client tip slot {}, resync the headers since slot {}",
+                    e.native_tip, e.onchain_tip, e.native_tip + 1)
+            },
+
+        CkbKeyBackup
+            { key_name: String, reason: String }
+            |e| {
+                format_args!("failed to export or import the encrypted key `{}`: {}",
+                    e.key_name, e.reason)
+            },
+
+        GenesisClientMismatch
+            { reason: String }
+            |e| {
+                format_args!("the header updates don't extend the genesis client: {}", e.reason)
+            },
+
+        CkbKeyFile
+            { path: String, reason: String }
+            |e| {
+                format_args!("failed to load the signing key from file `{}`: {}",
+                    e.path, e.reason)
+            },
+
+        CkbClientCellsGone
+            { type_id: String }
+            |e| {
+                format_args!("no multi-client cells of type id {} are found, the type id may be stale after a contract redeploy, clear `type_id` of the client type args to create a new multi-client",
+                    e.type_id)
+            },
+
+        CkbTxReorged
+            { tx_hash: String, block_number: u64 }
+            |e| {
+                format_args!("transaction {} was committed in block {} but reorged out before it's confirmed",
+                    e.tx_hash, e.block_number)
+            },
+
+        CkbExportedTxPending
+            { path: String }
+            |e| {
+                format_args!("the exported transaction {} isn't broadcast yet, broadcast it or remove the file before another update",
+                    e.path)
+            },
+
+        CkbAssembledClientMismatch
+            { reason: String }
+            |e| {
+                format_args!("the client in the assembled transaction doesn't match the computed one: {}",
+                    e.reason)
+            },
+
+        MissingSyncCommittee
+            { period: u64 }
+            |e| {
+                format_args!("the header updates cross into the sync-committee period {} without the committee of it",
+                    e.period)
+            },
+
+        CellsCountMismatch
+            { config: u8, onchain: usize }
+            |e| {
+                format_args!("expect {} client cells by the configured `cells_count` but found {} on chain",
+                    e.config, e.onchain)
+            },
+
+        CkbConsensusStateNotFound
+            { client_id: String, slot: u64 }
+            |e| {
+                format_args!("no consensus state of client {} at slot {}, it's out of the range of the client",
+                    e.client_id, e.slot)
+            },
+
+        InvalidClientSlotRange
+            { min: u64, max: u64 }
+            |e| {
+                format_args!("the client has an inverted slot range, its minimal slot {} is above its maximal slot {}",
+                    e.min, e.max)
+            },
+
+        UpdateRejectedLocally
+            { slot: u64, reason: String }
+            |e| {
+                format_args!("the beacon update of slot {} is rejected by the local verification: {}",
+                    e.slot, e.reason)
+            },
+
+        LastIdInconsistent
+            { last_id: u8, newest_id: u8, newest_slot: u64 }
+            |e| {
+                format_args!("the client info points at client {} as the last updated, but the newest client is {} of maximal slot {}, the update history is corrupted",
+                    e.last_id, e.newest_id, e.newest_slot)
+            },
+
+        BrokenHeaderChain
+            { expected_parent: String, found_parent: String }
+            |e| {
+                format_args!("the header updates don't chain to the stored tip, expect parent root {} but found {}",
+                    e.expected_parent, e.found_parent)
+            },
     }
 }
 
@@ -849,8 +849,18 @@ impl<DstChain: ChainHandle, SrcChain: ChainHandle> ForeignClient<DstChain, SrcCh
         let (client_state, elapsed) = self.validated_client_state()?;
 
         // The refresh_window is the maximum duration
-        // we can backoff between subsequent client updates.
-        let refresh_window = client_state.refresh_period();
+        // we can backoff between subsequent client updates,
+        // unless it's overridden by the source chain configuration.
+        let src_config = self.src_chain.config().map_err(|e| {
+            ForeignClientError::client_refresh(
+                self.id().clone(),
+                "failed while querying the source chain for configuration".to_string(),
+                e,
+            )
+        })?;
+        let refresh_window = src_config
+            .client_refresh_period()
+            .or_else(|| client_state.refresh_period());
 
         match (elapsed, refresh_window) {
             (None, _) | (_, None) => Ok(None),
@@ -243,6 +243,18 @@ impl Secp256k1KeyPair {
         })
     }
 
+    pub fn from_ckb_private_key(private_key: SecretKey, network: NetworkType) -> Self {
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &private_key);
+        let payload = AddressPayload::from_pubkey(&public_key);
+        Self {
+            private_key,
+            public_key,
+            address: get_address(&public_key, Secp256k1AddressType::Ckb),
+            address_type: Secp256k1AddressType::Ckb,
+            account: payload.display_with_network(network, false),
+        }
+    }
+
     pub fn into_ckb_keypair(self, network: NetworkType) -> Self {
         if let Secp256k1AddressType::Ckb = self.address_type {
             return self;
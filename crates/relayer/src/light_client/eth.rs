@@ -40,13 +40,13 @@ use crate::{
 
 use super::Verified;
 
-use self::utils::calc_sync_period;
+pub(crate) use self::utils::calc_sync_period;
 use self::utils::compute_domain;
 use self::utils::compute_signing_root;
 use self::utils::is_aggregate_valid;
 use self::utils::is_current_committee_proof_valid;
-use self::utils::is_finality_proof_valid;
-use self::utils::is_next_committee_proof_valid;
+pub(crate) use self::utils::is_finality_proof_valid;
+pub(crate) use self::utils::is_next_committee_proof_valid;
 
 pub const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u8 = 128;
 pub const MAX_CACHED_UPDATES: usize = 32 * 1024;
@@ -532,6 +532,7 @@ pub trait ConsensusRpc {
     async fn get_updates(&self, period: u64, count: u8) -> Result<Vec<Update>>;
     async fn get_finality_update(&self) -> Result<FinalityUpdate>;
     async fn get_header(&self, slot: u64) -> Result<Option<Header>>;
+    async fn get_genesis_validators_root(&self) -> Result<H256>;
 }
 
 #[derive(Default)]
@@ -660,6 +661,19 @@ impl ConsensusRpc for NimbusRpc {
             }
         }
     }
+
+    async fn get_genesis_validators_root(&self) -> Result<H256> {
+        let req = format!("{}/eth/v1/beacon/genesis", self.rpc[0]);
+        let res = self
+            .client
+            .get(req)
+            .send()
+            .await?
+            .json::<GenesisResponse>()
+            .await?;
+
+        Ok(res.data.genesis_validators_root)
+    }
 }
 
 pub struct LightClient {
@@ -831,6 +845,16 @@ struct FinalityUpdateResponse {
     data: FinalityUpdate,
 }
 
+#[derive(serde::Deserialize, Debug)]
+struct GenesisResponse {
+    data: GenesisData,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct GenesisData {
+    genesis_validators_root: H256,
+}
+
 type UpdateResponse = Vec<UpdateData>;
 
 #[derive(serde::Deserialize, Debug)]
@@ -882,7 +906,7 @@ mod tests {
 
     use super::{
         Bootstrap, ConsensusClient, ConsensusRpc, FinalityUpdate, HeaderResponse, NimbusRpc,
-        Result, Update,
+        Result, Update, H256,
     };
     use crate::config::eth::EthChainConfig;
     use crate::light_client::eth::utils::calc_sync_period;
@@ -925,6 +949,10 @@ mod tests {
             let response: Vec<HeaderResponse::Response> = serde_json::from_str(&header)?;
             Ok(response[slot as usize].clone().header())
         }
+
+        async fn get_genesis_validators_root(&self) -> Result<H256> {
+            Ok(EthChainConfig::goerli().genesis_root)
+        }
     }
 
     async fn get_client() -> ConsensusClient<MockRpc> {
@@ -938,6 +966,7 @@ mod tests {
             rpc_port: Default::default(),
             initial_checkpoint: Default::default(),
             key_name: Default::default(),
+            client_refresh_period: None,
         };
         let checkpoint =
             hex::decode("1e591af1e90f2db918b2a132991c7c2ee9a4ab26da496bd6e71e4f0bd65ea870")
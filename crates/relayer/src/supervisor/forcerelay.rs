@@ -9,7 +9,10 @@ use crate::chain::requests::{PageRequest, QueryClientStatesRequest};
 use crate::chain::tracking::{NonCosmosTrackingId, TrackedMsgs, TrackingId};
 use crate::client_state::IdentifiedAnyClientState;
 use crate::config::ChainConfig;
-use crate::error::{Error, ErrorDetail::LightClientVerification};
+use crate::error::{
+    Error,
+    ErrorDetail::{LightClientVerification, NativeBehindOnchain},
+};
 use crate::event::monitor::EventBatch;
 use tendermint_light_client::errors::ErrorDetail;
 
@@ -213,6 +216,10 @@ fn send_messages<Chain: ChainHandle>(
 }
 
 fn extract_missing_slot_from_error(error: &Error) -> Option<u64> {
+    // the native storage of CKB lost the headers since the slot, resync them
+    if let NativeBehindOnchain(detail) = error.detail() {
+        return Some(detail.native_tip + 1);
+    }
     if let LightClientVerification(verify_error) = error.detail() {
         match &verify_error.source {
             ErrorDetail::MissingLastBlockId(detail) => return Some(detail.height.into()),
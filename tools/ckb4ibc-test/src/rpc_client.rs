@@ -4,7 +4,7 @@ use ckb_jsonrpc_types::{
     BlockNumber, BlockView, CellWithStatus, ChainInfo, HeaderView, JsonBytes, OutPoint,
     OutputsValidator, RawTxPool, Transaction, TransactionWithStatusResponse, TxPoolInfo, Uint32,
 };
-use ckb_sdk::rpc::ckb_indexer::{Cell, Order, Pagination, SearchKey};
+use ckb_sdk::rpc::ckb_indexer::{Cell, Order, Pagination, SearchKey, Tip, Tx};
 use ckb_types::H256;
 use futures::FutureExt;
 use reqwest::Client;
@@ -12,7 +12,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tendermint_rpc::{Error as TmError, Url};
 
-use relayer::chain::ckb::prelude::{CkbReader, CkbWriter, Response as Rpc};
+use relayer::chain::ckb::prelude::{CkbReader, CkbWriter, FeeRateStatistics, Response as Rpc};
 use relayer::error::Error;
 
 #[allow(clippy::upper_case_acronyms)]
@@ -163,6 +163,42 @@ impl CkbReader for RpcClient {
         .boxed()
     }
 
+    fn get_transactions(
+        &self,
+        search_key: SearchKey,
+        limit: u32,
+        cursor: Option<JsonBytes>,
+    ) -> Rpc<Pagination<Tx>> {
+        let order = Order::Asc;
+        let limit = Uint32::from(limit);
+
+        jsonrpc!(
+            "get_transactions",
+            Target::Indexer,
+            self,
+            Pagination<Tx>,
+            search_key,
+            order,
+            limit,
+            cursor,
+        )
+        .boxed()
+    }
+
+    fn get_indexer_tip(&self) -> Rpc<Option<Tip>> {
+        jsonrpc!("get_indexer_tip", Target::Indexer, self, Option<Tip>).boxed()
+    }
+
+    fn get_fee_rate_statistics(&self) -> Rpc<Option<FeeRateStatistics>> {
+        jsonrpc!(
+            "get_fee_rate_statistics",
+            Target::CKB,
+            self,
+            Option<FeeRateStatistics>
+        )
+        .boxed()
+    }
+
     fn get_raw_tx_pool(&self, verbose: bool) -> Rpc<RawTxPool> {
         jsonrpc!("get_raw_tx_pool", Target::CKB, self, RawTxPool, verbose).boxed()
     }